@@ -0,0 +1,546 @@
+//! Generates `protocol::cdp` from the DevTools protocol JSON in `json/`, when the "cdp" feature
+//! is on.
+//!
+//! Every domain becomes a module holding its types, with `methods` and `events` submodules
+//! laid out like the hand-written ones in `src/protocol`. Each command gets a params struct
+//! implementing `protocol::Method` and a `...ReturnObject`, each event a `...Event` struct
+//! with its `params`, and all the events are gathered into `protocol::cdp::Event`, tagged by
+//! "method" like `protocol::Event`.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+const PROTOCOL_FILES: [&str; 2] = ["json/browser_protocol.json", "json/js_protocol.json"];
+
+#[derive(Deserialize)]
+struct Protocol {
+    domains: Vec<Domain>,
+}
+
+#[derive(Deserialize)]
+struct Domain {
+    domain: String,
+    description: Option<String>,
+    #[serde(default)]
+    experimental: bool,
+    #[serde(default)]
+    deprecated: bool,
+    #[serde(default)]
+    types: Vec<TypeDef>,
+    #[serde(default)]
+    commands: Vec<Command>,
+    #[serde(default)]
+    events: Vec<Command>,
+}
+
+/// The shape of a type, property or array item.
+#[derive(Deserialize)]
+struct Schema {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(rename = "$ref")]
+    reference: Option<String>,
+    #[serde(rename = "enum")]
+    variants: Option<Vec<String>>,
+    properties: Option<Vec<Property>>,
+    items: Option<Box<Schema>>,
+}
+
+#[derive(Deserialize)]
+struct TypeDef {
+    id: String,
+    description: Option<String>,
+    #[serde(default)]
+    experimental: bool,
+    #[serde(default)]
+    deprecated: bool,
+    #[serde(flatten)]
+    schema: Schema,
+}
+
+#[derive(Deserialize)]
+struct Property {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    optional: bool,
+    #[serde(default)]
+    experimental: bool,
+    #[serde(default)]
+    deprecated: bool,
+    #[serde(flatten)]
+    schema: Schema,
+}
+
+/// A command, or an event (which has no `returns`).
+#[derive(Deserialize)]
+struct Command {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    experimental: bool,
+    #[serde(default)]
+    deprecated: bool,
+    #[serde(default)]
+    parameters: Vec<Property>,
+    #[serde(default)]
+    returns: Vec<Property>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    for file in &PROTOCOL_FILES {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+    if env::var_os("CARGO_FEATURE_CDP").is_none() {
+        return;
+    }
+
+    let mut domains = vec![];
+    for file in &PROTOCOL_FILES {
+        let json = fs::read_to_string(file).expect("couldn't read the protocol JSON");
+        let protocol: Protocol =
+            serde_json::from_str(&json).expect("couldn't parse the protocol JSON");
+        domains.extend(protocol.domains);
+    }
+
+    let code = Generator::new(&domains).generate();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("cdp.rs"), code).unwrap();
+}
+
+struct Generator<'a> {
+    domains: &'a [Domain],
+    /// For each object type ("Domain.Type"), the object types its fields hold directly rather
+    /// than in an array, for finding the fields that need to be boxed.
+    holds: HashMap<String, Vec<String>>,
+    out: String,
+}
+
+impl<'a> Generator<'a> {
+    fn new(domains: &'a [Domain]) -> Self {
+        let mut holds = HashMap::new();
+        for domain in domains {
+            for type_def in &domain.types {
+                if let Some(properties) = &type_def.schema.properties {
+                    let held = properties
+                        .iter()
+                        .filter_map(|property| property.schema.reference.as_ref())
+                        .map(|reference| qualify(&domain.domain, reference))
+                        .collect();
+                    holds.insert(qualify(&domain.domain, &type_def.id), held);
+                }
+            }
+        }
+        Self {
+            domains,
+            holds,
+            out: String::new(),
+        }
+    }
+
+    fn generate(mut self) -> String {
+        for domain in self.domains {
+            self.generate_domain(domain);
+        }
+        self.generate_event_enum();
+        self.out
+    }
+
+    fn generate_domain(&mut self, domain: &Domain) {
+        self.doc(
+            domain.description.as_ref(),
+            domain.experimental,
+            domain.deprecated,
+        );
+        self.line(&format!("pub mod {} {{", snake_case(&domain.domain)));
+
+        for type_def in &domain.types {
+            self.generate_type(domain, type_def);
+        }
+
+        self.line("pub mod methods {");
+        for command in &domain.commands {
+            self.generate_command(domain, command);
+        }
+        self.line("}");
+
+        self.line("pub mod events {");
+        for event in &domain.events {
+            self.generate_event(domain, event);
+        }
+        self.line("}");
+
+        self.line("}");
+    }
+
+    fn generate_type(&mut self, domain: &Domain, type_def: &TypeDef) {
+        let schema = &type_def.schema;
+        if let Some(variants) = &schema.variants {
+            self.generate_enum(
+                &type_def.id,
+                type_def.description.as_ref(),
+                type_def.experimental,
+                type_def.deprecated,
+                variants,
+            );
+        } else if let Some(properties) = &schema.properties {
+            self.generate_struct(
+                domain,
+                &type_def.id,
+                type_def.description.as_ref(),
+                type_def.experimental,
+                type_def.deprecated,
+                properties,
+                Some(&qualify(&domain.domain, &type_def.id)),
+            );
+        } else {
+            let mut enums = vec![];
+            let rust_type = self.rust_type(domain, schema, &type_def.id, "", &mut enums);
+            self.doc(
+                type_def.description.as_ref(),
+                type_def.experimental,
+                type_def.deprecated,
+            );
+            self.line(&format!("pub type {} = {};", type_def.id, rust_type));
+            for (name, variants) in enums {
+                self.generate_enum(&name, None, false, false, variants);
+            }
+        }
+    }
+
+    fn generate_command(&mut self, domain: &Domain, command: &Command) {
+        let name = pascal_case(&command.name);
+        let return_object = format!("{}ReturnObject", name);
+        self.generate_struct(
+            domain,
+            &name,
+            command.description.as_ref(),
+            command.experimental,
+            command.deprecated,
+            &command.parameters,
+            None,
+        );
+        self.generate_struct(
+            domain,
+            &return_object,
+            None,
+            false,
+            false,
+            &command.returns,
+            None,
+        );
+        self.line(&format!("impl crate::protocol::Method for {} {{", name));
+        self.line(&format!(
+            "const NAME: &'static str = \"{}.{}\";",
+            domain.domain, command.name
+        ));
+        self.line(&format!("type ReturnObject = {};", return_object));
+        self.line("}");
+    }
+
+    fn generate_event(&mut self, domain: &Domain, event: &Command) {
+        let name = format!("{}Event", pascal_case(&event.name));
+        let params = format!("{}Params", name);
+
+        self.doc(
+            event.description.as_ref(),
+            event.experimental,
+            event.deprecated,
+        );
+        self.line("#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]");
+        self.line(&format!("pub struct {} {{", name));
+        if event.parameters.is_empty() {
+            // some events are sent without any params at all
+            self.line("#[serde(default)]");
+        }
+        self.line(&format!("pub params: {},", params));
+        self.line("}");
+        self.line(&format!("impl {} {{", name));
+        self.line(&format!(
+            "pub const NAME: &'static str = \"{}.{}\";",
+            domain.domain, event.name
+        ));
+        self.line("}");
+        self.line(&format!("impl crate::protocol::FromEvent for {} {{", name));
+        self.line("fn from_event(event: &crate::protocol::Event) -> ::std::option::Option<Self> {");
+        self.line("match event {");
+        self.line(&format!(
+            "crate::protocol::Event::Protocol(crate::protocol::cdp::Event::{}{}(event)) => {{",
+            domain.domain,
+            pascal_case(&event.name)
+        ));
+        self.line("Some(event.clone())");
+        self.line("}");
+        self.line("_ => None,");
+        self.line("}");
+        self.line("}");
+        self.line("}");
+
+        self.generate_struct(domain, &params, None, false, false, &event.parameters, None);
+    }
+
+    fn generate_event_enum(&mut self) {
+        self.line("/// Every event in the protocol, tagged by \"method\" like `protocol::Event`.");
+        self.line("#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]");
+        self.line("#[serde(tag = \"method\")]");
+        self.line("pub enum Event {");
+        for domain in self.domains {
+            for event in &domain.events {
+                self.line(&format!(
+                    "#[serde(rename = \"{}.{}\")]",
+                    domain.domain, event.name
+                ));
+                self.line(&format!(
+                    "{}{}({}::events::{}Event),",
+                    domain.domain,
+                    pascal_case(&event.name),
+                    snake_case(&domain.domain),
+                    pascal_case(&event.name)
+                ));
+            }
+        }
+        self.line("}");
+    }
+
+    /// `self_type` is the struct's "Domain.Type" when it's one of the domain's types, in which
+    /// case fields that (indirectly) hold a value of the same type are boxed.
+    #[allow(clippy::too_many_arguments)]
+    fn generate_struct(
+        &mut self,
+        domain: &Domain,
+        name: &str,
+        description: Option<&String>,
+        experimental: bool,
+        deprecated: bool,
+        properties: &[Property],
+        self_type: Option<&str>,
+    ) {
+        let mut enums = vec![];
+
+        self.doc(description, experimental, deprecated);
+        if properties.is_empty() {
+            self.line("#[derive(Debug, Clone, Default, PartialEq, ::serde::Serialize, ::serde::Deserialize)]");
+        } else {
+            self.line(
+                "#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]",
+            );
+        }
+        self.line(&format!("pub struct {} {{", name));
+        for property in properties {
+            let mut rust_type =
+                self.rust_type(domain, &property.schema, name, &property.name, &mut enums);
+            let boxed = match (self_type, &property.schema.reference) {
+                (Some(self_type), Some(reference)) => {
+                    self.reaches(&qualify(&domain.domain, reference), self_type)
+                }
+                _ => false,
+            };
+            if boxed {
+                rust_type = format!("::std::boxed::Box<{}>", rust_type);
+            }
+
+            self.doc(
+                property.description.as_ref(),
+                property.experimental,
+                property.deprecated,
+            );
+            self.line(&format!("#[serde(rename = \"{}\")]", property.name));
+            if property.optional {
+                self.line(
+                    "#[serde(default, skip_serializing_if = \"::std::option::Option::is_none\")]",
+                );
+                rust_type = format!("::std::option::Option<{}>", rust_type);
+            }
+            self.line(&format!(
+                "pub {}: {},",
+                field_name(&property.name),
+                rust_type
+            ));
+        }
+        self.line("}");
+
+        for (enum_name, variants) in enums {
+            self.generate_enum(&enum_name, None, false, false, variants);
+        }
+    }
+
+    fn generate_enum(
+        &mut self,
+        name: &str,
+        description: Option<&String>,
+        experimental: bool,
+        deprecated: bool,
+        variants: &[String],
+    ) {
+        self.doc(description, experimental, deprecated);
+        self.line("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ::serde::Serialize, ::serde::Deserialize)]");
+        self.line(&format!("pub enum {} {{", name));
+        let mut taken = HashSet::new();
+        for value in variants {
+            let mut variant = pascal_case(value);
+            if variant == "Self" {
+                variant.push('_');
+            }
+            while !taken.insert(variant.clone()) {
+                variant.push('_');
+            }
+            self.line(&format!("#[serde(rename = \"{}\")]", value));
+            self.line(&format!("{},", variant));
+        }
+        self.line("}");
+    }
+
+    /// The Rust type for `schema`, used in a field `property` of `owner`. An inline enum is
+    /// named after both, and added to `enums` to be generated alongside the owner.
+    fn rust_type<'s>(
+        &self,
+        domain: &Domain,
+        schema: &'s Schema,
+        owner: &str,
+        property: &str,
+        enums: &mut Vec<(String, &'s [String])>,
+    ) -> String {
+        if let Some(reference) = &schema.reference {
+            let qualified = qualify(&domain.domain, reference);
+            let mut parts = qualified.splitn(2, '.');
+            let (ref_domain, ref_type) = (parts.next().unwrap(), parts.next().unwrap());
+            return format!(
+                "crate::protocol::cdp::{}::{}",
+                snake_case(ref_domain),
+                ref_type
+            );
+        }
+        if let Some(variants) = &schema.variants {
+            let name = format!("{}{}", owner, pascal_case(property));
+            enums.push((name.clone(), variants));
+            return name;
+        }
+        match schema.kind.as_deref() {
+            Some("string") => "::std::string::String".to_string(),
+            Some("integer") => "i64".to_string(),
+            Some("number") => "f64".to_string(),
+            Some("boolean") => "bool".to_string(),
+            Some("array") => {
+                let items = schema.items.as_ref().expect("array without items");
+                format!(
+                    "::std::vec::Vec<{}>",
+                    self.rust_type(domain, items, owner, property, enums)
+                )
+            }
+            _ => "::serde_json::Value".to_string(),
+        }
+    }
+
+    /// Whether a value of type `from` can hold one of type `to`, other than through an array.
+    fn reaches(&self, from: &str, to: &str) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(current) = stack.pop() {
+            if current == to {
+                return true;
+            }
+            if seen.insert(current) {
+                if let Some(held) = self.holds.get(current) {
+                    stack.extend(held.iter().map(String::as_str));
+                }
+            }
+        }
+        false
+    }
+
+    fn doc(&mut self, description: Option<&String>, experimental: bool, deprecated: bool) {
+        if let Some(description) = description {
+            for line in description.lines() {
+                // trimmed, so that nothing gets taken for an indented code block (i.e. a doctest)
+                self.line(format!("/// {}", line.trim()).trim_end());
+            }
+        }
+        let note = match (experimental, deprecated) {
+            (_, true) => "Deprecated.",
+            (true, false) => "Experimental.",
+            (false, false) => return,
+        };
+        if description.is_some() {
+            self.line("///");
+        }
+        self.line(&format!("/// {}", note));
+    }
+
+    fn line(&mut self, line: &str) {
+        writeln!(self.out, "{}", line).unwrap();
+    }
+}
+
+/// "Domain.Type" for a reference made from within `domain`.
+fn qualify(domain: &str, reference: &str) -> String {
+    if reference.contains('.') {
+        reference.to_string()
+    } else {
+        format!("{}.{}", domain, reference)
+    }
+}
+
+/// "DOMDebugger" -> "dom_debugger", "requestURL" -> "request_url".
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            snake.push('_');
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(char::is_ascii_lowercase);
+            if previous.is_ascii_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_ascii_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+/// "getDocument" -> "GetDocument", "ERR_CERT_INVALID" -> "ErrCertInvalid", "x-large" -> "XLarge".
+fn pascal_case(name: &str) -> String {
+    let mut pascal = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            pascal.push(first.to_ascii_uppercase());
+            let rest: String = chars.collect();
+            if word.len() > 1 && word.chars().all(|c| !c.is_ascii_lowercase()) {
+                pascal.push_str(&rest.to_ascii_lowercase());
+            } else {
+                pascal.push_str(&rest);
+            }
+        }
+    }
+    if pascal.is_empty() {
+        pascal.push_str("Empty");
+    }
+    pascal
+}
+
+fn field_name(name: &str) -> String {
+    let snake = snake_case(name);
+    match snake.as_str() {
+        "self" | "super" | "crate" => format!("{}_", snake),
+        "as" | "async" | "await" | "break" | "const" | "continue" | "dyn" | "else" | "enum"
+        | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match"
+        | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct" | "trait"
+        | "true" | "type" | "unsafe" | "use" | "where" | "while" | "abstract" | "become"
+        | "box" | "do" | "final" | "macro" | "override" | "priv" | "typeof" | "unsized"
+        | "virtual" | "yield" | "try" | "gen" => format!("r#{}", snake),
+        _ => snake,
+    }
+}
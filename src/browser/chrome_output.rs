@@ -0,0 +1,179 @@
+//! Draining what Chrome writes to its stdout and stderr.
+//!
+//! Everything Chrome prints is forwarded to the `log` crate under `LOG_TARGET` (at debug
+//! level), and the most recent lines are kept around, so that there's something to look at
+//! when Chrome crashes or exits.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use failure::Error;
+use log::*;
+
+/// The `log` target Chrome's output is logged under.
+pub const LOG_TARGET: &str = "headless_chrome::chrome_output";
+
+/// How many lines a `ChromeOutput` keeps by default.
+pub const DEFAULT_CAPACITY: usize = 500;
+
+/// How many of the last lines get logged when Chrome's output ends.
+const LINES_LOGGED_ON_CLOSE: usize = 20;
+
+/// The last lines Chrome wrote to its stdout and stderr, oldest first.
+///
+/// Cheap to clone; clones share the same buffer.
+#[derive(Clone, Debug)]
+pub struct ChromeOutput {
+    state: Arc<Mutex<OutputState>>,
+}
+
+#[derive(Debug)]
+struct OutputState {
+    lines: VecDeque<String>,
+    capacity: usize,
+    open_streams: usize,
+    listeners: Vec<mpsc::Sender<String>>,
+}
+
+impl ChromeOutput {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(OutputState {
+                lines: VecDeque::with_capacity(capacity),
+                capacity,
+                open_streams: 0,
+                listeners: vec![],
+            })),
+        }
+    }
+
+    /// All the lines still in the buffer.
+    pub fn lines(&self) -> Vec<String> {
+        self.last_lines(usize::MAX)
+    }
+
+    /// Up to `count` of the most recent lines.
+    pub fn last_lines(&self, count: usize) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let skip = state.lines.len().saturating_sub(count);
+        state.lines.iter().skip(skip).cloned().collect()
+    }
+
+    /// Whether every stream being drained has ended, which usually means Chrome has exited.
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().open_streams == 0
+    }
+
+    /// The lines written so far, and a receiver for every line written after them. The
+    /// receiver gets disconnected once all streams have ended.
+    pub(crate) fn subscribe(&self) -> (Vec<String>, mpsc::Receiver<String>) {
+        let mut state = self.state.lock().unwrap();
+        let (tx, rx) = mpsc::channel();
+        if state.open_streams > 0 {
+            state.listeners.push(tx);
+        }
+        (state.lines.iter().cloned().collect(), rx)
+    }
+
+    /// Reads `stream` line by line on a background thread until it ends. `name` is only used
+    /// for logging.
+    pub(crate) fn drain<R>(&self, stream: R, name: &'static str) -> Result<(), Error>
+    where
+        R: Read + Send + 'static,
+    {
+        self.state.lock().unwrap().open_streams += 1;
+        let output = self.clone();
+        let spawned = thread::Builder::new()
+            .name(format!("chrome {}", name))
+            .spawn(move || {
+                let mut reader = BufReader::new(stream);
+                let mut line = vec![];
+                loop {
+                    line.clear();
+                    match reader.read_until(b'\n', &mut line) {
+                        Ok(0) => break,
+                        Ok(_) => {
+                            let text = String::from_utf8_lossy(&line);
+                            output.push(name, text.trim_end_matches(&['\r', '\n'][..]));
+                        }
+                        Err(err) => {
+                            debug!(target: LOG_TARGET, "Couldn't read Chrome's {}: {}", name, err);
+                            break;
+                        }
+                    }
+                }
+                output.close_stream(name);
+            });
+        if let Err(err) = spawned {
+            self.state.lock().unwrap().open_streams -= 1;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    fn push(&self, name: &str, line: &str) {
+        debug!(target: LOG_TARGET, "[{}] {}", name, line);
+        let mut state = self.state.lock().unwrap();
+        if state.lines.len() == state.capacity {
+            state.lines.pop_front();
+        }
+        if state.capacity > 0 {
+            state.lines.push_back(line.to_string());
+        }
+        state
+            .listeners
+            .retain(|listener| listener.send(line.to_string()).is_ok());
+    }
+
+    fn close_stream(&self, name: &str) {
+        trace!("Chrome's {} ended", name);
+        let mut state = self.state.lock().unwrap();
+        state.open_streams -= 1;
+        if state.open_streams > 0 {
+            return;
+        }
+        state.listeners.clear();
+        let skip = state.lines.len().saturating_sub(LINES_LOGGED_ON_CLOSE);
+        let last_lines: Vec<&str> = state.lines.iter().skip(skip).map(String::as_str).collect();
+        info!(
+            target: LOG_TARGET,
+            "Chrome's output ended. Last lines:\n{}",
+            last_lines.join("\n")
+        );
+    }
+}
+
+impl Default for ChromeOutput {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_last_lines_of_every_stream() {
+        let output = ChromeOutput::default();
+        output
+            .drain(&b"one\ntwo\r\nthree\nfour"[..], "stderr")
+            .unwrap();
+
+        // whatever wasn't written yet when subscribing arrives over the channel, until the
+        // stream ends
+        let (seen, new_lines) = output.subscribe();
+        let all_lines: Vec<String> = seen.into_iter().chain(new_lines.iter()).collect();
+        assert_eq!(vec!["one", "two", "three", "four"], all_lines);
+        assert!(output.is_closed());
+        assert_eq!(vec!["four"], output.last_lines(1));
+
+        let output = ChromeOutput::new(2);
+        output.drain(&b"one\ntwo\nthree\n"[..], "stdout").unwrap();
+        let (_, new_lines) = output.subscribe();
+        new_lines.iter().for_each(drop);
+        assert_eq!(vec!["two", "three"], output.lines());
+    }
+}
@@ -1,6 +1,10 @@
+use crate::browser::cookies::{self, CookieFormat};
 use crate::browser::tab::Tab;
+use crate::protocol::network::{Cookie, CookieParam};
+use crate::protocol::storage;
 use crate::protocol::target::methods::CreateTarget;
 use failure;
+use std::path::Path;
 use std::sync::Arc;
 
 /// Equivalent to a new incognito window
@@ -48,4 +52,48 @@ impl<'a> Context<'a> {
         }
         Ok(tabs)
     }
+
+    /// Every cookie in this context, whichever tab or URL it belongs to
+    pub fn get_cookies(&self) -> Result<Vec<Cookie>, failure::Error> {
+        let cookies = self.browser.call_method(storage::methods::GetCookies {
+            browser_context_id: Some(&self.id),
+        })?;
+        Ok(cookies.cookies)
+    }
+
+    pub fn set_cookies(&self, cookies: &[CookieParam]) -> Result<(), failure::Error> {
+        self.browser.call_method(storage::methods::SetCookies {
+            cookies,
+            browser_context_id: Some(&self.id),
+        })?;
+        Ok(())
+    }
+
+    pub fn clear_cookies(&self) -> Result<(), failure::Error> {
+        self.browser.call_method(storage::methods::ClearCookies {
+            browser_context_id: Some(&self.id),
+        })?;
+        Ok(())
+    }
+
+    /// Sets the cookies saved in `path`, e.g. by `export_cookies` after logging in once, and
+    /// returns how many there were
+    pub fn import_cookies<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: CookieFormat,
+    ) -> Result<usize, failure::Error> {
+        let cookies = cookies::load(path, format)?;
+        self.set_cookies(&cookies)?;
+        Ok(cookies.len())
+    }
+
+    /// Saves every cookie in this context to `path`
+    pub fn export_cookies<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: CookieFormat,
+    ) -> Result<(), failure::Error> {
+        cookies::save(path, &self.get_cookies()?, format)
+    }
 }
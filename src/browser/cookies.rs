@@ -0,0 +1,262 @@
+//! Saving cookies to and loading them from files, as JSON or as a Netscape `cookies.txt`, e.g.
+//! to seed a `Context` with a logged-in session instead of logging in on every run.
+
+use std::fs;
+use std::path::Path;
+
+use failure::{Error, Fail};
+
+use crate::protocol::network::{Cookie, CookieParam};
+
+const NETSCAPE_HEADER: &str = "# Netscape HTTP Cookie File\n";
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CookieFormat {
+    /// An array of cookies as `Network.getCookies` returns them (or as they're passed to
+    /// `Network.setCookies`, when loading).
+    Json,
+    /// The tab separated `cookies.txt` of curl, wget and browser extensions.
+    Netscape,
+}
+
+impl CookieFormat {
+    /// `Json` for paths ending in `.json`, `Netscape` for anything else.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let is_json = path
+            .as_ref()
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        if is_json {
+            CookieFormat::Json
+        } else {
+            CookieFormat::Netscape
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "Line {} of the cookies.txt file is invalid: {}",
+    line, reason
+)]
+pub struct InvalidCookiesTxt {
+    pub line: usize,
+    pub reason: String,
+}
+
+pub fn save<P: AsRef<Path>>(
+    path: P,
+    cookies: &[Cookie],
+    format: CookieFormat,
+) -> Result<(), Error> {
+    let contents = match format {
+        CookieFormat::Json => to_json(cookies)?,
+        CookieFormat::Netscape => to_netscape(cookies),
+    };
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(path: P, format: CookieFormat) -> Result<Vec<CookieParam>, Error> {
+    let contents = fs::read_to_string(path)?;
+    match format {
+        CookieFormat::Json => from_json(&contents),
+        CookieFormat::Netscape => from_netscape(&contents),
+    }
+}
+
+pub fn to_json(cookies: &[Cookie]) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(cookies)?)
+}
+
+/// Reads cookies as `to_json` writes them, or as `CookieParam`s; session cookies written by
+/// other tools with an `expires` of -1 are kept as session cookies.
+pub fn from_json(json: &str) -> Result<Vec<CookieParam>, Error> {
+    if let Ok(cookies) = serde_json::from_str::<Vec<Cookie>>(json) {
+        return Ok(cookies.into_iter().map(CookieParam::from).collect());
+    }
+    let mut cookies: Vec<CookieParam> = serde_json::from_str(json)?;
+    for cookie in &mut cookies {
+        if cookie.expires.is_some_and(|expires| expires <= 0.0) {
+            cookie.expires = None;
+        }
+    }
+    Ok(cookies)
+}
+
+/// Session cookies get an expiry of 0, and `HttpOnly` ones are marked the way curl does, by
+/// prefixing their domain with `#HttpOnly_`.
+pub fn to_netscape(cookies: &[Cookie]) -> String {
+    let lines = cookies.iter().map(|cookie| {
+        let expires = if cookie.session {
+            0.0
+        } else {
+            cookie.expires.trunc()
+        };
+        format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            if cookie.http_only {
+                HTTP_ONLY_PREFIX
+            } else {
+                ""
+            },
+            cookie.domain,
+            flag(cookie.domain.starts_with('.')),
+            cookie.path,
+            flag(cookie.secure),
+            expires,
+            cookie.name,
+            cookie.value
+        )
+    });
+    std::iter::once(NETSCAPE_HEADER.to_string())
+        .chain(lines)
+        .collect()
+}
+
+/// Cookies whose "include subdomains" flag is `FALSE` are set by URL, so they stay host-only.
+pub fn from_netscape(text: &str) -> Result<Vec<CookieParam>, Error> {
+    let mut cookies = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let invalid = |reason: &str| InvalidCookiesTxt {
+            line: index + 1,
+            reason: reason.to_string(),
+        };
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.splitn(7, '\t').collect();
+        if fields.len() != 7 {
+            return Err(invalid("expected 7 tab separated fields").into());
+        }
+        let (domain, path, secure) = (fields[0], fields[2], parse_flag(fields[3]));
+        let expires: f64 = fields[4]
+            .trim()
+            .parse()
+            .map_err(|_| invalid("the expiry isn't a number"))?;
+
+        let (url, domain) = if parse_flag(fields[1]) {
+            (None, Some(format!(".{}", domain.trim_start_matches('.'))))
+        } else {
+            let scheme = if secure { "https" } else { "http" };
+            let host = domain.trim_start_matches('.');
+            (Some(format!("{}://{}{}", scheme, host, path)), None)
+        };
+        cookies.push(CookieParam {
+            name: fields[5].to_string(),
+            value: fields[6].trim_end_matches('\r').to_string(),
+            url,
+            domain,
+            path: Some(path.to_string()),
+            secure: Some(secure),
+            http_only: Some(http_only),
+            same_site: None,
+            expires: if expires > 0.0 { Some(expires) } else { None },
+        });
+    }
+    Ok(cookies)
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+fn parse_flag(field: &str) -> bool {
+    field.trim().eq_ignore_ascii_case("TRUE")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::network::CookieSameSite;
+
+    fn cookie(name: &str, domain: &str, session: bool) -> Cookie {
+        Cookie {
+            name: name.to_string(),
+            value: "v=1".to_string(),
+            domain: domain.to_string(),
+            path: "/app".to_string(),
+            expires: if session { -1.0 } else { 1_893_456_000.5 },
+            size: 6,
+            http_only: session,
+            secure: !session,
+            session,
+            same_site: Some(CookieSameSite::Lax),
+        }
+    }
+
+    #[test]
+    fn netscape_round_trip() {
+        let cookies = vec![
+            cookie("sid", ".example.com", false),
+            cookie("csrf", "app.example.com", true),
+        ];
+        let text = to_netscape(&cookies);
+        assert_eq!(
+            "# Netscape HTTP Cookie File\n\
+             .example.com\tTRUE\t/app\tTRUE\t1893456000\tsid\tv=1\n\
+             #HttpOnly_app.example.com\tFALSE\t/app\tFALSE\t0\tcsrf\tv=1\n",
+            text
+        );
+
+        let loaded = from_netscape(&text).unwrap();
+        assert_eq!(
+            vec![
+                CookieParam {
+                    name: "sid".to_string(),
+                    value: "v=1".to_string(),
+                    domain: Some(".example.com".to_string()),
+                    path: Some("/app".to_string()),
+                    secure: Some(true),
+                    http_only: Some(false),
+                    expires: Some(1_893_456_000.0),
+                    ..Default::default()
+                },
+                CookieParam {
+                    name: "csrf".to_string(),
+                    value: "v=1".to_string(),
+                    url: Some("http://app.example.com/app".to_string()),
+                    path: Some("/app".to_string()),
+                    secure: Some(false),
+                    http_only: Some(true),
+                    ..Default::default()
+                },
+            ],
+            loaded
+        );
+    }
+
+    #[test]
+    fn netscape_errors_name_the_line() {
+        let text = "# comment\n\nexample.com\tFALSE\t/\tFALSE\tsoon\tname\tvalue\n";
+        let error = from_netscape(text).unwrap_err();
+        let error = error.downcast::<InvalidCookiesTxt>().unwrap();
+        assert_eq!(3, error.line);
+
+        assert!(from_netscape("example.com\tFALSE\t/\n").is_err());
+    }
+
+    #[test]
+    fn json_accepts_cookies_and_cookie_params() {
+        let cookies = vec![cookie("sid", ".example.com", false)];
+        let loaded = from_json(&to_json(&cookies).unwrap()).unwrap();
+        assert_eq!(vec![CookieParam::from(cookies[0].clone())], loaded);
+
+        let loaded = from_json(
+            r#"[{"name": "a", "value": "b", "url": "https://example.com/", "expires": -1}]"#,
+        )
+        .unwrap();
+        assert_eq!(Some("https://example.com/".to_string()), loaded[0].url);
+        assert_eq!(None, loaded[0].expires);
+    }
+}
@@ -0,0 +1,126 @@
+//! Finding a running Chrome through the HTTP endpoints served on its debugging port
+//! (`/json/version` and `/json/list`), so that you only need to know its host and port.
+//!
+//! See https://chromedevtools.github.io/devtools-protocol/#endpoints
+
+use failure::{Error, Fail};
+use log::*;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::protocol::target::{TargetId, TargetInfo, TargetType};
+
+#[derive(Debug, Fail)]
+pub enum DiscoveryError {
+    #[fail(display = "Couldn't reach {}: {}", url, reason)]
+    Unreachable { url: String, reason: String },
+    #[fail(display = "{} answered with HTTP status {}", url, status)]
+    BadStatus { url: String, status: u16 },
+    #[fail(display = "Couldn't parse the response from {}: {}", url, reason)]
+    InvalidResponse { url: String, reason: String },
+}
+
+/// What Chrome reports at `/json/version`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BrowserVersion {
+    #[serde(rename = "Browser")]
+    pub browser: String,
+    #[serde(rename = "Protocol-Version")]
+    pub protocol_version: String,
+    #[serde(rename = "User-Agent")]
+    pub user_agent: String,
+    #[serde(rename = "V8-Version")]
+    pub v8_version: Option<String>,
+    #[serde(rename = "WebKit-Version")]
+    pub webkit_version: Option<String>,
+    /// The browser-level WebSocket URL, i.e. what `Browser::connect` expects.
+    #[serde(rename = "webSocketDebuggerUrl")]
+    pub web_socket_debugger_url: String,
+}
+
+/// One entry of `/json/list`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListedTarget {
+    pub id: TargetId,
+    #[serde(rename = "type")]
+    pub target_type: TargetType,
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub description: String,
+    pub parent_id: Option<TargetId>,
+    pub devtools_frontend_url: Option<String>,
+    /// Chrome leaves this out while some other client is attached to the target.
+    pub web_socket_debugger_url: Option<String>,
+}
+
+impl From<ListedTarget> for TargetInfo {
+    fn from(target: ListedTarget) -> Self {
+        Self {
+            attached: target.web_socket_debugger_url.is_none(),
+            target_id: target.id,
+            target_type: target.target_type,
+            title: target.title,
+            url: target.url,
+            opener_id: None,
+            browser_context_id: None,
+        }
+    }
+}
+
+fn get_json<T>(host_port: &str, path: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    let url = format!("http://{}{}", host_port, path);
+    debug!("Fetching {}", url);
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(10))
+        .timeout_read(Duration::from_secs(10))
+        .build();
+    let response = match agent.get(&url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, _)) => {
+            return Err(DiscoveryError::BadStatus { url, status }.into());
+        }
+        Err(err) => {
+            return Err(DiscoveryError::Unreachable {
+                url,
+                reason: err.to_string(),
+            }
+            .into());
+        }
+    };
+
+    let body = response.into_string()?;
+    serde_json::from_str(&body).map_err(|err| {
+        DiscoveryError::InvalidResponse {
+            url,
+            reason: err.to_string(),
+        }
+        .into()
+    })
+}
+
+/// Asks the Chrome listening on `host_port` (e.g. "127.0.0.1:9222") for its version and
+/// browser-level WebSocket URL.
+pub fn get_version(host_port: &str) -> Result<BrowserVersion, Error> {
+    get_json(host_port, "/json/version")
+}
+
+/// Lists the targets (pages, iframes, workers...) of the Chrome listening on `host_port`.
+pub fn list_targets(host_port: &str) -> Result<Vec<TargetInfo>, Error> {
+    let targets: Vec<ListedTarget> = get_json(host_port, "/json/list")?;
+    Ok(targets.into_iter().map(Into::into).collect())
+}
+
+/// Like `list_targets`, but only the pages.
+pub fn list_pages(host_port: &str) -> Result<Vec<TargetInfo>, Error> {
+    Ok(list_targets(host_port)?
+        .into_iter()
+        .filter(|target| target.target_type.is_page())
+        .collect())
+}
@@ -1,14 +1,28 @@
+//! Downloading a known-good revision of Chromium, when `LaunchOptions` doesn't say which
+//! Chrome to use.
+//!
+//! Revisions are installed into the project's data directory (e.g.
+//! `~/.local/share/headless-chrome` on Linux) unless `FetcherOptions::install_dir` says
+//! otherwise, one directory per revision. Downloads come from Google's storage by default, but
+//! any mirror with the same layout works, including a local directory given as a `file://` URL.
+//!
+//! Installing takes a lock file per revision, so processes sharing an install directory don't
+//! download the same revision at once; it's removed once the install is done. Archives are
+//! extracted into a temporary directory which is only renamed into place once complete.
+
 use directories::ProjectDirs;
-use failure::{format_err, Error};
+use failure::{format_err, Error, Fail};
+use fs2::FileExt;
 use log::*;
+use sha2::{Digest, Sha256};
 use ureq;
 use zip;
 
 use std::{
+    collections::HashMap,
     fs::{self, File, OpenOptions},
-    io::{self, BufWriter},
+    io::{self, BufWriter, Read, Write},
     path::{Path, PathBuf},
-    str::FromStr,
 };
 
 pub const CUR_REV: &str = "634997";
@@ -23,29 +37,123 @@ const PLATFORM: &str = "mac";
 #[cfg(windows)]
 const PLATFORM: &str = "win";
 
+#[derive(Builder, Clone, Debug)]
+pub struct FetcherOptions {
+    /// Where to download from: an `http(s)://` or `file://` URL, or a local directory, under
+    /// which archives are found at the same paths as on Google's storage, e.g.
+    /// `chromium-browser-snapshots/Linux_x64/634997/chrome-linux.zip`.
+    ///
+    /// Defaults to Google's storage.
+    #[builder(default = "DEFAULT_HOST.to_string()")]
+    pub host: String,
+
+    /// A manifest of SHA-256 checksums to verify downloads against, as a URL or local path
+    /// like `host`. It's in the format `sha256sum` outputs, with archives named by their path
+    /// relative to `host`:
+    ///
+    /// ```text
+    /// 0c8e3c2e...  chromium-browser-snapshots/Linux_x64/634997/chrome-linux.zip
+    /// ```
+    ///
+    /// If one is given, archives that aren't in it won't be installed.
+    #[builder(default = "None")]
+    pub manifest: Option<String>,
+
+    /// Where to install revisions. Defaults to the project's data directory.
+    #[builder(default = "None")]
+    pub install_dir: Option<PathBuf>,
+}
+
+impl Default for FetcherOptions {
+    fn default() -> Self {
+        FetcherOptionsBuilder::default()
+            .build()
+            .expect("every fetcher option has a default")
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "SHA-256 of {} is {}, but the manifest says {}",
+    url, actual, expected
+)]
+pub struct ChecksumMismatch {
+    pub url: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// SHA-256 checksums of archives, keyed by their path relative to the download host.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    checksums: HashMap<String, String>,
+}
+
+impl Manifest {
+    /// Parses `sha256sum` output. Lines that aren't a checksum followed by a path are ignored.
+    pub fn parse(text: &str) -> Self {
+        let checksums = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.trim().splitn(2, char::is_whitespace);
+                let checksum = parts.next()?;
+                // `sha256sum --binary` marks paths with a '*'
+                let path = parts.next()?.trim_start().trim_start_matches('*');
+                let path = path.trim_start_matches("./");
+                if checksum.len() != 64 || path.is_empty() {
+                    return None;
+                }
+                Some((path.to_string(), checksum.to_lowercase()))
+            })
+            .collect();
+        Self { checksums }
+    }
+
+    pub fn checksum_for(&self, archive_path: &str) -> Option<&str> {
+        self.checksums.get(archive_path).map(String::as_str)
+    }
+}
+
 pub struct Fetcher<'a> {
     rev: &'a str,
-    dirs: ProjectDirs,
+    options: FetcherOptions,
+    install_dir: PathBuf,
 }
 
 impl<'a> Fetcher<'a> {
     pub fn new(rev: &'a str) -> Result<Self, Error> {
-        let dirs = get_project_dirs()?;
+        Self::with_options(rev, FetcherOptions::default())
+    }
+
+    pub fn with_options(rev: &'a str, options: FetcherOptions) -> Result<Self, Error> {
+        let install_dir = match options.install_dir {
+            Some(ref install_dir) => install_dir.clone(),
+            None => get_project_dirs()?.data_dir().to_path_buf(),
+        };
         info!(
-            "Creating XDG_DATA_DIR if it doesn't exist: {}",
-            dirs.data_dir().display()
+            "Creating install directory if it doesn't exist: {}",
+            install_dir.display()
         );
-        fs::create_dir_all(dirs.data_dir())?;
-        Ok(Self { rev, dirs })
+        fs::create_dir_all(&install_dir)?;
+        Ok(Self {
+            rev,
+            options,
+            install_dir,
+        })
     }
 
-    fn local_revisions(&self) -> Result<Vec<String>, Error> {
+    pub fn install_dir(&self) -> &Path {
+        &self.install_dir
+    }
+
+    /// The revisions installed in the install directory.
+    pub fn cached_revisions(&self) -> Result<Vec<String>, Error> {
         trace!(
-            "Enumerating contents of XDG_DATA_DIR: {}",
-            self.dirs.data_dir().display()
+            "Enumerating contents of install directory: {}",
+            self.install_dir.display()
         );
         let mut revisions = Vec::new();
-        for entry in fs::read_dir(self.dirs.data_dir())? {
+        for entry in fs::read_dir(&self.install_dir)? {
             let entry = entry?;
             let path = entry.path();
             if path.is_dir() {
@@ -65,8 +173,31 @@ impl<'a> Fetcher<'a> {
         Ok(revisions)
     }
 
+    /// Uninstalls `rev`, waiting for whoever is installing it to finish first.
+    pub fn remove_revision(&self, rev: &str) -> Result<(), Error> {
+        let _lock = self.lock(rev)?;
+        let path = self.base_path(rev);
+        if path.exists() {
+            info!("Removing revision {}: {}", rev, path.display());
+            fs::remove_dir_all(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Uninstalls every revision but the ones in `keep`, returning the ones it removed.
+    pub fn prune(&self, keep: &[&str]) -> Result<Vec<String>, Error> {
+        let mut removed = vec![];
+        for rev in self.cached_revisions()? {
+            if !keep.contains(&rev.as_str()) {
+                self.remove_revision(&rev)?;
+                removed.push(rev);
+            }
+        }
+        Ok(removed)
+    }
+
     fn base_path(&self, rev: &str) -> PathBuf {
-        let mut path = self.dirs.data_dir().to_path_buf();
+        let mut path = self.install_dir.clone();
         path.push(format!("{}-{}", PLATFORM, rev));
         path
     }
@@ -94,101 +225,233 @@ impl<'a> Fetcher<'a> {
         Ok(path)
     }
 
+    /// Takes the lock on `rev`, blocking until whoever holds it lets go. The lock is released
+    /// when the returned `RevisionLock` is dropped, or the process dies.
+    fn lock(&self, rev: &str) -> Result<RevisionLock, Error> {
+        let path = self.install_dir.join(format!("{}-{}.lock", PLATFORM, rev));
+        loop {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)?;
+            if file.try_lock_exclusive().is_err() {
+                info!("Waiting for another process to install revision {}", rev);
+                file.lock_exclusive()?;
+            }
+            // whoever held the lock may have removed the file before letting go, in which case
+            // anyone coming after locks a new one instead
+            if is_same_file(&file, &path) {
+                return Ok(RevisionLock { _file: file, path });
+            }
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        self.cached_revisions()
+            .is_ok_and(|revisions| revisions.iter().any(|r| r == self.rev))
+    }
+
+    /// Installs the revision if it isn't already, returning the path to its Chrome binary.
     pub fn run(&self) -> Result<PathBuf, Error> {
-        let revisions = self.local_revisions()?;
-        if let Some(revision) = revisions.into_iter().find(|r| r == self.rev) {
+        if self.is_installed() {
             info!("No need to download, we have the correct revision");
-            return Ok(self.chrome_path(&revision)?);
+            return self.chrome_path(self.rev);
+        }
+
+        let _lock = self.lock(self.rev)?;
+        if self.is_installed() {
+            info!("Revision {} was installed by another process", self.rev);
+            return self.chrome_path(self.rev);
         }
 
-        let url = dl_url(self.rev)?;
+        let archive_path = archive_path(self.rev)?;
+        let expected_checksum = match self.options.manifest {
+            Some(ref manifest) => {
+                let mut text = String::new();
+                open_url(manifest)?.read_to_string(&mut text)?;
+                let checksum = Manifest::parse(&text)
+                    .checksum_for(&archive_path)
+                    .map(ToString::to_string)
+                    .ok_or_else(|| {
+                        format_err!("{} isn't in the manifest at {}", archive_path, manifest)
+                    })?;
+                Some(checksum)
+            }
+            None => None,
+        };
+
+        let url = join_url(&self.options.host, &archive_path);
         info!("Chrome download url: {}", url);
-        let total = get_size(&url)?;
-        info!("Total size of download: {} MiB", total);
-        let path = self.base_path(self.rev).with_extension("zip");
 
-        info!("Creating file for download: {}", &path.display());
-        let mut file = OpenOptions::new().create(true).write(true).open(&path)?;
+        let archive = tempfile::Builder::new()
+            .prefix(".download-")
+            .suffix(".zip")
+            .tempfile_in(&self.install_dir)?;
+        let mut hashing = HashingWriter {
+            inner: BufWriter::new(archive.as_file()),
+            hasher: Sha256::new(),
+        };
+        let size = io::copy(&mut open_url(&url)?, &mut hashing)?;
+        hashing.flush()?;
+        info!("Downloaded {} MiB", size / 2_u64.pow(20));
+
+        let actual_checksum = format!("{:x}", hashing.hasher.result());
+        if let Some(expected) = expected_checksum {
+            if expected != actual_checksum {
+                return Err(ChecksumMismatch {
+                    url,
+                    expected,
+                    actual: actual_checksum,
+                }
+                .into());
+            }
+            debug!("Checksum of {} matches the manifest", url);
+        }
+
+        let extract_dir = tempfile::Builder::new()
+            .prefix(".extracting-")
+            .tempdir_in(&self.install_dir)?;
+        unzip(archive.path(), extract_dir.path())?;
+        fs::rename(extract_dir.path(), self.base_path(self.rev))?;
+
+        self.chrome_path(self.rev)
+    }
+}
 
-        let resp = ureq::get(&url).call();
-        io::copy(&mut resp.into_reader(), &mut file)?;
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
 
-        self.unzip(&path)?;
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.input(&buf[..written]);
+        Ok(written)
+    }
 
-        Ok(self.chrome_path(self.rev)?)
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
     }
+}
 
-    pub fn unzip<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
-        let mut archive = zip::ZipArchive::new(File::open(path.as_ref())?)?;
-        let extract_path = self.base_path(self.rev);
-        fs::create_dir_all(&extract_path)?;
+fn unzip(archive_path: &Path, extract_path: &Path) -> Result<(), Error> {
+    let mut archive = zip::ZipArchive::new(File::open(archive_path)?)?;
 
-        info!(
-            "Extracting (this can take a while): {}",
-            extract_path.display()
-        );
+    info!(
+        "Extracting (this can take a while): {}",
+        extract_path.display()
+    );
 
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let mut out_path = extract_path.clone();
-            out_path.push(file.sanitized_name().as_path());
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let mut out_path = extract_path.to_path_buf();
+        out_path.push(file.sanitized_name().as_path());
 
-            let comment = file.comment();
-            if !comment.is_empty() {
-                trace!("File {} comment: {}", i, comment);
-            }
+        let comment = file.comment();
+        if !comment.is_empty() {
+            trace!("File {} comment: {}", i, comment);
+        }
 
-            if (&*file.name()).ends_with('/') {
-                trace!(
-                    "File {} extracted to \"{}\"",
-                    i,
-                    out_path.as_path().display()
-                );
-                fs::create_dir_all(&out_path)?;
-            } else {
-                trace!(
-                    "File {} extracted to \"{}\" ({} bytes)",
-                    i,
-                    out_path.as_path().display(),
-                    file.size()
-                );
-                if let Some(p) = out_path.parent() {
-                    if !p.exists() {
-                        fs::create_dir_all(&p).unwrap();
-                    }
+        if (&*file.name()).ends_with('/') {
+            trace!(
+                "File {} extracted to \"{}\"",
+                i,
+                out_path.as_path().display()
+            );
+            fs::create_dir_all(&out_path)?;
+        } else {
+            trace!(
+                "File {} extracted to \"{}\" ({} bytes)",
+                i,
+                out_path.as_path().display(),
+                file.size()
+            );
+            if let Some(p) = out_path.parent() {
+                if !p.exists() {
+                    fs::create_dir_all(&p)?;
                 }
-                let mut out_file = BufWriter::new(File::create(&out_path)?);
-                io::copy(&mut file, &mut out_file)?;
             }
-            // Get and Set permissions
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
+            let mut out_file = BufWriter::new(File::create(&out_path)?);
+            io::copy(&mut file, &mut out_file)?;
+        }
+        // Get and Set permissions
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
 
-                if let Some(mode) = file.unix_mode() {
-                    fs::set_permissions(&out_path, fs::Permissions::from_mode(mode)).unwrap();
-                }
+            if let Some(mode) = file.unix_mode() {
+                fs::set_permissions(&out_path, fs::Permissions::from_mode(mode))?;
             }
         }
+    }
 
-        info!("Cleaning up");
-        if fs::remove_file(&path).is_err() {
-            info!("Failed to delete zip");
-            return Ok(());
-        }
+    Ok(())
+}
 
-        Ok(())
+fn join_url(host: &str, path: &str) -> String {
+    format!("{}/{}", host.trim_end_matches('/'), path)
+}
+
+/// Opens an `http(s)://` or `file://` URL, or a local path, for reading.
+fn open_url(url: &str) -> Result<Box<dyn Read>, Error> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let resp = ureq::get(url).call().map_err(|err| match err {
+            ureq::Error::Status(status, resp) => {
+                format_err!("{} responded with {} {}", url, status, resp.status_text())
+            }
+            ureq::Error::Transport(err) => format_err!("Couldn't download {}: {}", url, err),
+        })?;
+        return Ok(Box::new(resp.into_reader()));
+    }
+
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    let file = File::open(path).map_err(|err| format_err!("Couldn't open {}: {}", url, err))?;
+    Ok(Box::new(file))
+}
+
+/// Held while a revision is installed or removed. On Unix, the lock file is removed when it's
+/// dropped, so that they don't pile up in the install directory.
+struct RevisionLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl Drop for RevisionLock {
+    fn drop(&mut self) {
+        // removed while it's still locked, see `Fetcher::lock`
+        #[cfg(unix)]
+        {
+            if let Err(err) = fs::remove_file(&self.path) {
+                debug!("Couldn't remove {}: {}", self.path.display(), err);
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            trace!("Keeping {}", self.path.display());
+        }
     }
 }
 
-fn get_size<U: AsRef<str>>(url: U) -> Result<u64, Error> {
-    let resp = ureq::get(url.as_ref()).call();
-    match resp.header("Content-Length") {
-        Some(len) => Ok(u64::from_str(len)? / 2_u64.pow(20)),
-        None => Err(format_err!("response doesn't include the content length")),
+/// Whether `file` is still the one at `path`, rather than one that was removed since it was
+/// opened.
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(locked), Ok(current)) => locked.dev() == current.dev() && locked.ino() == current.ino(),
+        _ => false,
     }
 }
 
+/// Lock files are only removed on Unix, so the one at `path` is the one that was locked.
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, _path: &Path) -> bool {
+    true
+}
+
 fn get_project_dirs() -> Result<ProjectDirs, Error> {
     info!("Getting project dir");
     match ProjectDirs::from("", "", APP_NAME) {
@@ -197,39 +460,24 @@ fn get_project_dirs() -> Result<ProjectDirs, Error> {
     }
 }
 
-fn dl_url<R>(revision: R) -> Result<String, Error>
+/// Where the archive of `revision` is, relative to the download host.
+fn archive_path<R>(revision: R) -> Result<String, Error>
 where
     R: AsRef<str>,
 {
     #[cfg(target_os = "linux")]
-    {
-        Ok(format!(
-            "{}/chromium-browser-snapshots/Linux_x64/{}/{}.zip",
-            DEFAULT_HOST,
-            revision.as_ref(),
-            archive_name(revision.as_ref())?
-        ))
-    }
-
+    let platform_dir = "Linux_x64";
     #[cfg(target_os = "macos")]
-    {
-        Ok(format!(
-            "{}/chromium-browser-snapshots/Mac/{}/{}.zip",
-            DEFAULT_HOST,
-            revision.as_ref(),
-            archive_name(revision.as_ref())?
-        ))
-    }
-
+    let platform_dir = "Mac";
     #[cfg(windows)]
-    {
-        Ok(format!(
-            "{}/chromium-browser-snapshots/Win_x64/{}/{}.zip",
-            DEFAULT_HOST,
-            revision.as_ref(),
-            archive_name(revision.as_ref())?
-        ))
-    }
+    let platform_dir = "Win_x64";
+
+    Ok(format!(
+        "chromium-browser-snapshots/{}/{}/{}.zip",
+        platform_dir,
+        revision.as_ref(),
+        archive_name(revision.as_ref())?
+    ))
 }
 
 fn archive_name<R: AsRef<str>>(revision: R) -> Result<&'static str, Error> {
@@ -256,4 +504,118 @@ fn archive_name<R: AsRef<str>>(revision: R) -> Result<&'static str, Error> {
             Ok("chrome-win32")
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mirror holding an archive of `rev` with a fake Chrome in it, and a manifest with the
+    /// given checksum for it (or the archive's actual one).
+    fn mirror(dir: &Path, rev: &str, checksum: Option<&str>) -> FetcherOptions {
+        let archive_path = archive_path(rev).unwrap();
+        let archive_file = dir.join(&archive_path);
+        fs::create_dir_all(archive_file.parent().unwrap()).unwrap();
+
+        let mut zip = zip::ZipWriter::new(File::create(&archive_file).unwrap());
+        let binary = Path::new(archive_name(rev).unwrap()).join("chrome");
+        zip.start_file(
+            binary.to_str().unwrap(),
+            zip::write::FileOptions::default().unix_permissions(0o755),
+        )
+        .unwrap();
+        zip.write_all(b"#!/bin/sh\n").unwrap();
+        zip.finish().unwrap();
+
+        let actual = format!("{:x}", Sha256::digest(&fs::read(&archive_file).unwrap()));
+        let manifest = dir.join("SHA256SUMS");
+        fs::write(
+            &manifest,
+            format!("{}  {}\n", checksum.unwrap_or(&actual), archive_path),
+        )
+        .unwrap();
+
+        FetcherOptionsBuilder::default()
+            .host(format!("file://{}", dir.display()))
+            .manifest(Some(manifest.display().to_string()))
+            .install_dir(Some(dir.join("installed")))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn installs_verifies_and_prunes_revisions_from_a_mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = mirror(dir.path(), "1", None);
+
+        let chrome = Fetcher::with_options("1", options.clone())
+            .unwrap()
+            .run()
+            .unwrap();
+        assert!(chrome.is_file());
+        assert!(chrome.starts_with(dir.path().join("installed")));
+
+        let fetcher =
+            Fetcher::with_options("2", mirror(dir.path(), "2", Some(&"0".repeat(64)))).unwrap();
+        let err = fetcher.run().unwrap_err();
+        assert!(err.downcast::<ChecksumMismatch>().is_ok());
+        assert_eq!(vec!["1".to_string()], fetcher.cached_revisions().unwrap());
+
+        Fetcher::with_options("3", mirror(dir.path(), "3", None))
+            .unwrap()
+            .run()
+            .unwrap();
+        let mut removed = fetcher.prune(&["3"]).unwrap();
+        removed.sort();
+        assert_eq!(vec!["1".to_string()], removed);
+        assert_eq!(vec!["3".to_string()], fetcher.cached_revisions().unwrap());
+
+        // no lock files are left behind, whether installs succeeded, failed or were removed
+        #[cfg(unix)]
+        {
+            let lock_files: Vec<_> = fs::read_dir(dir.path().join("installed"))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "lock")
+                })
+                .collect();
+            assert!(lock_files.is_empty(), "{:?}", lock_files);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lock_files_are_removed_without_letting_two_holders_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = FetcherOptionsBuilder::default()
+            .install_dir(Some(dir.path().to_path_buf()))
+            .build()
+            .unwrap();
+        let lock_path = dir.path().join(format!("{}-1.lock", PLATFORM));
+
+        let first = Fetcher::with_options("1", options.clone())
+            .unwrap()
+            .lock("1")
+            .unwrap();
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let waiting = {
+            let lock_path = lock_path.clone();
+            std::thread::spawn(move || {
+                let fetcher = Fetcher::with_options("1", options).unwrap();
+                let second = fetcher.lock("1").unwrap();
+                // it's holding the new lock file, not the one that was removed
+                assert!(is_same_file(&second._file, &lock_path));
+                locked_tx.send(()).unwrap();
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(locked_rx.try_recv().is_err());
+
+        drop(first);
+        waiting.join().unwrap();
+        locked_rx.recv().unwrap();
+        assert!(!lock_path.exists());
+    }
+}
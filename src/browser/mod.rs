@@ -10,10 +10,14 @@ use serde;
 use which::which;
 
 pub use process::LaunchOptionsBuilder;
-use process::{LaunchOptions, Process};
+use process::{ChromeExited, LaunchOptions, Process};
 pub use tab::Tab;
-use transport::Transport;
+pub use transport::{CancellationHandle, ReconnectPolicyBuilder};
+use transport::recording::{Recorder, Replayer};
+use transport::{ReconnectPolicy, Transport};
+use version::{ChromeVersion, VersionCheck};
 
+use crate::browser::chrome_output::ChromeOutput;
 use crate::browser::context::Context;
 use crate::protocol::browser::methods::GetVersion;
 pub use crate::protocol::browser::methods::VersionInformationReturnObject;
@@ -22,12 +26,17 @@ use crate::protocol::{self, Event};
 use crate::util;
 use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
 
+pub mod chrome_output;
 pub mod context;
+pub mod cookies;
+pub mod discovery;
 #[cfg(feature = "fetch")]
-mod fetcher;
+pub mod fetcher;
+pub mod pool;
 pub mod process;
 pub mod tab;
 pub mod transport;
+pub mod version;
 
 /// A handle to an instance of Chrome / Chromium, which wraps a WebSocket connection to its debugging port.
 ///
@@ -67,6 +76,7 @@ pub struct Browser {
     transport: Arc<Transport>,
     tabs: Arc<Mutex<Vec<Arc<Tab>>>>,
     loop_shutdown_tx: mpsc::Sender<()>,
+    version: Option<ChromeVersion>,
 }
 
 impl Browser {
@@ -75,36 +85,114 @@ impl Browser {
     /// The browser will have its user data (aka "profile") directory stored in a temporary directory.
     /// The browser process will be killed when this struct is dropped.
     pub fn new(launch_options: LaunchOptions) -> Result<Self, Error> {
-        let process = Process::new(launch_options)?;
+        let recorder = launch_options
+            .record_protocol_to
+            .as_ref()
+            .map(Recorder::create)
+            .transpose()?;
+
+        let version_check = launch_options.version_check;
+        let mut process = Process::new(launch_options)?;
         let process_id = process.get_id();
 
-        let transport = Arc::new(Transport::new(
-            process.debug_ws_url.clone(),
-            Some(process_id),
-        )?);
+        let transport = if let Some(pipe) = process.take_debugging_pipe() {
+            Transport::with_pipe(pipe, Some(process_id), recorder)?
+        } else if let Some(recorder) = recorder {
+            Transport::with_recorder(process.debug_ws_url.clone(), Some(process_id), recorder)?
+        } else {
+            Transport::new(process.debug_ws_url.clone(), Some(process_id))?
+        };
+        let transport = Arc::new(transport.watching(process.exit_monitor()));
 
-        Self::create_browser(Some(process), transport)
+        Self::create_browser(Some(process), transport, version_check)
     }
 
     pub fn connect(debug_ws_url: String) -> Result<Self, Error> {
+        Self::connect_with_version_check(debug_ws_url, VersionCheck::default())
+    }
+
+    /// Like `connect`, but with a say in what happens if Chrome's version isn't supported.
+    pub fn connect_with_version_check(
+        debug_ws_url: String,
+        version_check: VersionCheck,
+    ) -> Result<Self, Error> {
         let transport = Arc::new(Transport::new(debug_ws_url, None)?);
         trace!("created transport");
 
-        Self::create_browser(None, transport)
+        Self::create_browser(None, transport, version_check)
+    }
+
+    /// Like `connect`, but records all protocol traffic to the JSONL file at `recording_path`.
+    pub fn connect_and_record<P: AsRef<std::path::Path>>(
+        debug_ws_url: String,
+        recording_path: P,
+    ) -> Result<Self, Error> {
+        let recorder = Recorder::create(recording_path)?;
+        let transport = Arc::new(Transport::with_recorder(debug_ws_url, None, recorder)?);
+
+        Self::create_browser(None, transport, VersionCheck::default())
+    }
+
+    /// A browser that doesn't talk to Chrome at all, but is served a recording made with
+    /// `connect_and_record` or `LaunchOptions::record_protocol_to`. Method calls are answered
+    /// by matching them against the recorded ones by method and params.
+    pub fn replay<P: AsRef<std::path::Path>>(recording_path: P) -> Result<Self, Error> {
+        let transport = Arc::new(Transport::replay(Replayer::open(recording_path)?));
+
+        Self::create_browser(None, transport, VersionCheck::default())
+    }
+
+    /// Connects to a Chrome that's already running with its debugging port at `host_port`
+    /// (e.g. "127.0.0.1:9222"), looking up the WebSocket URL via its `/json/version` endpoint.
+    ///
+    /// The targets it has open can be listed beforehand with `discovery::list_targets`.
+    pub fn discover_and_connect(host_port: &str) -> Result<Self, Error> {
+        let debug_ws_url = discovery::get_version(host_port)?.web_socket_debugger_url;
+        debug!("Discovered WebSocket URL: {}", debug_ws_url);
+
+        Self::connect(debug_ws_url)
+    }
+
+    /// Like `connect`, but survives the WebSocket dropping: the transport reconnects according
+    /// to `policy`, after which every tab re-attaches to its target and re-enables the domains
+    /// it had turned on.
+    ///
+    /// Method calls in flight while the connection is down fail with `ConnectionClosed`.
+    pub fn connect_with_reconnect(
+        debug_ws_url: String,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, Error> {
+        let transport = Arc::new(Transport::with_reconnect(debug_ws_url, None, policy)?);
+        trace!("created transport");
+
+        let reconnects_rx = transport.listen_to_reconnects();
+        let browser = Self::create_browser(None, transport, VersionCheck::default())?;
+        browser.handle_reconnects(reconnects_rx);
+
+        Ok(browser)
     }
 
-    fn create_browser(process: Option<Process>, transport: Arc<Transport>) -> Result<Self, Error> {
+    fn create_browser(
+        process: Option<Process>,
+        transport: Arc<Transport>,
+        version_check: VersionCheck,
+    ) -> Result<Self, Error> {
         let tabs = Arc::new(Mutex::new(vec![]));
 
         let (shutdown_tx, shutdown_rx) = mpsc::channel();
 
-        let browser = Self {
+        let mut browser = Self {
             process,
             tabs,
             transport,
             loop_shutdown_tx: shutdown_tx,
+            version: None,
         };
 
+        if version_check != VersionCheck::Skip {
+            browser.version = version::check_version(browser.get_version(), version_check)?;
+        }
+
         let incoming_events_rx = browser.transport.listen_to_browser_events();
 
         browser.handle_browser_level_events(
@@ -127,6 +215,24 @@ impl Browser {
         self.process.as_ref().map(Process::get_id)
     }
 
+    /// The version of Chrome, as checked on connect. `None` if the check was skipped, or the
+    /// version couldn't be determined.
+    pub fn version(&self) -> Option<&ChromeVersion> {
+        self.version.as_ref()
+    }
+
+    /// How Chrome exited, if this browser launched it and it has.
+    pub fn exited(&self) -> Option<ChromeExited> {
+        self.process
+            .as_ref()
+            .and_then(|process| process.exit_monitor().exited())
+    }
+
+    /// The last lines Chrome wrote to its stdout and stderr, if this browser launched it.
+    pub fn chrome_output(&self) -> Option<&ChromeOutput> {
+        self.process.as_ref().map(Process::output)
+    }
+
     /// The tabs are behind an `Arc` and `Mutex` because they're accessible from multiple threads
     /// (including the one that handles incoming protocol events about new or changed tabs).
     pub fn get_tabs(&self) -> &Arc<Mutex<Vec<Arc<Tab>>>> {
@@ -277,7 +383,12 @@ impl Browser {
                             Event::TargetCreated(ev) => {
                                 let target_info = ev.params.target_info;
                                 trace!("Creating target: {:?}", target_info);
-                                if target_info.target_type.is_page() {
+                                let already_known = tabs
+                                    .lock()
+                                    .unwrap()
+                                    .iter()
+                                    .any(|tab| *tab.get_target_id() == target_info.target_id);
+                                if target_info.target_type.is_page() && !already_known {
                                     match Tab::new(target_info, Arc::clone(&transport)) {
                                         Ok(new_tab) => {
                                             tabs.lock().unwrap().push(Arc::new(new_tab));
@@ -303,6 +414,15 @@ impl Browser {
                             }
                             Event::TargetDestroyed(ev) => {
                                 trace!("Target destroyed: {:?}", ev.params.target_id);
+                                tabs.lock()
+                                    .unwrap()
+                                    .retain(|tab| *tab.get_target_id() != ev.params.target_id);
+                            }
+                            Event::TargetCrashed(ev) => {
+                                warn!(
+                                    "Target crashed: {:?} ({}, error code {})",
+                                    ev.params.target_id, ev.params.status, ev.params.error_code
+                                );
                             }
                             _ => {
                                 let mut raw_event = format!("{:?}", event);
@@ -317,6 +437,38 @@ impl Browser {
         });
     }
 
+    fn handle_reconnects(&self, reconnects_rx: mpsc::Receiver<()>) {
+        let tabs = Arc::clone(&self.tabs);
+        let transport = Arc::clone(&self.transport);
+
+        std::thread::spawn(move || {
+            for _ in reconnects_rx {
+                info!("Transport reconnected, re-attaching tabs");
+                let known_tabs: Vec<Arc<Tab>> = tabs.lock().unwrap().clone();
+                for tab in known_tabs {
+                    if let Err(err) = tab.reattach() {
+                        warn!(
+                            "Couldn't re-attach to target {:?}, forgetting it: {}",
+                            tab.get_target_id(),
+                            err
+                        );
+                        tabs.lock()
+                            .unwrap()
+                            .retain(|t| t.get_target_id() != tab.get_target_id());
+                    }
+                }
+
+                // also picks up any targets that were created while we were disconnected
+                if let Err(err) =
+                    transport.call_method_on_browser(SetDiscoverTargets { discover: true })
+                {
+                    warn!("Couldn't re-enable target discovery: {}", err);
+                }
+            }
+            info!("Finished browser's reconnect handling loop");
+        });
+    }
+
     /// Call a browser method.
     ///
     /// See the `cdtp` module documentation for available methods.
@@ -0,0 +1,530 @@
+//! Sharing a few browsers between many concurrent jobs.
+//!
+//! A `BrowserPool` launches up to `browsers` Chrome processes, each with
+//! `contexts_per_browser` isolated browser contexts, and leases out tabs in them. A lease is a
+//! `TabLease`, which derefs to `Tab` and closes (or keeps, for the next lease) its tab when
+//! it's dropped.
+//!
+//! Browsers that exit, fail a health check, or have handed out `max_leases_per_browser` tabs
+//! are retired: they get no new leases, and are dropped (killing Chrome) once the last of
+//! their tabs is returned. Their replacements are launched on demand.
+
+use std::ops::Deref;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use failure::{Error, Fail};
+use log::*;
+
+use crate::browser::{Browser, Tab};
+use crate::protocol::target::methods::CreateTarget;
+
+/// What happens to a tab when its lease is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnRelease {
+    /// Close the tab. The next lease gets a fresh one.
+    Close,
+    /// Keep the tab around for the next lease in its context, which navigates it to about:blank
+    /// before handing it out.
+    ResetToBlank,
+}
+
+#[derive(Builder, Debug, Clone)]
+pub struct PoolOptions {
+    /// How many browsers to run at most. Defaults to 2.
+    #[builder(default = "2")]
+    pub browsers: usize,
+
+    /// How many browser contexts (the equivalent of incognito windows) to create in each
+    /// browser. Leased tabs are spread across them. Defaults to 4.
+    #[builder(default = "4")]
+    pub contexts_per_browser: usize,
+
+    /// How many tabs can be leased at the same time, across all browsers. Defaults to 8.
+    #[builder(default = "8")]
+    pub max_leases: usize,
+
+    /// How many tabs a browser hands out before it's retired and replaced by a fresh one.
+    /// Defaults to 100; `None` keeps browsers until they exit.
+    #[builder(default = "Some(100)")]
+    pub max_leases_per_browser: Option<usize>,
+
+    /// How long `lease` waits for a tab to be returned when `max_leases` are out. Defaults to
+    /// 30 seconds.
+    #[builder(default = "Duration::from_secs(30)")]
+    pub lease_timeout: Duration,
+
+    /// What happens to a tab when its lease is dropped. Defaults to `OnRelease::Close`.
+    #[builder(default = "OnRelease::Close")]
+    pub on_release: OnRelease,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptionsBuilder::default()
+            .build()
+            .expect("every pool option has a default")
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "No tab was returned to the pool within {:?}", timeout)]
+pub struct PoolExhausted {
+    pub timeout: Duration,
+}
+
+/// A snapshot of what's in a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    /// Browsers that can still hand out tabs.
+    pub browsers: usize,
+    /// Browsers waiting for their tabs to be returned before they're dropped.
+    pub retired_browsers: usize,
+    pub leased_tabs: usize,
+    pub idle_tabs: usize,
+}
+
+type Launcher = dyn Fn() -> Result<Browser, Error> + Send + Sync;
+
+struct PooledBrowser {
+    id: usize,
+    browser: Arc<Browser>,
+    context_ids: Vec<String>,
+    next_context: usize,
+    leased: usize,
+    handed_out: usize,
+    retired: bool,
+    /// Tabs of dropped leases, with the context they're in. They're reset to about:blank when
+    /// they're leased again.
+    idle_tabs: Vec<(Option<String>, Arc<Tab>)>,
+}
+
+impl PooledBrowser {
+    fn is_available(&self) -> bool {
+        !self.retired && self.browser.exited().is_none()
+    }
+
+    fn retire(&mut self, reason: &str) {
+        if !self.retired {
+            info!("Retiring pooled browser {}: {}", self.id, reason);
+            self.retired = true;
+            self.idle_tabs.clear();
+        }
+    }
+
+    fn next_context_id(&mut self) -> Option<String> {
+        if self.context_ids.is_empty() {
+            return None;
+        }
+        let context_id = self.context_ids[self.next_context % self.context_ids.len()].clone();
+        self.next_context += 1;
+        Some(context_id)
+    }
+}
+
+/// The browser a lease goes to, and the idle tab it gets if there is one.
+struct Picked {
+    browser_id: usize,
+    browser: Arc<Browser>,
+    context_id: Option<String>,
+    tab: Option<Arc<Tab>>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    browsers: Vec<PooledBrowser>,
+    leased: usize,
+    /// Browsers being launched by `lease` calls, which happens without holding the lock.
+    launching: usize,
+    next_id: usize,
+}
+
+impl PoolState {
+    /// Drops retired browsers that have nothing leased anymore.
+    fn remove_retired(&mut self) {
+        self.browsers
+            .retain(|pooled| !(pooled.retired && pooled.leased == 0));
+    }
+
+    fn find(&mut self, browser_id: usize) -> Option<&mut PooledBrowser> {
+        self.browsers
+            .iter_mut()
+            .find(|pooled| pooled.id == browser_id)
+    }
+}
+
+struct Shared {
+    options: PoolOptions,
+    launch: Box<Launcher>,
+    state: Mutex<PoolState>,
+    returned: Condvar,
+}
+
+/// Leases tabs in a handful of browsers to any number of threads. Cheap to clone; clones
+/// share the same browsers.
+///
+/// ```rust,no_run
+/// # use failure::Error;
+/// # fn main() -> Result<(), Error> {
+/// #
+/// use headless_chrome::{Browser, LaunchOptionsBuilder};
+/// use headless_chrome::browser::pool::{BrowserPool, PoolOptions};
+///
+/// let pool = BrowserPool::new(PoolOptions::default(), || {
+///     Browser::new(LaunchOptionsBuilder::default().build().unwrap())
+/// });
+/// let tab = pool.lease()?;
+/// tab.navigate_to("https://example.com")?;
+/// // the tab gets closed here
+/// drop(tab);
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BrowserPool {
+    shared: Arc<Shared>,
+}
+
+impl BrowserPool {
+    /// A pool that uses `launch` to start browsers, which it only does when tabs are leased.
+    pub fn new<F>(options: PoolOptions, launch: F) -> Self
+    where
+        F: Fn() -> Result<Browser, Error> + Send + Sync + 'static,
+    {
+        Self {
+            shared: Arc::new(Shared {
+                options,
+                launch: Box::new(launch),
+                state: Mutex::new(PoolState::default()),
+                returned: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn options(&self) -> &PoolOptions {
+        &self.shared.options
+    }
+
+    /// Leases a tab, waiting up to `lease_timeout` if `max_leases` tabs are already out.
+    ///
+    /// If opening the tab fails, its browser is retired and the tab is opened in another one.
+    pub fn lease(&self) -> Result<TabLease, Error> {
+        let deadline = Instant::now() + self.shared.options.lease_timeout;
+        let mut state = self.wait_for_lease_slot(deadline)?;
+        state.leased += 1;
+        drop(state);
+
+        match self.open_tab(deadline).or_else(|err| {
+            warn!(
+                "Couldn't open a pooled tab, trying another browser: {}",
+                err
+            );
+            self.open_tab(deadline)
+        }) {
+            Ok(picked) => Ok(TabLease {
+                tab: picked.tab,
+                browser: picked.browser,
+                browser_id: picked.browser_id,
+                context_id: picked.context_id,
+                shared: Arc::clone(&self.shared),
+            }),
+            Err(err) => {
+                self.shared.state.lock().unwrap().leased -= 1;
+                self.shared.returned.notify_all();
+                Err(err)
+            }
+        }
+    }
+
+    /// Pings every browser that isn't retired, retiring the ones that have exited or don't
+    /// answer. Returns how many were retired.
+    pub fn health_check(&self) -> usize {
+        let browsers: Vec<(usize, Arc<Browser>)> = {
+            let state = self.shared.state.lock().unwrap();
+            state
+                .browsers
+                .iter()
+                .filter(|pooled| !pooled.retired)
+                .map(|pooled| (pooled.id, Arc::clone(&pooled.browser)))
+                .collect()
+        };
+
+        let mut unhealthy = vec![];
+        for (browser_id, browser) in browsers {
+            if let Some(exited) = browser.exited() {
+                unhealthy.push((browser_id, exited.to_string()));
+            } else if let Err(err) = browser.get_version() {
+                unhealthy.push((browser_id, err.to_string()));
+            }
+        }
+
+        let mut state = self.shared.state.lock().unwrap();
+        for (browser_id, reason) in &unhealthy {
+            if let Some(pooled) = state.find(*browser_id) {
+                pooled.retire(reason);
+            }
+        }
+        state.remove_retired();
+        drop(state);
+        self.shared.returned.notify_all();
+        unhealthy.len()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let state = self.shared.state.lock().unwrap();
+        let mut snapshot = PoolStats {
+            leased_tabs: state.leased,
+            ..PoolStats::default()
+        };
+        for pooled in &state.browsers {
+            if pooled.retired {
+                snapshot.retired_browsers += 1;
+            } else {
+                snapshot.browsers += 1;
+            }
+            snapshot.idle_tabs += pooled.idle_tabs.len();
+        }
+        snapshot
+    }
+
+    fn wait_for_lease_slot(&self, deadline: Instant) -> Result<MutexGuard<'_, PoolState>, Error> {
+        let mut state = self.shared.state.lock().unwrap();
+        while state.leased >= self.shared.options.max_leases {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PoolExhausted {
+                    timeout: self.shared.options.lease_timeout,
+                }
+                .into());
+            }
+            state = self
+                .shared
+                .returned
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+        Ok(state)
+    }
+
+    /// Picks a browser (launching one if there's room for it) and opens a tab in it, unless
+    /// it has an idle one, which is reset to about:blank instead.
+    fn open_tab(&self, deadline: Instant) -> Result<Picked, Error> {
+        let mut picked = self.pick_browser(deadline)?;
+
+        let opened = match picked.tab.take() {
+            Some(tab) => match tab.navigate_to("about:blank") {
+                Ok(_) => Ok(tab),
+                Err(err) => {
+                    debug!(
+                        "Couldn't reset pooled tab {}, opening another one: {}",
+                        tab.get_target_id(),
+                        err
+                    );
+                    if let Err(err) = tab.close() {
+                        debug!("Couldn't close pooled tab {}: {}", tab.get_target_id(), err);
+                    }
+                    Self::new_tab(&picked)
+                }
+            },
+            None => Self::new_tab(&picked),
+        };
+
+        let mut state = self.shared.state.lock().unwrap();
+        match opened {
+            Ok(tab) => {
+                if let Some(pooled) = state.find(picked.browser_id) {
+                    pooled.handed_out += 1;
+                    let max = self.shared.options.max_leases_per_browser;
+                    if max.is_some_and(|max| pooled.handed_out >= max) {
+                        pooled.retire("it has handed out as many tabs as it may");
+                    }
+                }
+                picked.tab = Some(tab);
+                Ok(picked)
+            }
+            Err(err) => {
+                if let Some(pooled) = state.find(picked.browser_id) {
+                    pooled.leased -= 1;
+                    pooled.retire(&format!("couldn't open a tab: {}", err));
+                }
+                state.remove_retired();
+                Err(err)
+            }
+        }
+    }
+
+    fn new_tab(picked: &Picked) -> Result<Arc<Tab>, Error> {
+        picked.browser.new_tab_with_options(CreateTarget {
+            url: "about:blank",
+            width: None,
+            height: None,
+            browser_context_id: picked.context_id.as_deref(),
+            enable_begin_frame_control: None,
+        })
+    }
+
+    /// Finds the available browser with the fewest tabs leased, or launches a new one if fewer
+    /// than `browsers` are running. Either way, it's counted as having one more tab leased.
+    ///
+    /// If the only browsers are being launched by other leases, it waits for them until
+    /// `deadline`.
+    fn pick_browser(&self, deadline: Instant) -> Result<Picked, Error> {
+        let mut state = self.shared.state.lock().unwrap();
+        let index = loop {
+            for pooled in &mut state.browsers {
+                if let Some(exited) = pooled.browser.exited() {
+                    pooled.retire(&exited.to_string());
+                }
+            }
+            state.remove_retired();
+
+            let running = state
+                .browsers
+                .iter()
+                .filter(|pooled| !pooled.retired)
+                .count();
+
+            if running + state.launching < self.shared.options.browsers.max(1) {
+                state.launching += 1;
+                let browser_id = state.next_id;
+                state.next_id += 1;
+                drop(state);
+
+                let launched = self.launch_browser(browser_id);
+
+                state = self.shared.state.lock().unwrap();
+                state.launching -= 1;
+                // leases waiting for this launch either get the browser or launch another one
+                self.shared.returned.notify_all();
+                state.browsers.push(launched?);
+                break state.browsers.len() - 1;
+            }
+
+            let available = state
+                .browsers
+                .iter()
+                .enumerate()
+                .filter(|(_, pooled)| pooled.is_available())
+                .min_by_key(|(_, pooled)| pooled.leased)
+                .map(|(index, _)| index);
+            if let Some(index) = available {
+                break index;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PoolExhausted {
+                    timeout: self.shared.options.lease_timeout,
+                }
+                .into());
+            }
+            state = self
+                .shared
+                .returned
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        };
+
+        let pooled = &mut state.browsers[index];
+        pooled.leased += 1;
+        let (context_id, tab) = match pooled.idle_tabs.pop() {
+            Some((context_id, tab)) => (context_id, Some(tab)),
+            None => (pooled.next_context_id(), None),
+        };
+        Ok(Picked {
+            browser_id: pooled.id,
+            browser: Arc::clone(&pooled.browser),
+            context_id,
+            tab,
+        })
+    }
+
+    fn launch_browser(&self, browser_id: usize) -> Result<PooledBrowser, Error> {
+        debug!("Launching pooled browser {}", browser_id);
+        let browser = (self.shared.launch)()?;
+        let context_ids = (0..self.shared.options.contexts_per_browser)
+            .map(|_| {
+                browser
+                    .new_context()
+                    .map(|context| context.get_id().to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(PooledBrowser {
+            id: browser_id,
+            browser: Arc::new(browser),
+            context_ids,
+            next_context: 0,
+            leased: 0,
+            handed_out: 0,
+            retired: false,
+            idle_tabs: vec![],
+        })
+    }
+}
+
+/// A tab leased from a `BrowserPool`. It's closed, or kept to be reset to about:blank for the
+/// next lease, when this is dropped, according to the pool's `on_release` option.
+pub struct TabLease {
+    tab: Option<Arc<Tab>>,
+    browser: Arc<Browser>,
+    browser_id: usize,
+    context_id: Option<String>,
+    shared: Arc<Shared>,
+}
+
+impl TabLease {
+    pub fn tab(&self) -> &Arc<Tab> {
+        self.tab.as_ref().expect("the tab is only taken on drop")
+    }
+
+    /// The browser the tab is in.
+    pub fn browser(&self) -> &Browser {
+        &self.browser
+    }
+
+    /// The ID of the browser context the tab is in, if the pool creates any.
+    pub fn context_id(&self) -> Option<&str> {
+        self.context_id.as_deref()
+    }
+}
+
+impl Deref for TabLease {
+    type Target = Tab;
+
+    fn deref(&self) -> &Tab {
+        self.tab()
+    }
+}
+
+impl Drop for TabLease {
+    fn drop(&mut self) {
+        let Some(tab) = self.tab.take() else {
+            return;
+        };
+        // the tab is reset by the lease that gets it next, so that dropping doesn't block on it
+        let keep = self.shared.options.on_release == OnRelease::ResetToBlank
+            && self.browser.exited().is_none();
+        if !keep {
+            if let Err(err) = tab.close() {
+                debug!("Couldn't close pooled tab {}: {}", tab.get_target_id(), err);
+            }
+        }
+
+        let mut state = self.shared.state.lock().unwrap();
+        state.leased -= 1;
+        if let Some(pooled) = state.find(self.browser_id) {
+            pooled.leased -= 1;
+            if keep && !pooled.retired {
+                pooled.idle_tabs.push((self.context_id.take(), tab));
+            }
+        }
+        state.remove_retired();
+        drop(state);
+        // both leases waiting for a slot and ones waiting for a browser wait on `returned`
+        self.shared.returned.notify_all();
+    }
+}
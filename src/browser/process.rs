@@ -5,27 +5,46 @@ use rand::thread_rng;
 use regex::Regex;
 
 use std::{
-    borrow::BorrowMut,
-    ffi::OsStr,
-    io::{prelude::*, BufRead, BufReader},
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    fmt,
+    fs::File,
     net,
-    process::{Child, Command, Stdio},
-    time::Duration,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex, Weak},
+    thread,
+    time::{Duration, Instant},
 };
 
 #[cfg(windows)]
 use winreg::{enums::HKEY_LOCAL_MACHINE, RegKey};
 
 #[cfg(feature = "fetch")]
-use super::fetcher::{self, Fetcher};
+use super::fetcher::{self, Fetcher, FetcherOptions};
 #[cfg(not(feature = "fetch"))]
 use crate::browser::default_executable;
 
-use crate::util;
+use super::chrome_output::ChromeOutput;
+use super::version::VersionCheck;
 
 pub struct Process {
     child_process: TemporaryProcess,
+    /// Empty when Chrome was launched with a remote debugging pipe instead of a port.
     pub debug_ws_url: String,
+    debugging_pipe: Option<DebuggingPipe>,
+    output: ChromeOutput,
+    exit_monitor: ExitMonitor,
+}
+
+/// Our ends of the pipes Chrome speaks the DevTools protocol over when launched with
+/// `--remote-debugging-pipe`. Chrome reads messages from its fd 3 and writes to its fd 4,
+/// each message terminated by a NUL byte.
+#[derive(Debug)]
+pub struct DebuggingPipe {
+    /// Connected to Chrome's fd 4.
+    pub reader: File,
+    /// Connected to Chrome's fd 3.
+    pub writer: File,
 }
 
 #[derive(Debug, Fail)]
@@ -36,6 +55,9 @@ enum ChromeLaunchError {
     NoAvailablePorts,
     #[fail(display = "The chosen debugging port is already in use")]
     DebugPortInUse,
+    #[cfg(not(unix))]
+    #[fail(display = "A remote debugging pipe is only supported on Unix")]
+    DebuggingPipeUnsupported,
 }
 
 #[cfg(windows)]
@@ -47,12 +69,132 @@ pub(crate) fn get_chrome_path_from_registry() -> Option<std::path::PathBuf> {
         .ok()
 }
 
-struct TemporaryProcess(Child);
+/// How many of Chrome's last output lines `ChromeExited` carries.
+const EXIT_OUTPUT_LINES: usize = 50;
+
+/// Chrome exited while we were still using it.
+#[derive(Debug)]
+pub struct ChromeExited {
+    pub status: ExitStatus,
+    /// The last lines Chrome wrote to its stdout and stderr, oldest first.
+    pub last_output: Vec<String>,
+}
+
+impl ChromeExited {
+    /// The signal that killed Chrome, if that's how it exited.
+    #[cfg(unix)]
+    pub fn signal(&self) -> Option<i32> {
+        use std::os::unix::process::ExitStatusExt;
+        self.status.signal()
+    }
+}
+
+impl fmt::Display for ChromeExited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Chrome exited unexpectedly ({})", self.status)?;
+        if !self.last_output.is_empty() {
+            write!(f, ". Its last output was:\n{}", self.last_output.join("\n"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Fail for ChromeExited {}
+
+/// Keeps an eye on a Chrome process without owning it, so that whoever finds the connection
+/// to it closed can tell whether (and how) it exited.
+#[derive(Clone, Debug)]
+pub struct ExitMonitor {
+    exit_status: Arc<Mutex<Option<ExitStatus>>>,
+    output: ChromeOutput,
+}
+
+impl ExitMonitor {
+    /// How long it takes to notice that Chrome exited.
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    fn start(child: &Arc<Mutex<Option<Child>>>, output: ChromeOutput) -> Self {
+        let monitor = Self {
+            exit_status: Arc::new(Mutex::new(None)),
+            output,
+        };
+        let child = Arc::downgrade(child);
+        let exit_status = Arc::clone(&monitor.exit_status);
+        thread::spawn(move || Self::watch(&child, &exit_status));
+        monitor
+    }
+
+    fn watch(child: &Weak<Mutex<Option<Child>>>, exit_status: &Mutex<Option<ExitStatus>>) {
+        loop {
+            thread::sleep(Self::POLL_INTERVAL);
+            let Some(child) = child.upgrade() else {
+                return;
+            };
+            let mut child = child.lock().unwrap();
+            // gone when we killed it ourselves
+            let Some(child) = child.as_mut() else {
+                return;
+            };
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    warn!("Chrome (PID {}) exited: {}", child.id(), status);
+                    *exit_status.lock().unwrap() = Some(status);
+                    return;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    warn!("Can't tell whether Chrome is still running: {}", err);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// How Chrome exited, if it has.
+    pub fn exited(&self) -> Option<ChromeExited> {
+        let status = (*self.exit_status.lock().unwrap())?;
+        Some(ChromeExited {
+            status,
+            last_output: self.output.last_lines(EXIT_OUTPUT_LINES),
+        })
+    }
+
+    /// Like `exited`, but gives Chrome up to `timeout` to finish exiting. Useful right after
+    /// the connection to it closed, which tends to happen just before the exit is noticed.
+    pub fn wait_for_exit(&self, timeout: Duration) -> Option<ChromeExited> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(exited) = self.exited() {
+                return Some(exited);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            thread::sleep(Self::POLL_INTERVAL / 2);
+        }
+    }
+}
+
+struct TemporaryProcess {
+    pid: u32,
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+impl TemporaryProcess {
+    fn new(child: Child) -> Self {
+        Self {
+            pid: child.id(),
+            child: Arc::new(Mutex::new(Some(child))),
+        }
+    }
+}
 
 impl Drop for TemporaryProcess {
     fn drop(&mut self) {
-        info!("Killing Chrome. PID: {}", self.0.id());
-        self.0.kill().and_then(|_| self.0.wait()).ok();
+        info!("Killing Chrome. PID: {}", self.pid);
+        if let Some(mut child) = self.child.lock().unwrap().take() {
+            child.kill().and_then(|()| child.wait()).ok();
+        }
     }
 }
 
@@ -84,12 +226,136 @@ pub struct LaunchOptions<'a> {
     #[builder(default)]
     extensions: Vec<&'a OsStr>,
 
+    /// Extra command line arguments to pass to Chrome, after the ones this crate passes itself.
+    #[builder(default)]
+    args: Vec<&'a OsStr>,
+
+    /// The profile directory to launch Chrome with.
+    ///
+    /// If unspecified, each launch gets a fresh temporary directory, so nothing (cookies, local
+    /// storage, caches) carries over between browsers.
+    #[builder(default = "None")]
+    user_data_dir: Option<std::path::PathBuf>,
+
+    /// The size of the browser window as (width, height), in pixels.
+    #[builder(default = "None")]
+    window_size: Option<(u32, u32)>,
+
+    /// A proxy for Chrome to send its requests through, e.g. "http://127.0.0.1:8080" or
+    /// "socks5://127.0.0.1:1080".
+    #[builder(default = "None")]
+    proxy_server: Option<&'a str>,
+
+    /// Hosts that are reached without going through `proxy_server`, e.g. "localhost" or
+    /// "*.example.com".
+    #[builder(default)]
+    proxy_bypass_list: Vec<&'a str>,
+
+    /// The UI language Chrome should use, which also determines its `Accept-Language` header.
+    /// For example "en-US" or "de".
+    #[builder(default = "None")]
+    lang: Option<&'a str>,
+
+    /// Whether to run Chrome's renderers in its sandbox. Defaults to true.
+    ///
+    /// Chrome usually refuses to start as root (e.g. inside Docker) unless this is false.
+    #[builder(default = "true")]
+    sandbox: bool,
+
+    /// Whether to use the setuid sandbox on Linux. Defaults to true.
+    #[builder(default = "true")]
+    setuid_sandbox: bool,
+
+    /// Environment variables to set for the Chrome process, on top of the ones it inherits.
+    #[builder(default)]
+    envs: HashMap<String, String>,
+
+    /// Talk to Chrome over a pair of pipes (`--remote-debugging-pipe`) instead of a WebSocket.
+    ///
+    /// No debugging port is opened, so nothing else on the machine can connect to the browser.
+    /// Only supported on Unix, and only by the synchronous `Browser`. Defaults to false.
+    #[builder(default = "false")]
+    remote_debugging_pipe: bool,
+
+    /// Record all protocol traffic with the browser to this JSONL file, so that it can be
+    /// replayed later with `Browser::replay` or `ChromeBrowser::replay`.
+    #[builder(default = "None")]
+    pub(crate) record_protocol_to: Option<std::path::PathBuf>,
+
+    /// What to do if Chrome's version turns out not to be supported, see `browser::version`.
+    /// Defaults to logging a warning.
+    #[builder(default)]
+    pub(crate) version_check: VersionCheck,
+
     /// The revision of chrome to use
     ///
     /// By default, we'll use a revision guaranteed to work with our API.
     #[cfg(feature = "fetch")]
     #[builder(default = "self.default_revision()")]
     revision: &'static str,
+
+    /// Where to download that revision from, and where to install it.
+    #[cfg(feature = "fetch")]
+    #[builder(default)]
+    fetcher_options: FetcherOptions,
+}
+
+impl LaunchOptions<'_> {
+    /// Every command line argument Chrome gets launched with.
+    fn args_for_chrome(
+        &self,
+        debugging_option: &str,
+        user_data_dir: &std::path::Path,
+    ) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            debugging_option.into(),
+            "--verbose".into(),
+            //  "--disable-gpu",
+            "--no-first-run".into(),
+        ];
+
+        let mut data_dir_option = OsString::from("--user-data-dir=");
+        data_dir_option.push(user_data_dir);
+        args.push(data_dir_option);
+
+        if self.headless {
+            args.push("--headless".into());
+        }
+
+        if let Some((width, height)) = self.window_size {
+            args.push(format!("--window-size={},{}", width, height).into());
+        }
+
+        if let Some(proxy_server) = self.proxy_server {
+            args.push(format!("--proxy-server={}", proxy_server).into());
+        }
+
+        if !self.proxy_bypass_list.is_empty() {
+            args.push(format!("--proxy-bypass-list={}", self.proxy_bypass_list.join(";")).into());
+        }
+
+        if let Some(lang) = self.lang {
+            args.push(format!("--lang={}", lang).into());
+        }
+
+        if !self.sandbox {
+            args.push("--no-sandbox".into());
+        }
+
+        if !self.setuid_sandbox {
+            args.push("--disable-setuid-sandbox".into());
+        }
+
+        for extension in &self.extensions {
+            let mut extension_option = OsString::from("--load-extension=");
+            extension_option.push(extension);
+            args.push(extension_option);
+        }
+
+        args.extend(self.args.iter().map(|&arg| arg.to_owned()));
+
+        args
+    }
 }
 
 #[cfg(feature = "fetch")]
@@ -104,7 +370,10 @@ impl Process {
         if launch_options.path.is_none() {
             #[cfg(feature = "fetch")]
             {
-                let fetch = Fetcher::new(launch_options.revision)?;
+                let fetch = Fetcher::with_options(
+                    launch_options.revision,
+                    launch_options.fetcher_options.clone(),
+                )?;
                 launch_options.path = Some(fetch.run()?);
             }
             #[cfg(not(feature = "fetch"))]
@@ -113,9 +382,20 @@ impl Process {
             }
         }
 
-        let mut process = Self::start_process(&launch_options)?;
+        let (mut process, debugging_pipe, mut output) = Self::start_process(&launch_options)?;
 
-        info!("Started Chrome. PID: {}", process.0.id());
+        info!("Started Chrome. PID: {}", process.pid);
+
+        if debugging_pipe.is_some() {
+            let exit_monitor = ExitMonitor::start(&process.child, output.clone());
+            return Ok(Self {
+                child_process: process,
+                debug_ws_url: String::new(),
+                debugging_pipe,
+                output,
+                exit_monitor,
+            });
+        }
 
         let url;
         let mut attempts = 0;
@@ -124,7 +404,7 @@ impl Process {
                 return Err(ChromeLaunchError::NoAvailablePorts {}.into());
             }
 
-            match Self::ws_url_from_output(process.0.borrow_mut()) {
+            match Self::ws_url_from_output(&output) {
                 Ok(debug_ws_url) => {
                     url = debug_ws_url;
                     debug!("Found debugging WS URL: {:?}", url);
@@ -133,7 +413,9 @@ impl Process {
                 Err(error) => {
                     trace!("Problem getting WebSocket URL from Chrome: {}", error);
                     if launch_options.port.is_none() {
-                        process = Self::start_process(&launch_options)?;
+                        let (new_process, _, new_output) = Self::start_process(&launch_options)?;
+                        process = new_process;
+                        output = new_output;
                     } else {
                         return Err(error);
                     }
@@ -147,49 +429,45 @@ impl Process {
             attempts += 1;
         }
 
+        let exit_monitor = ExitMonitor::start(&process.child, output.clone());
         Ok(Self {
             child_process: process,
             debug_ws_url: url,
+            debugging_pipe: None,
+            output,
+            exit_monitor,
         })
     }
 
-    fn start_process(launch_options: &LaunchOptions) -> Result<TemporaryProcess, Error> {
-        let debug_port = if let Some(port) = launch_options.port {
-            port
+    fn start_process(
+        launch_options: &LaunchOptions,
+    ) -> Result<(TemporaryProcess, Option<DebuggingPipe>, ChromeOutput), Error> {
+        let debugging_option = if launch_options.remote_debugging_pipe {
+            "--remote-debugging-pipe".to_string()
         } else {
-            get_available_port().ok_or(ChromeLaunchError::NoAvailablePorts {})?
+            let debug_port = if let Some(port) = launch_options.port {
+                port
+            } else {
+                get_available_port().ok_or(ChromeLaunchError::NoAvailablePorts {})?
+            };
+            format!("--remote-debugging-port={}", debug_port)
         };
-        let port_option = format!("--remote-debugging-port={}", debug_port);
 
         // NOTE: picking random data dir so that each a new browser instance is launched
         // (see man google-chrome)
-        let user_data_dir = ::tempfile::Builder::new()
-            .prefix("rust-headless-chrome-profile")
-            .tempdir()?;
-        let data_dir_option = format!("--user-data-dir={}", user_data_dir.path().to_str().unwrap());
-
-        trace!("Chrome will have profile: {}", data_dir_option);
-
-        let mut args = vec![
-            port_option.as_str(),
-            "--verbose",
-            //  "--disable-gpu",
-            "--no-first-run",
-            data_dir_option.as_str(),
-            //            "--window-size=1920,1080"
-        ];
-
-        if launch_options.headless {
-            args.extend(&["--headless"]);
-        }
+        let temp_user_data_dir;
+        let user_data_dir = if let Some(dir) = &launch_options.user_data_dir {
+            dir.as_path()
+        } else {
+            temp_user_data_dir = ::tempfile::Builder::new()
+                .prefix("rust-headless-chrome-profile")
+                .tempdir()?;
+            temp_user_data_dir.path()
+        };
 
-        let extension_args: Vec<String> = launch_options
-            .extensions
-            .iter()
-            .map(|e| format!("--load-extension={}", e.to_str().unwrap()))
-            .collect();
+        trace!("Chrome will have profile: {}", user_data_dir.display());
 
-        args.extend(extension_args.iter().map(String::as_str));
+        let args = launch_options.args_for_chrome(&debugging_option, user_data_dir);
 
         let path = launch_options
             .path
@@ -198,18 +476,45 @@ impl Process {
 
         info!("Launching Chrome binary at {:?}", &path);
 
-        let process = TemporaryProcess(
-            Command::new(&path)
-                .args(&args)
-                .stderr(Stdio::piped())
-                .spawn()?,
-        );
-        Ok(process)
+        let mut command = Command::new(&path);
+        command.args(&args).envs(&launch_options.envs);
+
+        // Chrome's output gets drained for as long as it runs, so that it can't fill up the
+        // pipes and block it
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let (mut child, debugging_pipe) = if launch_options.remote_debugging_pipe {
+            let (child, debugging_pipe) = spawn_with_debugging_pipe(&mut command)?;
+            (child, Some(debugging_pipe))
+        } else {
+            (command.spawn()?, None)
+        };
+
+        let output = ChromeOutput::default();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let process = TemporaryProcess::new(child);
+        if let Some(stdout) = stdout {
+            output.drain(stdout, "stdout")?;
+        }
+        if let Some(stderr) = stderr {
+            output.drain(stderr, "stderr")?;
+        }
+
+        Ok((process, debugging_pipe, output))
+    }
+
+    #[cfg(test)]
+    fn ws_url_from_reader<R>(reader: std::io::BufReader<R>) -> Result<Option<String>, Error>
+    where
+        R: std::io::Read,
+    {
+        use std::io::BufRead;
+        Self::ws_url_from_lines(reader.lines().filter_map(Result::ok))
     }
 
-    fn ws_url_from_reader<R>(reader: BufReader<R>) -> Result<Option<String>, Error>
+    fn ws_url_from_lines<I>(lines: I) -> Result<Option<String>, Error>
     where
-        R: Read,
+        I: Iterator<Item = String>,
     {
         let port_taken_re = Regex::new(r"ERROR.*bind").unwrap();
 
@@ -221,10 +526,7 @@ impl Process {
             Some(cap.into())
         };
 
-        for line in reader.lines() {
-            let chrome_output = line?;
-            trace!("Chrome output: {}", chrome_output);
-
+        for chrome_output in lines {
             if port_taken_re.is_match(&chrome_output) {
                 return Err(ChromeLaunchError::DebugPortInUse {}.into());
             }
@@ -237,31 +539,97 @@ impl Process {
         Ok(None)
     }
 
-    fn ws_url_from_output(child_process: &mut Child) -> Result<String, Error> {
-        let chrome_output_result = util::Wait::with_timeout(Duration::from_secs(10)).until(|| {
-            let my_stderr = BufReader::new(child_process.stderr.as_mut().unwrap());
-            match Self::ws_url_from_reader(my_stderr) {
-                Ok(output_option) => {
-                    if let Some(output) = output_option {
-                        Some(Ok(output))
-                    } else {
-                        None
-                    }
-                }
-                Err(err) => Some(Err(err)),
-            }
-        });
+    fn ws_url_from_output(output: &ChromeOutput) -> Result<String, Error> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let (lines_so_far, new_lines) = output.subscribe();
+        let lines = lines_so_far.into_iter().chain(std::iter::from_fn(|| {
+            let timeout = deadline.checked_duration_since(Instant::now())?;
+            new_lines.recv_timeout(timeout).ok()
+        }));
 
-        if let Ok(output_result) = chrome_output_result {
-            output_result
+        if let Some(url) = Self::ws_url_from_lines(lines)? {
+            Ok(url)
         } else {
             Err(ChromeLaunchError::PortOpenTimeout {}.into())
         }
     }
 
     pub fn get_id(&self) -> u32 {
-        self.child_process.0.id()
+        self.child_process.pid
+    }
+
+    /// What Chrome has written to its stdout and stderr lately.
+    pub fn output(&self) -> &ChromeOutput {
+        &self.output
+    }
+
+    /// A handle for finding out whether Chrome has exited, and how.
+    pub fn exit_monitor(&self) -> ExitMonitor {
+        self.exit_monitor.clone()
     }
+
+    /// Hands over the pipe Chrome was launched with, if any. Only the first call returns it.
+    pub fn take_debugging_pipe(&mut self) -> Option<DebuggingPipe> {
+        self.debugging_pipe.take()
+    }
+}
+
+#[cfg(unix)]
+fn spawn_with_debugging_pipe(command: &mut Command) -> Result<(Child, DebuggingPipe), Error> {
+    use std::os::unix::io::{FromRawFd, RawFd};
+    use std::os::unix::process::CommandExt;
+
+    fn cloexec_pipe() -> std::io::Result<(RawFd, RawFd)> {
+        let mut fds = [0; 2];
+        unsafe {
+            if libc::pipe(fds.as_mut_ptr()) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            libc::fcntl(fds[0], libc::F_SETFD, libc::FD_CLOEXEC);
+            libc::fcntl(fds[1], libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+        Ok((fds[0], fds[1]))
+    }
+
+    let (chrome_reads, writer) = cloexec_pipe()?;
+    let (reader, chrome_writes) = cloexec_pipe()?;
+    let debugging_pipe = unsafe {
+        DebuggingPipe {
+            reader: File::from_raw_fd(reader),
+            writer: File::from_raw_fd(writer),
+        }
+    };
+
+    unsafe {
+        command.pre_exec(move || {
+            // copy both ends above 4 first, so that neither gets clobbered by the other's dup2
+            let read_fd = libc::fcntl(chrome_reads, libc::F_DUPFD, 5);
+            let write_fd = libc::fcntl(chrome_writes, libc::F_DUPFD, 5);
+            if read_fd < 0
+                || write_fd < 0
+                || libc::dup2(read_fd, 3) < 0
+                || libc::dup2(write_fd, 4) < 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            libc::close(read_fd);
+            libc::close(write_fd);
+            Ok(())
+        });
+    }
+
+    let spawn_result = command.spawn();
+    unsafe {
+        libc::close(chrome_reads);
+        libc::close(chrome_writes);
+    }
+
+    Ok((spawn_result?, debugging_pipe))
+}
+
+#[cfg(not(unix))]
+fn spawn_with_debugging_pipe(_command: &mut Command) -> Result<(Child, DebuggingPipe), Error> {
+    Err(ChromeLaunchError::DebuggingPipeUnsupported {}.into())
 }
 
 fn get_available_port() -> Option<u16> {
@@ -278,6 +646,7 @@ fn port_is_available(port: u16) -> bool {
 mod tests {
     use super::*;
     use crate::browser::default_executable;
+    use std::io::BufReader;
     use std::sync::{Once, ONCE_INIT};
     use std::thread;
 
@@ -301,6 +670,159 @@ mod tests {
         info!("{:?}", chrome.debug_ws_url);
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn can_launch_chrome_with_debugging_pipe() {
+        setup();
+        let mut chrome = super::Process::new(
+            LaunchOptionsBuilder::default()
+                .path(Some(default_executable().unwrap()))
+                .remote_debugging_pipe(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(chrome.debug_ws_url.is_empty());
+        assert!(chrome.take_debugging_pipe().is_some());
+        assert!(chrome.take_debugging_pipe().is_none());
+    }
+
+    #[test]
+    fn passes_launch_options_as_args() {
+        let options = LaunchOptionsBuilder::default()
+            .window_size(Some((1280, 720)))
+            .proxy_server(Some("socks5://127.0.0.1:1080"))
+            .proxy_bypass_list(vec!["localhost", "*.example.com"])
+            .lang(Some("de"))
+            .sandbox(false)
+            .args(vec![OsStr::new("--mute-audio")])
+            .build()
+            .unwrap();
+        let args = options.args_for_chrome(
+            "--remote-debugging-port=9222",
+            std::path::Path::new("/tmp/profile"),
+        );
+
+        let expected: Vec<OsString> = vec![
+            "--remote-debugging-port=9222",
+            "--verbose",
+            "--no-first-run",
+            "--user-data-dir=/tmp/profile",
+            "--headless",
+            "--window-size=1280,720",
+            "--proxy-server=socks5://127.0.0.1:1080",
+            "--proxy-bypass-list=localhost;*.example.com",
+            "--lang=de",
+            "--no-sandbox",
+            "--mute-audio",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(expected, args);
+    }
+
+    /// A shell script standing in for Chrome: it prints a WebSocket URL like Chrome does, then
+    /// runs `rest`.
+    #[cfg(unix)]
+    fn fake_chrome(dir: &tempfile::TempDir, rest: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.path().join("chrome");
+        std::fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\n\
+                 echo 'DevTools listening on ws://127.0.0.1:9222/devtools/browser/abc' >&2\n\
+                 {}\n",
+                rest
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn keeps_draining_output_after_finding_ws_url() {
+        setup();
+        let dir = tempfile::tempdir().unwrap();
+        let fake_chrome = fake_chrome(
+            &dir,
+            "echo 'on stdout'\n\
+             echo 'after the URL' >&2\n\
+             exec sleep 30",
+        );
+
+        let chrome = super::Process::new(
+            LaunchOptionsBuilder::default()
+                .path(Some(fake_chrome))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            "ws://127.0.0.1:9222/devtools/browser/abc",
+            chrome.debug_ws_url
+        );
+
+        crate::util::Wait::with_timeout(Duration::from_secs(5))
+            .until(|| {
+                let lines = chrome.output().lines();
+                if lines.contains(&"on stdout".to_string())
+                    && lines.contains(&"after the URL".to_string())
+                {
+                    Some(())
+                } else {
+                    None
+                }
+            })
+            .unwrap();
+        assert!(!chrome.output().is_closed());
+        assert!(chrome.exit_monitor().exited().is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn reports_how_chrome_exited() {
+        setup();
+        let dir = tempfile::tempdir().unwrap();
+        let exits_with_code = fake_chrome(&dir, "sleep 0.2\necho 'out of memory' >&2\nexit 3");
+        let chrome = super::Process::new(
+            LaunchOptionsBuilder::default()
+                .path(Some(exits_with_code))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let exited = chrome
+            .exit_monitor()
+            .wait_for_exit(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(Some(3), exited.status.code());
+        assert_eq!(None, exited.signal());
+        assert!(exited.last_output.contains(&"out of memory".to_string()));
+        assert!(exited.to_string().contains("out of memory"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let killed = fake_chrome(&dir, "sleep 0.2\nkill -SEGV $$");
+        let chrome = super::Process::new(
+            LaunchOptionsBuilder::default()
+                .path(Some(killed))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let exited = chrome
+            .exit_monitor()
+            .wait_for_exit(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(Some(libc::SIGSEGV), exited.signal());
+    }
+
     #[test]
     fn handle_errors_in_chrome_output() {
         setup();
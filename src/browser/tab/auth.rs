@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::protocol::network::{events::AuthChallenge, methods};
+
+/// A username and password to answer HTTP authentication challenges with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+}
+
+/// Picks the credentials for a challenge, see `CredentialsProvider::Callback`.
+pub type CredentialsCallback = Arc<dyn Fn(&AuthChallenge) -> Option<Credentials> + Send + Sync>;
+
+/// How a tab answers HTTP authentication challenges, from servers (basic or digest) as well as
+/// from proxies.
+#[derive(Clone, Default)]
+pub enum CredentialsProvider {
+    /// Leave challenges to Chrome, which cancels them when headless.
+    #[default]
+    BrowserDefault,
+    /// Answer every challenge with these.
+    Static(Credentials),
+    /// Ask the callback, which can tell challenges apart by their origin, scheme and realm.
+    /// Returning `None` cancels the challenge, so that the page gets the 401 (or 407)
+    /// response. It's called on the thread handling the tab's events, so it mustn't wait on the
+    /// tab itself.
+    Callback(CredentialsCallback),
+}
+
+impl fmt::Debug for CredentialsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CredentialsProvider::BrowserDefault => write!(f, "BrowserDefault"),
+            CredentialsProvider::Static(credentials) => {
+                write!(f, "Static({:?})", credentials.username)
+            }
+            CredentialsProvider::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
+
+/// The answer to an authentication challenge.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthResponse {
+    BrowserDefault,
+    Cancel,
+    Provide(Credentials),
+}
+
+impl CredentialsProvider {
+    pub fn respond(&self, challenge: &AuthChallenge) -> AuthResponse {
+        match self {
+            CredentialsProvider::BrowserDefault => AuthResponse::BrowserDefault,
+            CredentialsProvider::Static(credentials) => AuthResponse::Provide(credentials.clone()),
+            CredentialsProvider::Callback(callback) => match callback(challenge) {
+                Some(credentials) => AuthResponse::Provide(credentials),
+                None => AuthResponse::Cancel,
+            },
+        }
+    }
+}
+
+/// The requests a tab has given credentials to. Chrome challenges a request again when its
+/// credentials are rejected, which gets cancelled rather than answered with the same ones.
+#[derive(Debug, Default)]
+pub struct AnsweredChallenges {
+    request_ids: HashSet<String>,
+}
+
+impl AnsweredChallenges {
+    /// Answers the challenge for `request_id` with `provider`, or cancels it if the request was
+    /// given credentials before.
+    pub fn respond(
+        &mut self,
+        provider: &CredentialsProvider,
+        request_id: &str,
+        challenge: &AuthChallenge,
+    ) -> AuthResponse {
+        if self.request_ids.remove(request_id) {
+            return AuthResponse::Cancel;
+        }
+        let response = provider.respond(challenge);
+        if let AuthResponse::Provide(_) = response {
+            self.request_ids.insert(request_id.to_string());
+        }
+        response
+    }
+}
+
+impl AuthResponse {
+    pub fn as_method_param(&self) -> methods::AuthChallengeResponse<'_> {
+        match self {
+            AuthResponse::BrowserDefault => methods::AuthChallengeResponse {
+                response: "Default",
+                username: None,
+                password: None,
+            },
+            AuthResponse::Cancel => methods::AuthChallengeResponse {
+                response: "CancelAuth",
+                username: None,
+                password: None,
+            },
+            AuthResponse::Provide(credentials) => methods::AuthChallengeResponse {
+                response: "ProvideCredentials",
+                username: Some(&credentials.username),
+                password: Some(&credentials.password),
+            },
+        }
+    }
+}
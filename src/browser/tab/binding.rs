@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use failure::Error;
+use log::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::protocol::runtime::events::BindingCalledParams;
+
+/// A Rust function the page can call, see `Tab::expose_function`. It gets the arguments the
+/// page passed as an array.
+pub type ExposedFunction = Arc<dyn Fn(Value) -> Result<Value, Error> + Send + Sync>;
+
+/// Bindings are named after the function they're for, with this prefix, so that the page-side
+/// wrapper can take the function's name.
+const BINDING_PREFIX: &str = "__headless_chrome_";
+
+pub(crate) fn binding_name(name: &str) -> String {
+    format!("{}{}", BINDING_PREFIX, name)
+}
+
+/// The script that replaces the function `name` with one that passes its arguments on to the
+/// binding and returns a promise, which `ExposedCall::call`'s expression settles.
+pub(crate) fn page_wrapper(name: &str) -> String {
+    format!(
+        r#"(function (name, bindingName) {{
+    const binding = window[bindingName];
+    if (typeof binding !== "function") {{
+        return;
+    }}
+    const calls = new Map();
+    let lastSeq = 0;
+    window[name] = (...args) => new Promise((resolve, reject) => {{
+        const seq = ++lastSeq;
+        calls.set(seq, {{ resolve, reject }});
+        binding(JSON.stringify({{ seq, args }}));
+    }});
+    window.__headlessChromeCalls = window.__headlessChromeCalls || {{}};
+    window.__headlessChromeCalls[name] = (seq, result, error) => {{
+        const call = calls.get(seq);
+        if (!call) {{
+            return;
+        }}
+        calls.delete(seq);
+        if (error === null) {{
+            call.resolve(result);
+        }} else {{
+            call.reject(new Error(error));
+        }}
+    }};
+}})({}, {});"#,
+        Value::from(name),
+        Value::from(binding_name(name))
+    )
+}
+
+/// The functions a tab exposed, by name.
+#[derive(Clone, Default)]
+pub(crate) struct ExposedFunctions {
+    functions: HashMap<String, ExposedFunction>,
+}
+
+impl fmt::Debug for ExposedFunctions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.functions.keys()).finish()
+    }
+}
+
+impl ExposedFunctions {
+    pub fn insert(&mut self, name: &str, function: ExposedFunction) {
+        self.functions.insert(name.to_string(), function);
+    }
+
+    pub fn binding_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.functions.keys().map(|name| binding_name(name))
+    }
+
+    /// The call `params` reports, if it's to one of these functions.
+    pub fn called(&self, params: &BindingCalledParams) -> Option<ExposedCall> {
+        let name = params.name.strip_prefix(BINDING_PREFIX)?;
+        let function = self.functions.get(name)?;
+        Some(ExposedCall {
+            name: name.to_string(),
+            function: Arc::clone(function),
+            payload: params.payload.clone(),
+        })
+    }
+}
+
+/// A call from the page to an exposed function.
+pub(crate) struct ExposedCall {
+    name: String,
+    function: ExposedFunction,
+    payload: String,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    seq: u64,
+    args: Value,
+}
+
+impl ExposedCall {
+    /// Calls the function, returning the expression that settles the page's promise with its
+    /// result, or `None` if the page didn't call the binding through the wrapper.
+    pub fn call(self) -> Option<String> {
+        let payload: Payload = match serde_json::from_str(&self.payload) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!("Couldn't decode call to {}: {}", self.name, err);
+                return None;
+            }
+        };
+        let (result, error) = match (self.function)(payload.args) {
+            Ok(result) => (result, Value::Null),
+            Err(err) => (Value::Null, Value::from(err.to_string())),
+        };
+        Some(format!(
+            "window.__headlessChromeCalls[{}]({}, {}, {})",
+            Value::from(self.name),
+            payload.seq,
+            result,
+            error
+        ))
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+
+use crate::protocol::runtime::events::{
+    ConsoleAPICalledParams, ConsoleAPIType, ExceptionThrownParams,
+};
+use crate::protocol::runtime::{
+    ExceptionDetails, ExecutionContextId, ObjectPreview, RemoteObject, StackTrace, Timestamp,
+};
+
+/// How many messages, and separately how many exceptions, a `ConsoleBuffer` keeps before it
+/// starts dropping the oldest ones.
+const CAPACITY: usize = 1000;
+
+/// A call to one of the `console` methods, e.g. `console.error("failed:", err)`.
+#[derive(Debug, Clone)]
+pub struct ConsoleMessage {
+    pub call_type: ConsoleAPIType,
+    /// The arguments rendered the way the DevTools console shows them, separated by spaces.
+    pub text: String,
+    pub args: Vec<RemoteObject>,
+    pub execution_context_id: ExecutionContextId,
+    pub timestamp: Timestamp,
+    pub stack_trace: Option<StackTrace>,
+}
+
+impl ConsoleMessage {
+    /// Whether this was a `console.error`, or a `console.assert` that failed.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.call_type,
+            ConsoleAPIType::Error | ConsoleAPIType::Assert
+        )
+    }
+}
+
+impl From<ConsoleAPICalledParams> for ConsoleMessage {
+    fn from(params: ConsoleAPICalledParams) -> Self {
+        let text = params
+            .args
+            .iter()
+            .map(describe)
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            call_type: params.call_type,
+            text,
+            args: params.args,
+            execution_context_id: params.execution_context_id,
+            timestamp: params.timestamp,
+            stack_trace: params.stack_trace,
+        }
+    }
+}
+
+/// An exception the page threw and nothing caught.
+#[derive(Debug, Clone)]
+pub struct UncaughtException {
+    /// The exception's description, e.g. "Error: boom" followed by its stack, or if it has none,
+    /// Chrome's summary ("Uncaught").
+    pub text: String,
+    pub timestamp: Timestamp,
+    pub details: ExceptionDetails,
+}
+
+impl From<ExceptionThrownParams> for UncaughtException {
+    fn from(params: ExceptionThrownParams) -> Self {
+        let details = params.exception_details;
+        let text = details
+            .exception
+            .as_ref()
+            .map_or_else(|| details.text.clone(), describe);
+        Self {
+            text,
+            timestamp: params.timestamp,
+            details,
+        }
+    }
+}
+
+/// What a tab logged to its console and the exceptions it didn't catch, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleBuffer {
+    messages: VecDeque<ConsoleMessage>,
+    exceptions: VecDeque<UncaughtException>,
+}
+
+impl ConsoleBuffer {
+    pub fn messages(&self) -> impl Iterator<Item = &ConsoleMessage> {
+        self.messages.iter()
+    }
+
+    pub fn exceptions(&self) -> impl Iterator<Item = &UncaughtException> {
+        self.exceptions.iter()
+    }
+
+    /// Whether the page called `console.error`, failed a `console.assert` or threw an uncaught
+    /// exception: handy for failing a test.
+    pub fn has_errors(&self) -> bool {
+        !self.exceptions.is_empty() || self.messages.iter().any(ConsoleMessage::is_error)
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.exceptions.clear();
+    }
+
+    pub(crate) fn push_message(&mut self, message: ConsoleMessage) {
+        if self.messages.len() == CAPACITY {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    pub(crate) fn push_exception(&mut self, exception: UncaughtException) {
+        if self.exceptions.len() == CAPACITY {
+            self.exceptions.pop_front();
+        }
+        self.exceptions.push_back(exception);
+    }
+}
+
+/// Renders `object` as the console would: strings as they are, other primitives as JSON, and
+/// objects from their preview when Chrome sent one.
+fn describe(object: &RemoteObject) -> String {
+    if let Some(value) = &object.value {
+        return match value {
+            serde_json::Value::String(string) => string.clone(),
+            other => other.to_string(),
+        };
+    }
+    if let Some(value) = &object.unserializable_value {
+        return value.clone();
+    }
+    if let Some(preview) = &object.preview {
+        return describe_preview(preview);
+    }
+    object
+        .description
+        .clone()
+        .unwrap_or_else(|| object.object_type.clone())
+}
+
+fn describe_preview(preview: &ObjectPreview) -> String {
+    let mut properties: Vec<String> = preview
+        .properties
+        .iter()
+        .map(|property| {
+            let value = match (property.object_type.as_ref(), &property.value) {
+                ("string", Some(value)) => format!("{:?}", value),
+                (_, Some(value)) => value.clone(),
+                (object_type, None) => object_type.to_string(),
+            };
+            if preview.subtype.as_deref() == Some("array") {
+                value
+            } else {
+                format!("{}: {}", property.name, value)
+            }
+        })
+        .collect();
+    if preview.overflow {
+        properties.push("…".to_string());
+    }
+    if preview.subtype.as_deref() == Some("array") {
+        format!("[{}]", properties.join(", "))
+    } else {
+        format!("{{{}}}", properties.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn renders_arguments_from_their_previews() {
+        let params: ConsoleAPICalledParams = serde_json::from_value(json!({
+            "type": "error",
+            "args": [
+                {"type": "string", "value": "failed:"},
+                {"type": "number", "value": 3, "description": "3"},
+                {"type": "number", "unserializableValue": "NaN", "description": "NaN"},
+                {
+                    "type": "object",
+                    "className": "Object",
+                    "description": "Object",
+                    "objectId": "1",
+                    "preview": {
+                        "type": "object",
+                        "description": "Object",
+                        "overflow": false,
+                        "properties": [
+                            {"name": "code", "type": "number", "value": "404"},
+                            {"name": "reason", "type": "string", "value": "not found"},
+                            {"name": "next", "type": "object", "value": "Object"}
+                        ]
+                    }
+                },
+                {
+                    "type": "object",
+                    "subtype": "array",
+                    "description": "Array(3)",
+                    "objectId": "2",
+                    "preview": {
+                        "type": "object",
+                        "subtype": "array",
+                        "description": "Array(3)",
+                        "overflow": true,
+                        "properties": [{"name": "0", "type": "number", "value": "1"}]
+                    }
+                },
+                {"type": "function", "description": "function f() {}", "objectId": "3"}
+            ],
+            "executionContextId": 1,
+            "timestamp": 1.5
+        }))
+        .unwrap();
+
+        let message = ConsoleMessage::from(params);
+        assert!(message.is_error());
+        assert_eq!(
+            r#"failed: 3 NaN {code: 404, reason: "not found", next: Object} [1, …] function f() {}"#,
+            message.text
+        );
+    }
+
+    #[test]
+    fn keeps_the_latest_entries() {
+        let exception: ExceptionThrownParams = serde_json::from_value(json!({
+            "timestamp": 2.0,
+            "exceptionDetails": {
+                "exceptionId": 1,
+                "text": "Uncaught",
+                "lineNumber": 0,
+                "columnNumber": 70000,
+                "exception": {
+                    "type": "object",
+                    "subtype": "error",
+                    "className": "Error",
+                    "description": "Error: boom\n    at <anonymous>:1:7"
+                }
+            }
+        }))
+        .unwrap();
+        let exception = UncaughtException::from(exception);
+        assert_eq!("Error: boom\n    at <anonymous>:1:7", exception.text);
+
+        let mut buffer = ConsoleBuffer::default();
+        assert!(!buffer.has_errors());
+        buffer.push_exception(exception);
+        assert!(buffer.has_errors());
+
+        for n in 0..=CAPACITY {
+            buffer.push_message(ConsoleMessage {
+                call_type: ConsoleAPIType::Log,
+                text: n.to_string(),
+                args: vec![],
+                execution_context_id: 1,
+                timestamp: 0.0,
+                stack_trace: None,
+            });
+        }
+        assert_eq!(CAPACITY, buffer.messages().count());
+        assert_eq!("1", buffer.messages().next().unwrap().text);
+
+        buffer.clear();
+        assert!(!buffer.has_errors());
+        assert_eq!(0, buffer.messages().count());
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::protocol::page::events::JavascriptDialogOpeningParams;
+
+/// Decides how to answer a dialog, see `DialogPolicy::Callback`.
+pub type DialogCallback =
+    Arc<dyn Fn(&JavascriptDialogOpeningParams) -> DialogResponse + Send + Sync>;
+
+/// How to answer an `alert`, `confirm`, `prompt` or `beforeunload` dialog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DialogResponse {
+    pub accept: bool,
+    /// What to enter into a prompt before accepting it; `None` keeps its default text.
+    pub prompt_text: Option<String>,
+}
+
+/// What a tab does when the page opens a dialog, which otherwise blocks it (and any call
+/// waiting on it) until it's answered.
+#[derive(Clone, Default)]
+pub enum DialogPolicy {
+    /// Accept every dialog, leaving prompts' default text as it is. Accepting `beforeunload`
+    /// lets the page be closed or navigated away from.
+    #[default]
+    Accept,
+    /// Dismiss every dialog: prompts and confirms return as if cancelled, and `beforeunload`
+    /// keeps the page open.
+    Dismiss,
+    /// Accept every dialog, entering this text into prompts.
+    AcceptWithPromptText(String),
+    /// Ask the callback. It's called on the thread handling the tab's events, so it mustn't
+    /// wait on the tab itself.
+    Callback(DialogCallback),
+    /// Leave dialogs open, to be answered with `handle_java_script_dialog`.
+    Manual,
+}
+
+impl fmt::Debug for DialogPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DialogPolicy::Accept => write!(f, "Accept"),
+            DialogPolicy::Dismiss => write!(f, "Dismiss"),
+            DialogPolicy::AcceptWithPromptText(text) => {
+                write!(f, "AcceptWithPromptText({:?})", text)
+            }
+            DialogPolicy::Callback(_) => write!(f, "Callback"),
+            DialogPolicy::Manual => write!(f, "Manual"),
+        }
+    }
+}
+
+impl DialogPolicy {
+    /// How to answer the dialog `params` describes, or `None` if it should be left open.
+    pub fn respond(&self, params: &JavascriptDialogOpeningParams) -> Option<DialogResponse> {
+        match self {
+            DialogPolicy::Accept => Some(DialogResponse {
+                accept: true,
+                prompt_text: None,
+            }),
+            DialogPolicy::Dismiss => Some(DialogResponse {
+                accept: false,
+                prompt_text: None,
+            }),
+            DialogPolicy::AcceptWithPromptText(text) => Some(DialogResponse {
+                accept: true,
+                prompt_text: Some(text.clone()),
+            }),
+            DialogPolicy::Callback(callback) => Some(callback(params)),
+            DialogPolicy::Manual => None,
+        }
+    }
+}
@@ -0,0 +1,917 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+use failure::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::network::{
+    self,
+    events::{
+        DataReceivedParams, LoadingFailedParams, LoadingFinishedParams, RequestWillBeSentParams,
+        ResponseReceivedParams,
+    },
+    methods::GetResponseBodyReturnObject,
+};
+
+/// A HAR 1.2 archive (see http://www.softwareishard.com/blog/har-12-spec/), as put together by
+/// a `HarRecorder`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Har {
+    pub log: Log,
+}
+
+impl Har {
+    /// Writes the archive to `path` as (pretty printed) JSON, e.g. to attach it to the report
+    /// of a failed test.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// ISO 8601, e.g. "2019-05-25T09:00:08.110Z".
+    pub started_date_time: String,
+    /// Milliseconds, the sum of the `timings` that apply.
+    pub time: f64,
+    pub request: Request,
+    pub response: Response,
+    pub cache: Cache,
+    pub timings: Timings,
+    #[serde(
+        rename = "serverIPAddress",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub server_ip_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub connection: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<Cookie>,
+    pub headers: Vec<Header>,
+    pub query_string: Vec<QueryParam>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub post_data: Option<PostData>,
+    /// -1 if unknown.
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    /// 0 if there's no response, because the request failed or is still waiting for it.
+    pub status: i32,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<Cookie>,
+    pub headers: Vec<Header>,
+    pub content: Content,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    /// -1 if unknown.
+    pub headers_size: i64,
+    /// -1 if unknown, 0 if the response came from the cache.
+    pub body_size: i64,
+    /// Why the request failed, as Chrome's DevTools put it in their HARs.
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Content {
+    /// The decoded size of the body.
+    pub size: i64,
+    pub mime_type: String,
+    /// Only there if the body was recorded.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub text: Option<String>,
+    /// "base64" for binary bodies.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub encoding: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Cache {}
+
+/// Milliseconds spent in each phase of the request, -1 for the ones that don't apply. `ssl` is
+/// part of `connect`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Timings {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    pub ssl: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueryParam {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub secure: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+/// What's known of one request, until it's turned into an `Entry`.
+#[derive(Debug, Clone)]
+struct RecordedRequest {
+    sent: RequestWillBeSentParams,
+    response: Option<network::Response>,
+    /// Where the request got redirected to; its `response` is the redirect.
+    redirect_url: Option<String>,
+    data_length: u64,
+    encoded_data_length: Option<u32>,
+    finished_at: Option<network::MonotonicTime>,
+    error: Option<String>,
+    body: Option<GetResponseBodyReturnObject>,
+}
+
+/// Puts together a HAR out of the Network domain's `requestWillBeSent`, `responseReceived`,
+/// `dataReceived`, `loadingFinished` and `loadingFailed` events, which have to be fed to it.
+/// See `Tab::start_har_recording` for the sync tab, or the async tab's `start_har_recording`.
+#[derive(Debug, Default)]
+pub struct HarRecorder {
+    requests: Vec<RecordedRequest>,
+    /// Where in `requests` the latest request for each ID is; redirects keep the ID.
+    by_id: HashMap<network::RequestId, usize>,
+    record_bodies: bool,
+}
+
+impl HarRecorder {
+    /// With `record_bodies`, the responses' bodies are expected to be fetched with
+    /// `Network.getResponseBody` once `wants_body` says so, and handed to `set_response_body`.
+    pub fn new(record_bodies: bool) -> Self {
+        Self {
+            record_bodies,
+            ..Default::default()
+        }
+    }
+
+    pub fn request_will_be_sent(&mut self, params: &RequestWillBeSentParams) {
+        if let Some(redirect_response) = &params.redirect_response {
+            if let Some(redirected) = self.find_mut(&params.request_id) {
+                redirected.response = Some(redirect_response.clone());
+                redirected.redirect_url = Some(params.request.url.clone());
+                redirected.finished_at = Some(params.timestamp);
+            }
+        }
+        self.by_id
+            .insert(params.request_id.clone(), self.requests.len());
+        self.requests.push(RecordedRequest {
+            sent: params.clone(),
+            response: None,
+            redirect_url: None,
+            data_length: 0,
+            encoded_data_length: None,
+            finished_at: None,
+            error: None,
+            body: None,
+        });
+    }
+
+    pub fn response_received(&mut self, params: &ResponseReceivedParams) {
+        if let Some(request) = self.find_mut(&params.request_id) {
+            request.response = Some(params.response.clone());
+        }
+    }
+
+    pub fn data_received(&mut self, params: &DataReceivedParams) {
+        if let Some(request) = self.find_mut(&params.request_id) {
+            request.data_length += u64::from(params.data_length);
+        }
+    }
+
+    pub fn loading_finished(&mut self, params: &LoadingFinishedParams) {
+        if let Some(request) = self.find_mut(&params.request_id) {
+            request.finished_at = Some(params.timestamp);
+            request.encoded_data_length = Some(params.encoded_data_length);
+        }
+    }
+
+    pub fn loading_failed(&mut self, params: &LoadingFailedParams) {
+        if let Some(request) = self.find_mut(&params.request_id) {
+            request.finished_at = Some(params.timestamp);
+            request.error = Some(params.error_text.clone());
+        }
+    }
+
+    /// Whether the body of `request_id` should be fetched now: bodies are recorded, and the
+    /// request finished loading with a response that has one.
+    pub fn wants_body(&self, request_id: &str) -> bool {
+        if !self.record_bodies {
+            return false;
+        }
+        match self
+            .by_id
+            .get(request_id)
+            .map(|index| &self.requests[*index])
+        {
+            Some(request) => {
+                request.encoded_data_length.is_some()
+                    && request.body.is_none()
+                    && request
+                        .response
+                        .as_ref()
+                        .is_some_and(|response| response.status != 204 && response.status != 304)
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_response_body(&mut self, request_id: &str, body: GetResponseBodyReturnObject) {
+        if let Some(request) = self.find_mut(request_id) {
+            request.body = Some(body);
+        }
+    }
+
+    /// The requests recorded so far, in the order they were sent. The ones still waiting for
+    /// their response have a status of 0.
+    pub fn har(&self) -> Har {
+        Har {
+            log: Log {
+                version: "1.2".to_string(),
+                creator: Creator {
+                    name: "headless_chrome".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries: self.requests.iter().map(RecordedRequest::entry).collect(),
+            },
+        }
+    }
+
+    /// Forgets the requests recorded so far.
+    pub fn clear(&mut self) {
+        self.requests.clear();
+        self.by_id.clear();
+    }
+
+    fn find_mut(&mut self, request_id: &str) -> Option<&mut RecordedRequest> {
+        let index = *self.by_id.get(request_id)?;
+        self.requests.get_mut(index)
+    }
+}
+
+impl RecordedRequest {
+    fn entry(&self) -> Entry {
+        let timings = self.timings();
+        let time = [
+            timings.blocked,
+            timings.dns,
+            timings.connect,
+            timings.send,
+            timings.wait,
+            timings.receive,
+        ]
+        .iter()
+        .filter(|time| **time > 0.0)
+        .sum();
+        let response = self.response.as_ref();
+        Entry {
+            started_date_time: iso_8601(self.sent.wall_time),
+            time,
+            request: self.request(),
+            response: self.response(),
+            cache: Cache::default(),
+            timings,
+            server_ip_address: response.and_then(|response| response.remote_ip_address.clone()),
+            connection: response
+                .filter(|response| response.connection_id != 0)
+                .map(|response| response.connection_id.to_string()),
+        }
+    }
+
+    fn request(&self) -> Request {
+        let request = &self.sent.request;
+        let headers = match self
+            .response
+            .as_ref()
+            .and_then(|r| r.request_headers.as_ref())
+        {
+            Some(sent_headers) => headers(sent_headers),
+            None => headers(&request.headers),
+        };
+        let cookies = headers
+            .iter()
+            .filter(|header| header.name.eq_ignore_ascii_case("Cookie"))
+            .flat_map(|header| header.value.split(';'))
+            .filter_map(|cookie| name_value(cookie.trim()))
+            .map(|(name, value)| Cookie {
+                name,
+                value,
+                path: None,
+                domain: None,
+                http_only: None,
+                secure: None,
+            })
+            .collect();
+        let post_data = request.post_data.as_ref().map(|text| PostData {
+            mime_type: header_value(&headers, "Content-Type").unwrap_or_default(),
+            text: text.clone(),
+        });
+        let url = match &request.url_fragment {
+            Some(fragment) => format!("{}{}", request.url, fragment),
+            None => request.url.clone(),
+        };
+        Request {
+            method: request.method.clone(),
+            query_string: query_string(&url),
+            url,
+            http_version: self.http_version(),
+            cookies,
+            headers,
+            body_size: post_data.as_ref().map_or(0, |data| len(&data.text)),
+            post_data,
+            headers_size: self
+                .response
+                .as_ref()
+                .and_then(|response| response.request_headers_text.as_ref())
+                .map_or(-1, |text| len(text)),
+        }
+    }
+
+    fn response(&self) -> Response {
+        let Some(response) = &self.response else {
+            return Response {
+                status: 0,
+                status_text: String::new(),
+                http_version: String::new(),
+                cookies: vec![],
+                headers: vec![],
+                content: Content {
+                    size: 0,
+                    mime_type: "x-unknown".to_string(),
+                    text: None,
+                    encoding: None,
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: -1,
+                error: self.error.clone(),
+            };
+        };
+        let headers = headers(&response.headers);
+        let cookies = headers
+            .iter()
+            .filter(|header| header.name.eq_ignore_ascii_case("Set-Cookie"))
+            .filter_map(|header| set_cookie(&header.value))
+            .collect();
+        let from_cache = response.from_disk_cache == Some(true)
+            || response.from_prefetch_cache == Some(true)
+            || response.status == 304;
+        let body_size = if from_cache {
+            0
+        } else {
+            self.encoded_data_length.map_or(-1, |total| {
+                (i64::from(total) - i64::from(response.encoded_data_length)).max(0)
+            })
+        };
+        Response {
+            status: response.status,
+            status_text: response.status_text.clone(),
+            http_version: self.http_version(),
+            cookies,
+            redirect_url: self
+                .redirect_url
+                .clone()
+                .or_else(|| header_value(&headers, "Location"))
+                .unwrap_or_default(),
+            headers,
+            content: Content {
+                size: i64::try_from(self.data_length).unwrap_or(i64::MAX),
+                mime_type: response.mime_type.clone(),
+                text: self.body.as_ref().map(|body| body.body.clone()),
+                encoding: self
+                    .body
+                    .as_ref()
+                    .filter(|body| body.base64_encoded)
+                    .map(|_| "base64".to_string()),
+            },
+            headers_size: response.headers_text.as_ref().map_or(-1, |text| len(text)),
+            body_size,
+            error: self.error.clone(),
+        }
+    }
+
+    fn timings(&self) -> Timings {
+        let timing = self
+            .response
+            .as_ref()
+            .and_then(|response| response.timing.as_ref());
+        // served from the cache, or failed before there was a response
+        let Some(timing) = timing else {
+            return Timings {
+                blocked: -1.0,
+                dns: -1.0,
+                connect: -1.0,
+                send: 0.0,
+                wait: self.finished_at.map_or(0.0, |finished_at| {
+                    ((finished_at - self.sent.timestamp) * 1000.0).max(0.0)
+                }),
+                receive: 0.0,
+                ssl: -1.0,
+            };
+        };
+        // queued from when the request was issued until Chrome started on it
+        let queued = (timing.request_time - self.sent.timestamp) * 1000.0;
+        let started = [timing.dns_start, timing.connect_start, timing.send_start]
+            .iter()
+            .copied()
+            .find(|start| *start >= 0.0)
+            .map_or(0.0, f64::from);
+        let headers_received_at =
+            timing.request_time + f64::from(timing.receive_headers_end) / 1000.0;
+        Timings {
+            blocked: (queued + started).max(0.0),
+            dns: span(timing.dns_start, timing.dns_end),
+            connect: span(timing.connect_start, timing.connect_end),
+            send: span(timing.send_start, timing.send_end).max(0.0),
+            wait: f64::from(timing.receive_headers_end - timing.send_end).max(0.0),
+            receive: self.finished_at.map_or(0.0, |finished_at| {
+                ((finished_at - headers_received_at) * 1000.0).max(0.0)
+            }),
+            ssl: span(timing.ssl_start, timing.ssl_end),
+        }
+    }
+
+    fn http_version(&self) -> String {
+        let protocol = self
+            .response
+            .as_ref()
+            .and_then(|response| response.protocol.as_ref());
+        match protocol.map(String::as_str) {
+            None => String::new(),
+            Some("h2") => "HTTP/2".to_string(),
+            Some(protocol) if protocol.starts_with("h3") => "HTTP/3".to_string(),
+            Some(protocol) => protocol.to_uppercase(),
+        }
+    }
+}
+
+/// Milliseconds from `start` to `end`, or -1 if that phase didn't happen.
+fn span(start: f32, end: f32) -> f64 {
+    if start < 0.0 {
+        -1.0
+    } else {
+        f64::from(end - start)
+    }
+}
+
+fn len(text: &str) -> i64 {
+    i64::try_from(text.len()).unwrap_or(i64::MAX)
+}
+
+/// Chrome joins the values of headers that come more than once with newlines.
+fn headers(headers: &HashMap<String, String>) -> Vec<Header> {
+    let mut headers: Vec<Header> = headers
+        .iter()
+        .flat_map(|(name, values)| {
+            values.split('\n').map(move |value| Header {
+                name: name.clone(),
+                value: value.to_string(),
+            })
+        })
+        .collect();
+    headers.sort_by(|a, b| a.name.cmp(&b.name));
+    headers
+}
+
+fn header_value(headers: &[Header], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case(name))
+        .map(|header| header.value.clone())
+}
+
+fn name_value(pair: &str) -> Option<(String, String)> {
+    if pair.is_empty() {
+        return None;
+    }
+    let mut parts = pair.splitn(2, '=');
+    let name = parts.next()?.to_string();
+    let value = parts.next().unwrap_or_default().to_string();
+    Some((name, value))
+}
+
+fn set_cookie(header: &str) -> Option<Cookie> {
+    let mut parts = header.split(';').map(str::trim);
+    let (name, value) = name_value(parts.next()?)?;
+    let mut cookie = Cookie {
+        name,
+        value,
+        path: None,
+        domain: None,
+        http_only: None,
+        secure: None,
+    };
+    for (attribute, value) in parts.filter_map(name_value) {
+        match attribute.to_lowercase().as_str() {
+            "path" => cookie.path = Some(value),
+            "domain" => cookie.domain = Some(value),
+            "httponly" => cookie.http_only = Some(true),
+            "secure" => cookie.secure = Some(true),
+            _ => {}
+        }
+    }
+    Some(cookie)
+}
+
+fn query_string(url: &str) -> Vec<QueryParam> {
+    let url = url.split('#').next().unwrap_or_default();
+    let query = match url.find('?') {
+        Some(index) => &url[index + 1..],
+        None => return vec![],
+    };
+    query
+        .split('&')
+        .filter_map(name_value)
+        .map(|(name, value)| QueryParam {
+            name: percent_decode(&name),
+            value: percent_decode(&value),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = text
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+            (b'+', None) => decoded.push(b' '),
+            (byte, None) => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// `seconds` since the Unix epoch as an ISO 8601 date in UTC.
+#[allow(clippy::cast_possible_truncation)]
+fn iso_8601(seconds: network::TimeSinceEpoch) -> String {
+    let millis = (seconds * 1000.0).round() as i64;
+    let days = millis.div_euclid(86_400_000);
+    let millis_of_day = millis.rem_euclid(86_400_000);
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        millis_of_day / 3_600_000,
+        millis_of_day / 60_000 % 60,
+        millis_of_day / 1000 % 60,
+        millis_of_day % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn approx(expected: f64, actual: f64) -> bool {
+        (expected - actual).abs() < 1e-3
+    }
+
+    fn request_will_be_sent(url: &str, timestamp: f64, redirect_response: Option<Value>) -> Value {
+        let mut params = json!({
+            "requestId": "1.1",
+            "loaderId": "loader",
+            "documentURL": url,
+            "request": {
+                "url": url,
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "High",
+                "referrerPolicy": "no-referrer"
+            },
+            "timestamp": timestamp,
+            "wallTime": 1_558_774_808.0,
+            "initiator": {"type": "other"},
+            "type": "Document"
+        });
+        if let Some(redirect_response) = redirect_response {
+            params["redirectResponse"] = redirect_response;
+        }
+        params
+    }
+
+    fn response(status: i32, timing: Option<Value>) -> Value {
+        let mut response = json!({
+            "url": "http://example.com/",
+            "status": status,
+            "statusText": "",
+            "headers": {},
+            "mimeType": "text/html",
+            "connectionReused": false,
+            "connectionId": 0,
+            "encodedDataLength": 0,
+            "securityState": "insecure"
+        });
+        if let Some(timing) = timing {
+            response["timing"] = timing;
+        }
+        response
+    }
+
+    fn timing(request_time: f64, phases: [f32; 9]) -> Value {
+        let [dns_start, dns_end, connect_start, connect_end, ssl_start, ssl_end, send_start, send_end, receive_headers_end] =
+            phases;
+        json!({
+            "requestTime": request_time,
+            "proxyStart": -1, "proxyEnd": -1,
+            "dnsStart": dns_start, "dnsEnd": dns_end,
+            "connectStart": connect_start, "connectEnd": connect_end,
+            "sslStart": ssl_start, "sslEnd": ssl_end,
+            "workerStart": -1, "workerReady": -1,
+            "sendStart": send_start, "sendEnd": send_end,
+            "pushStart": 0, "pushEnd": 0,
+            "receiveHeadersEnd": receive_headers_end
+        })
+    }
+
+    /// One request sent at 100s, answered with `response` and finished at `finished_at`.
+    fn recorded(response: Option<Value>, finished_at: Option<f64>) -> Entry {
+        let mut recorder = HarRecorder::new(false);
+        let sent = request_will_be_sent("http://example.com/", 100.0, None);
+        recorder.request_will_be_sent(&serde_json::from_value(sent).unwrap());
+        if let Some(response) = response {
+            recorder.response_received(
+                &serde_json::from_value(json!({
+                    "requestId": "1.1",
+                    "loaderId": "loader",
+                    "timestamp": 100.0,
+                    "type": "Document",
+                    "response": response
+                }))
+                .unwrap(),
+            );
+        }
+        if let Some(finished_at) = finished_at {
+            recorder.loading_finished(
+                &serde_json::from_value(json!({
+                    "requestId": "1.1", "timestamp": finished_at, "encodedDataLength": 0
+                }))
+                .unwrap(),
+            );
+        }
+        recorder.har().log.entries.remove(0)
+    }
+
+    #[test]
+    fn iso_8601_dates() {
+        for (seconds, expected) in &[
+            (0.0, "1970-01-01T00:00:00.000Z"),
+            (1_558_774_808.110_41, "2019-05-25T09:00:08.110Z"),
+            // leap days, in a year divisible by 400 and in one only divisible by 4
+            (951_782_400.0, "2000-02-29T00:00:00.000Z"),
+            (1_709_208_000.0, "2024-02-29T12:00:00.000Z"),
+            (-1.5, "1969-12-31T23:59:58.500Z"),
+            // rounding the milliseconds carries over into the next year
+            (4_102_444_799.999_6, "2100-01-01T00:00:00.000Z"),
+        ] {
+            assert_eq!(*expected, iso_8601(*seconds), "{}", seconds);
+        }
+    }
+
+    #[test]
+    fn spans_of_phases_that_did_not_happen_are_minus_one() {
+        for (start, end, expected) in &[
+            (-1.0, -1.0, -1.0),
+            (-1.0, 5.0, -1.0),
+            (0.0, 0.0, 0.0),
+            (1.5, 4.0, 2.5),
+        ] {
+            assert!(approx(*expected, span(*start, *end)), "{} {}", start, end);
+        }
+    }
+
+    #[test]
+    fn timings() {
+        // name, response timing, finished at, [blocked, dns, connect, send, wait, receive, ssl]
+        let cases: Vec<(&str, Option<Value>, Option<f64>, [f64; 7])> = vec![
+            (
+                "new connection, queued for 10ms",
+                Some(timing(
+                    100.01,
+                    [1.0, 3.0, 3.0, 8.0, -1.0, -1.0, 9.0, 10.0, 50.0],
+                )),
+                Some(100.1),
+                [11.0, 2.0, 5.0, 1.0, 40.0, 40.0, -1.0],
+            ),
+            (
+                "TLS, still receiving",
+                Some(timing(
+                    100.0,
+                    [0.0, 2.0, 2.0, 30.0, 10.0, 30.0, 31.0, 32.0, 80.0],
+                )),
+                None,
+                [0.0, 2.0, 28.0, 1.0, 48.0, 0.0, 20.0],
+            ),
+            (
+                "reused connection",
+                Some(timing(
+                    100.0,
+                    [-1.0, -1.0, -1.0, -1.0, -1.0, -1.0, 0.0, 0.5, 20.0],
+                )),
+                Some(100.03),
+                [0.0, -1.0, -1.0, 0.5, 19.5, 10.0, -1.0],
+            ),
+            (
+                "request time before the request was sent",
+                Some(timing(
+                    99.9,
+                    [-1.0, -1.0, -1.0, -1.0, -1.0, -1.0, 0.0, 1.0, 2.0],
+                )),
+                None,
+                [0.0, -1.0, -1.0, 1.0, 1.0, 0.0, -1.0],
+            ),
+            (
+                "served from the cache",
+                None,
+                Some(100.25),
+                [-1.0, -1.0, -1.0, 0.0, 250.0, 0.0, -1.0],
+            ),
+        ];
+        for (name, timing, finished_at, expected) in cases {
+            let response = timing.map(|timing| response(200, Some(timing)));
+            let entry = recorded(response, finished_at);
+            let timings = &entry.timings;
+            let actual = [
+                timings.blocked,
+                timings.dns,
+                timings.connect,
+                timings.send,
+                timings.wait,
+                timings.receive,
+                timings.ssl,
+            ];
+            for (expected, actual) in expected.iter().zip(&actual) {
+                assert!(approx(*expected, *actual), "{}: {:?}", name, actual);
+            }
+            let time: f64 = expected[..6].iter().filter(|time| **time > 0.0).sum();
+            assert!(approx(time, entry.time), "{}: {}", name, entry.time);
+        }
+    }
+
+    #[test]
+    fn percent_decoding() {
+        for (text, expected) in &[
+            ("a%20b", "a b"),
+            ("a+b", "a b"),
+            ("%2B", "+"),
+            ("%2b%3D", "+="),
+            // not escapes, kept as they are
+            ("%zz", "%zz"),
+            ("100%", "100%"),
+            ("%2", "%2"),
+            ("%%41", "%A"),
+            ("%€", "%€"),
+            // multibyte characters, escaped or not
+            ("caf%C3%A9", "café"),
+            ("%E2%82%AC+€", "€ €"),
+            // an incomplete UTF-8 sequence
+            ("%C3", "\u{FFFD}"),
+        ] {
+            assert_eq!(*expected, percent_decode(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn query_strings() {
+        let pairs = |url: &str| -> Vec<(String, String)> {
+            query_string(url)
+                .into_iter()
+                .map(|param| (param.name, param.value))
+                .collect()
+        };
+        assert!(pairs("http://example.com/path").is_empty());
+        assert!(pairs("http://example.com/?").is_empty());
+        assert_eq!(
+            vec![
+                ("q".to_string(), "a b".to_string()),
+                ("empty".to_string(), String::new()),
+                ("flag".to_string(), String::new()),
+                ("x".to_string(), "1=2".to_string()),
+            ],
+            pairs("http://example.com/?q=a+b&empty=&flag&&x=1%3D2#frag?not=query")
+        );
+    }
+
+    #[test]
+    fn redirects_are_chained() {
+        let mut recorder = HarRecorder::new(false);
+        let sent = request_will_be_sent("http://example.com/old", 100.0, None);
+        recorder.request_will_be_sent(&serde_json::from_value(sent).unwrap());
+        let mut redirect = response(302, None);
+        redirect["headers"] = json!({"Location": "/relative"});
+        let sent = request_will_be_sent("http://example.com/new", 100.02, Some(redirect));
+        recorder.request_will_be_sent(&serde_json::from_value(sent).unwrap());
+        let sent = request_will_be_sent(
+            "http://example.com/final",
+            100.05,
+            Some(response(301, None)),
+        );
+        recorder.request_will_be_sent(&serde_json::from_value(sent).unwrap());
+
+        let entries = recorder.har().log.entries;
+        let chain: Vec<(&str, i32, &str)> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.request.url.as_str(),
+                    entry.response.status,
+                    entry.response.redirect_url.as_str(),
+                )
+            })
+            .collect();
+        // the URL that was actually requested wins over the `Location` header
+        assert_eq!(
+            vec![
+                ("http://example.com/old", 302, "http://example.com/new"),
+                ("http://example.com/new", 301, "http://example.com/final"),
+                ("http://example.com/final", 0, ""),
+            ],
+            chain
+        );
+        assert!(approx(20.0, entries[0].time));
+        assert!(approx(30.0, entries[1].time));
+    }
+}
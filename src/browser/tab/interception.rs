@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use failure::Error;
+
+use crate::browser::transport::SessionId;
+use crate::browser::Transport;
+use crate::protocol::fetch::{self, events::RequestPausedParams, HeaderEntry};
+use crate::protocol::network;
+
+/// Decides what happens to each request paused by the Fetch domain, see
+/// `Tab::enable_fetch_interception`. Like a `RequestInterceptor`, it gets the tab's `Transport`
+/// and session ID, for calling methods on the tab while the request waits.
+pub type FetchInterceptor =
+    Box<dyn Fn(Arc<Transport>, SessionId, RequestPausedParams) -> FetchDecision + Send + Sync>;
+
+/// What to do with a paused request.
+#[derive(Debug, Clone)]
+pub enum FetchDecision {
+    /// Let the request go on, changed by whichever of the overrides are set.
+    Continue(RequestOverrides),
+    /// Answer the request with this response, without sending it.
+    Fulfill(FulfilledResponse),
+    /// Fail the request, as if the network had.
+    Fail(network::ErrorReason),
+}
+
+impl Default for FetchDecision {
+    fn default() -> Self {
+        FetchDecision::Continue(RequestOverrides::default())
+    }
+}
+
+/// Changes to a request that's let go on; the ones left `None` stay as they were.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOverrides {
+    pub url: Option<String>,
+    pub method: Option<String>,
+    /// Replaces all of the request's headers.
+    pub headers: Option<Vec<HeaderEntry>>,
+    pub post_data: Option<Vec<u8>>,
+}
+
+/// A response made up in place of the server's.
+#[derive(Debug, Clone)]
+pub struct FulfilledResponse {
+    pub status: u32,
+    pub headers: Vec<HeaderEntry>,
+    pub body: Vec<u8>,
+}
+
+impl FulfilledResponse {
+    pub fn new<B: Into<Vec<u8>>>(status: u32, body: B) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push(HeaderEntry::new(name, value));
+        self
+    }
+
+    /// The server's response to a request paused at the `Response` stage, given its body, or
+    /// `None` if the request was paused before being sent or it failed. Leaves out the
+    /// `Content-Encoding` and `Content-Length` headers, as the body comes decoded and is likely
+    /// to be changed.
+    pub fn from_paused(paused: &RequestPausedParams, body: Vec<u8>) -> Option<Self> {
+        if paused.response_error_reason.is_some() {
+            return None;
+        }
+        let mut response = Self {
+            status: paused.response_status_code?,
+            headers: paused.response_headers.clone().unwrap_or_default(),
+            body,
+        };
+        response.remove_header("Content-Encoding");
+        response.remove_header("Content-Length");
+        Some(response)
+    }
+
+    /// The value of the first header called `name`, ignoring case.
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_str())
+    }
+
+    /// Replaces any headers called `name` (ignoring case) with this one.
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.remove_header(name);
+        self.headers.push(HeaderEntry::new(name, value));
+    }
+
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|header| !header.name.eq_ignore_ascii_case(name));
+    }
+}
+
+/// Gets the server's response to a request paused at the `Response` stage, body and all, for
+/// a `FetchInterceptor` to change and answer the request with as `FetchDecision::Fulfill`.
+/// `None` if the request wasn't paused at that stage, or it failed.
+pub fn paused_response(
+    transport: &Transport,
+    session_id: SessionId,
+    paused: &RequestPausedParams,
+) -> Result<Option<FulfilledResponse>, Error> {
+    if !paused.is_response_stage() || paused.response_error_reason.is_some() {
+        return Ok(None);
+    }
+    let body = transport.call_method_on_target(
+        session_id,
+        fetch::methods::GetResponseBody {
+            request_id: &paused.request_id,
+        },
+    )?;
+    Ok(FulfilledResponse::from_paused(paused, decode_body(&body)?))
+}
+
+pub(crate) fn decode_body(
+    body: &fetch::methods::GetResponseBodyReturnObject,
+) -> Result<Vec<u8>, Error> {
+    if body.base64_encoded {
+        Ok(base64::decode(&body.body)?)
+    } else {
+        Ok(body.body.clone().into_bytes())
+    }
+}
+
+impl FetchDecision {
+    /// Answers the paused request `request_id` with this decision.
+    pub(crate) fn answer(
+        &self,
+        transport: &Transport,
+        session_id: SessionId,
+        request_id: &str,
+    ) -> Result<(), Error> {
+        match self {
+            FetchDecision::Continue(overrides) => {
+                let post_data = overrides.post_data.as_ref().map(base64::encode);
+                transport.call_method_on_target(
+                    session_id,
+                    fetch::methods::ContinueRequest {
+                        request_id,
+                        url: overrides.url.as_deref(),
+                        method: overrides.method.as_deref(),
+                        post_data: post_data.as_deref(),
+                        headers: overrides.headers.as_deref(),
+                    },
+                )?;
+            }
+            FetchDecision::Fulfill(response) => {
+                let body = base64::encode(&response.body);
+                transport.call_method_on_target(
+                    session_id,
+                    fetch::methods::FulfillRequest {
+                        request_id,
+                        response_code: response.status,
+                        response_headers: Some(&response.headers),
+                        body: Some(&body),
+                        response_phrase: None,
+                    },
+                )?;
+            }
+            FetchDecision::Fail(error_reason) => {
+                transport.call_method_on_target(
+                    session_id,
+                    fetch::methods::FailRequest {
+                        request_id,
+                        error_reason: *error_reason,
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,28 +1,41 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
 use std::sync::Mutex;
+use std::sync::{Arc, Weak};
 use std::thread;
 
 use failure::{Error, Fail};
 use log::*;
 use serde;
 
+use auth::{AnsweredChallenges, CredentialsProvider};
+use binding::ExposedFunctions;
+use console::ConsoleBuffer;
+use dialog::DialogPolicy;
 use element::Element;
+use har::{Har, HarRecorder};
+use interception::{FetchDecision, FetchInterceptor};
 use point::Point;
 
 use crate::browser::Transport;
 use crate::protocol::page::methods::Navigate;
 use crate::protocol::target::TargetId;
 use crate::protocol::target::TargetInfo;
-use crate::protocol::{dom, input, page, profiler, target};
-use crate::protocol::{network, Event};
+use crate::protocol::{dom, fetch, input, page, profiler, runtime, target};
+use crate::protocol::{network, Event, FromEvent};
 use crate::{protocol, util};
 
-use super::transport::SessionId;
+use super::transport::{CancellationHandle, MethodDestination, SessionId};
 use crate::protocol::dom::Node;
 use std::time::Duration;
 
+pub mod auth;
+pub mod binding;
+pub mod console;
+pub mod dialog;
 pub mod element;
+pub mod har;
+pub mod interception;
 pub mod keys;
 pub mod point;
 
@@ -43,15 +56,70 @@ pub type RequestInterceptor = Box<
         + Sync,
 >;
 
+/// Called with every event a tab receives; see `Tab::add_event_listener`.
+pub type EventListener = Arc<dyn Fn(&Event) + Send + Sync>;
+
+type EventListeners = Arc<Mutex<Vec<(usize, EventListener)>>>;
+
+/// Keeps a listener added with `Tab::add_event_listener` subscribed. Dropping it unsubscribes
+/// the listener.
+#[must_use = "the listener is removed as soon as its handle is dropped"]
+pub struct EventListenerHandle {
+    id: usize,
+    listeners: Weak<Mutex<Vec<(usize, EventListener)>>>,
+}
+
+impl Drop for EventListenerHandle {
+    fn drop(&mut self) {
+        if let Some(listeners) = self.listeners.upgrade() {
+            listeners.lock().unwrap().retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+/// A script added with `Tab::add_script_on_new_document`, kept so that it can be added again
+/// after re-attaching, which gives it a new identifier.
+#[derive(Debug, Clone)]
+pub(crate) struct NewDocumentScript {
+    /// The identifier it was first added under, which callers know it by.
+    pub identifier: page::ScriptIdentifier,
+    /// The identifier Chrome knows it by now.
+    pub current_identifier: page::ScriptIdentifier,
+    pub source: String,
+}
+
+/// The domains a tab has switched on beyond the ones every tab enables (Page and lifecycle
+/// events), so that they can be switched on again after re-attaching.
+#[derive(Debug, Default)]
+struct EnabledDomains {
+    network: bool,
+    runtime: bool,
+    profiler: bool,
+    debugger: bool,
+    request_interception: Option<Vec<network::methods::RequestPattern>>,
+    fetch_interception: Option<Vec<fetch::RequestPattern>>,
+    handle_auth_requests: bool,
+    scripts_on_new_document: Vec<NewDocumentScript>,
+}
+
 /// A handle to a single page. Exposes methods for simulating user actions (clicking,
 /// typing), and also for getting information about the DOM and other parts of the page.
 pub struct Tab {
     target_id: TargetId,
     transport: Arc<Transport>,
-    session_id: SessionId,
+    session_id: Arc<Mutex<SessionId>>,
     navigating: Arc<AtomicBool>,
     target_info: Arc<Mutex<TargetInfo>>,
     request_interceptor: Arc<Mutex<RequestInterceptor>>,
+    fetch_interceptor: Arc<Mutex<FetchInterceptor>>,
+    credentials_provider: Arc<Mutex<CredentialsProvider>>,
+    enabled_domains: Mutex<EnabledDomains>,
+    event_listeners: EventListeners,
+    next_listener_id: AtomicUsize,
+    console: Arc<Mutex<ConsoleBuffer>>,
+    dialog_policy: Arc<Mutex<DialogPolicy>>,
+    exposed_functions: Arc<Mutex<ExposedFunctions>>,
+    har_recorder: Arc<Mutex<Option<HarRecorder>>>,
 }
 
 #[derive(Debug, Fail)]
@@ -68,13 +136,7 @@ impl<'a> Tab {
     pub fn new(target_info: TargetInfo, transport: Arc<Transport>) -> Result<Self, Error> {
         let target_id = target_info.target_id.clone();
 
-        let session_id = transport
-            .call_method_on_browser(target::methods::AttachToTarget {
-                target_id: &target_id,
-                flatten: None,
-            })?
-            .session_id
-            .into();
+        let session_id = Self::attach(&transport, &target_id)?;
 
         debug!("New tab attached with session ID: {:?}", session_id);
 
@@ -83,12 +145,23 @@ impl<'a> Tab {
         let tab = Self {
             target_id,
             transport,
-            session_id,
+            session_id: Arc::new(Mutex::new(session_id)),
             navigating: Arc::new(AtomicBool::new(false)),
             target_info: target_info_mutex,
             request_interceptor: Arc::new(Mutex::new(Box::new(
                 |_transport, _session_id, _interception| RequestInterceptionDecision::Continue,
             ))),
+            fetch_interceptor: Arc::new(Mutex::new(Box::new(
+                |_transport, _session_id, _paused| FetchDecision::default(),
+            ))),
+            credentials_provider: Arc::new(Mutex::new(CredentialsProvider::default())),
+            enabled_domains: Mutex::new(EnabledDomains::default()),
+            event_listeners: Arc::new(Mutex::new(vec![])),
+            next_listener_id: AtomicUsize::new(0),
+            console: Arc::new(Mutex::new(ConsoleBuffer::default())),
+            dialog_policy: Arc::new(Mutex::new(DialogPolicy::default())),
+            exposed_functions: Arc::new(Mutex::new(ExposedFunctions::default())),
+            har_recorder: Arc::new(Mutex::new(None)),
         };
 
         tab.call_method(page::methods::Enable {})?;
@@ -99,6 +172,79 @@ impl<'a> Tab {
         Ok(tab)
     }
 
+    fn attach(transport: &Transport, target_id: &TargetId) -> Result<SessionId, Error> {
+        Ok(transport
+            .call_method_on_browser(target::methods::AttachToTarget {
+                target_id,
+                flatten: Some(true),
+            })?
+            .session_id
+            .into())
+    }
+
+    /// Attaches to the target again under a new session, e.g. after the browser's transport
+    /// reconnected, and turns back on whichever domains this tab had enabled.
+    pub fn reattach(&self) -> Result<(), Error> {
+        let new_session_id = Self::attach(&self.transport, &self.target_id)?;
+        debug!("Tab re-attached with session ID: {:?}", new_session_id);
+
+        {
+            let mut session_id = self.session_id.lock().unwrap();
+            self.transport
+                .move_target_listener(&session_id, new_session_id.clone());
+            *session_id = new_session_id;
+        }
+
+        self.call_method(page::methods::Enable {})?;
+        self.call_method(page::methods::SetLifecycleEventsEnabled { enabled: true })?;
+
+        let mut enabled_domains = self.enabled_domains.lock().unwrap();
+        if enabled_domains.network {
+            self.call_method(network::methods::Enable {
+                max_total_buffer_size: None,
+                max_resource_buffer_size: None,
+                max_post_data_size: None,
+            })?;
+        }
+        if enabled_domains.runtime {
+            self.call_method(runtime::methods::Enable {})?;
+        }
+        if enabled_domains.profiler {
+            self.call_method(profiler::methods::Enable {})?;
+        }
+        if enabled_domains.debugger {
+            self.call_method(protocol::debugger::methods::Enable {})?;
+        }
+        if let Some(patterns) = &enabled_domains.request_interception {
+            self.call_method(network::methods::SetRequestInterception { patterns })?;
+        }
+        if enabled_domains.fetch_interception.is_some() || enabled_domains.handle_auth_requests {
+            self.enable_fetch(&enabled_domains)?;
+        }
+        // the bindings have to be there before the scripts wrapping them run
+        let binding_names: Vec<String> =
+            self.exposed_functions.lock().unwrap().binding_names().collect();
+        for name in &binding_names {
+            self.call_method(runtime::methods::AddBinding {
+                name,
+                execution_context_name: None,
+            })?;
+        }
+        for script in &mut enabled_domains.scripts_on_new_document {
+            script.current_identifier = self
+                .call_method(page::methods::AddScriptToEvaluateOnNewDocument {
+                    source: &script.source,
+                    world_name: None,
+                })?
+                .identifier;
+        }
+        Ok(())
+    }
+
+    pub fn get_session_id(&self) -> SessionId {
+        self.session_id.lock().unwrap().clone()
+    }
+
     pub fn update_target_info(&self, target_info: TargetInfo) {
         let mut info = self.target_info.lock().unwrap();
         *info = target_info;
@@ -130,13 +276,32 @@ impl<'a> Tab {
         let transport: Arc<Transport> = Arc::clone(&self.transport);
         let incoming_events_rx = self
             .transport
-            .listen_to_target_events(self.session_id.clone());
+            .listen_to_target_events(self.get_session_id());
         let navigating = Arc::clone(&self.navigating);
         let interceptor_mutex = Arc::clone(&self.request_interceptor);
-        let session_id = self.session_id.clone();
+        let fetch_interceptor_mutex = Arc::clone(&self.fetch_interceptor);
+        let credentials_provider = Arc::clone(&self.credentials_provider);
+        let session_id_mutex = Arc::clone(&self.session_id);
+        let event_listeners = Arc::clone(&self.event_listeners);
+        let console = Arc::clone(&self.console);
+        let dialog_policy = Arc::clone(&self.dialog_policy);
+        let exposed_functions = Arc::clone(&self.exposed_functions);
+        let har_recorder = Arc::clone(&self.har_recorder);
+        let mut answered_challenges = AnsweredChallenges::default();
 
         thread::spawn(move || {
             for event in incoming_events_rx {
+                // cloned out, so that listeners can add or remove listeners themselves
+                let listeners: Vec<EventListener> = event_listeners
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(_, listener)| Arc::clone(listener))
+                    .collect();
+                for listener in listeners {
+                    listener(&event);
+                }
+
                 match event {
                     Event::Lifecycle(lifecycle_event) => {
                         match lifecycle_event.params.name.as_ref() {
@@ -151,6 +316,21 @@ impl<'a> Tab {
                     }
                     Event::RequestIntercepted(interception_event) => {
                         let id = interception_event.params.interception_id.clone();
+                        let session_id = session_id_mutex.lock().unwrap().clone();
+                        if let Some(challenge) = &interception_event.params.auth_challenge {
+                            // cloned out, so that the callback can set the provider itself
+                            let provider = credentials_provider.lock().unwrap().clone();
+                            let response = answered_challenges.respond(&provider, &id, challenge);
+                            let method = network::methods::ContinueInterceptedRequest {
+                                interception_id: &id,
+                                auth_challenge_response: Some(response.as_method_param()),
+                                ..Default::default()
+                            };
+                            if let Err(err) = transport.call_method_on_target(session_id, method) {
+                                warn!("Couldn't answer auth challenge for {}: {}", id, err);
+                            }
+                            continue;
+                        }
                         let interceptor = interceptor_mutex.lock().unwrap();
                         let decision = interceptor(
                             Arc::clone(&transport),
@@ -179,6 +359,122 @@ impl<'a> Tab {
                             }
                         }
                     }
+                    Event::RequestPaused(paused_event) => {
+                        let id = paused_event.params.request_id.clone();
+                        let session_id = session_id_mutex.lock().unwrap().clone();
+                        let interceptor = fetch_interceptor_mutex.lock().unwrap();
+                        let decision = interceptor(
+                            Arc::clone(&transport),
+                            session_id.clone(),
+                            paused_event.params,
+                        );
+                        if let Err(err) = decision.answer(&transport, session_id, &id) {
+                            warn!("Couldn't answer paused request {}: {}", id, err);
+                        }
+                    }
+                    Event::AuthRequired(auth_event) => {
+                        let id = auth_event.params.request_id;
+                        let session_id = session_id_mutex.lock().unwrap().clone();
+                        let provider = credentials_provider.lock().unwrap().clone();
+                        let response = answered_challenges.respond(
+                            &provider,
+                            &id,
+                            &auth_event.params.auth_challenge,
+                        );
+                        let method = fetch::methods::ContinueWithAuth {
+                            request_id: &id,
+                            auth_challenge_response: response.as_method_param(),
+                        };
+                        if let Err(err) = transport.call_method_on_target(session_id, method) {
+                            warn!("Couldn't answer auth challenge for {}: {}", id, err);
+                        }
+                    }
+                    Event::RequestWillBeSent(network_event) => {
+                        if let Some(recorder) = har_recorder.lock().unwrap().as_mut() {
+                            recorder.request_will_be_sent(&network_event.params);
+                        }
+                    }
+                    Event::ResponseReceived(network_event) => {
+                        if let Some(recorder) = har_recorder.lock().unwrap().as_mut() {
+                            recorder.response_received(&network_event.params);
+                        }
+                    }
+                    Event::DataReceived(network_event) => {
+                        if let Some(recorder) = har_recorder.lock().unwrap().as_mut() {
+                            recorder.data_received(&network_event.params);
+                        }
+                    }
+                    Event::LoadingFailed(network_event) => {
+                        if let Some(recorder) = har_recorder.lock().unwrap().as_mut() {
+                            recorder.loading_failed(&network_event.params);
+                        }
+                    }
+                    Event::LoadingFinished(network_event) => {
+                        let id = network_event.params.request_id.clone();
+                        let wants_body = match har_recorder.lock().unwrap().as_mut() {
+                            Some(recorder) => {
+                                recorder.loading_finished(&network_event.params);
+                                recorder.wants_body(&id)
+                            }
+                            None => false,
+                        };
+                        if wants_body {
+                            let session_id = session_id_mutex.lock().unwrap().clone();
+                            let method = network::methods::GetResponseBody { request_id: &id };
+                            match transport.call_method_on_target(session_id, method) {
+                                Ok(body) => {
+                                    if let Some(recorder) = har_recorder.lock().unwrap().as_mut() {
+                                        recorder.set_response_body(&id, body);
+                                    }
+                                }
+                                Err(err) => warn!("Couldn't get the body of {}: {}", id, err),
+                            }
+                        }
+                    }
+                    Event::ConsoleAPICalled(console_event) => {
+                        console
+                            .lock()
+                            .unwrap()
+                            .push_message(console_event.params.into());
+                    }
+                    Event::ExceptionThrown(exception_event) => {
+                        console
+                            .lock()
+                            .unwrap()
+                            .push_exception(exception_event.params.into());
+                    }
+                    Event::JavascriptDialogOpening(dialog_event) => {
+                        // cloned out, so that the callback can set the policy itself
+                        let policy = dialog_policy.lock().unwrap().clone();
+                        let response = policy.respond(&dialog_event.params);
+                        if let Some(response) = response {
+                            let session_id = session_id_mutex.lock().unwrap().clone();
+                            let method = page::methods::HandleJavaScriptDialog {
+                                accept: response.accept,
+                                prompt_text: response.prompt_text.as_deref(),
+                            };
+                            if let Err(err) = transport.call_method_on_target(session_id, method) {
+                                warn!("Couldn't answer {:?} dialog: {}", dialog_event.params, err);
+                            }
+                        }
+                    }
+                    Event::BindingCalled(binding_event) => {
+                        let call = exposed_functions
+                            .lock()
+                            .unwrap()
+                            .called(&binding_event.params);
+                        if let Some(expression) = call.and_then(binding::ExposedCall::call) {
+                            let session_id = session_id_mutex.lock().unwrap().clone();
+                            let method = runtime::methods::Evaluate {
+                                expression: &expression,
+                                context_id: Some(binding_event.params.execution_context_id),
+                                ..Default::default()
+                            };
+                            if let Err(err) = transport.call_method_on_target(session_id, method) {
+                                warn!("Couldn't return to {}: {}", binding_event.params.name, err);
+                            }
+                        }
+                    }
                     _ => {
                         let mut raw_event = format!("{:?}", event);
                         raw_event.truncate(50);
@@ -190,6 +486,43 @@ impl<'a> Tab {
         });
     }
 
+    /// Calls `listener` with every event this tab receives from now on, until the returned
+    /// handle is dropped. Listeners are called on the tab's event handling thread, in the order
+    /// they were added, so events queue up behind a listener that takes its time.
+    pub fn add_event_listener<F>(&self, listener: F) -> EventListenerHandle
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        let id = self.next_listener_id.fetch_add(1, Ordering::SeqCst);
+        self.event_listeners
+            .lock()
+            .unwrap()
+            .push((id, Arc::new(listener)));
+        EventListenerHandle {
+            id,
+            listeners: Arc::downgrade(&self.event_listeners),
+        }
+    }
+
+    /// Waits for the next event of type `E` (e.g. `page::events::FrameNavigatedEvent`, or
+    /// `protocol::RawEvent`) that `predicate` accepts. Only events received after the call
+    /// count. Fails with `util::Timeout` if none comes within `timeout`.
+    pub fn wait_for_event<E, P>(&self, predicate: P, timeout: Duration) -> Result<E, Error>
+    where
+        E: FromEvent + Send + 'static,
+        P: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        let (events_tx, events_rx) = mpsc::channel();
+        let _listener = self.add_event_listener(move |event| {
+            if let Some(event) = E::from_event(event).filter(|event| predicate(event)) {
+                let _ = events_tx.send(event);
+            }
+        });
+        events_rx
+            .recv_timeout(timeout)
+            .map_err(|_| util::Timeout.into())
+    }
+
     pub fn call_method<C>(&self, method: C) -> Result<C::ReturnObject, Error>
     where
         C: protocol::Method + serde::Serialize + std::fmt::Debug,
@@ -197,13 +530,51 @@ impl<'a> Tab {
         trace!("Calling method: {:?}", method);
         let result = self
             .transport
-            .call_method_on_target(self.session_id.clone(), method);
+            .call_method_on_target(self.get_session_id(), method);
         let mut result_string = format!("{:?}", result);
         result_string.truncate(70);
         trace!("Got result: {:?}", result_string);
         result
     }
 
+    /// Like `call_method`, but fails with `MethodCallTimeout` if Chrome hasn't answered
+    /// within `timeout`.
+    pub fn call_method_with_timeout<C>(
+        &self,
+        method: C,
+        timeout: Duration,
+    ) -> Result<C::ReturnObject, Error>
+    where
+        C: protocol::Method + serde::Serialize + std::fmt::Debug,
+    {
+        trace!("Calling method with timeout {:?}: {:?}", timeout, method);
+        self.transport.call_method_with_timeout(
+            method,
+            MethodDestination::Target(self.get_session_id()),
+            timeout,
+        )
+    }
+
+    /// Like `call_method_with_timeout`, but `cancellation` can also be used from another
+    /// thread to stop waiting, e.g. when whatever needed the result has gone away.
+    pub fn call_method_cancellable<C>(
+        &self,
+        method: C,
+        timeout: Duration,
+        cancellation: &CancellationHandle,
+    ) -> Result<C::ReturnObject, Error>
+    where
+        C: protocol::Method + serde::Serialize + std::fmt::Debug,
+    {
+        trace!("Calling cancellable method: {:?}", method);
+        self.transport.call_method_cancellable(
+            method,
+            MethodDestination::Target(self.get_session_id()),
+            timeout,
+            cancellation,
+        )
+    }
+
     pub fn wait_until_navigated(&self) -> Result<&Self, Error> {
         debug!("waiting to start navigating");
         // wait for navigating to go to true
@@ -498,9 +869,107 @@ impl<'a> Tab {
         Ok(self)
     }
 
+    /// Evaluates `source` in every frame of this tab as soon as it gets a new document, before
+    /// the page's own scripts run, e.g. to install polyfills or mocks. Pages that are already
+    /// loaded aren't affected. The script is added again if the tab re-attaches, under the same
+    /// identifier as far as `remove_script_on_new_document` is concerned.
+    pub fn add_script_on_new_document(&self, source: &str) -> Result<page::ScriptIdentifier, Error> {
+        let identifier = self
+            .call_method(page::methods::AddScriptToEvaluateOnNewDocument {
+                source,
+                world_name: None,
+            })?
+            .identifier;
+        self.enabled_domains
+            .lock()
+            .unwrap()
+            .scripts_on_new_document
+            .push(NewDocumentScript {
+                identifier: identifier.clone(),
+                current_identifier: identifier.clone(),
+                source: source.to_string(),
+            });
+        Ok(identifier)
+    }
+
+    /// Stops evaluating a script added with `add_script_on_new_document` in new documents.
+    pub fn remove_script_on_new_document(&self, identifier: &str) -> Result<&Self, Error> {
+        let current_identifier = {
+            let mut enabled_domains = self.enabled_domains.lock().unwrap();
+            let scripts = &mut enabled_domains.scripts_on_new_document;
+            match scripts.iter().position(|script| script.identifier == identifier) {
+                Some(index) => scripts.remove(index).current_identifier,
+                None => identifier.to_string(),
+            }
+        };
+        self.call_method(page::methods::RemoveScriptToEvaluateOnNewDocument {
+            identifier: &current_identifier,
+        })?;
+        Ok(self)
+    }
+
+    /// Makes `function` callable from the page as `window.<name>(...)`, returning a promise of
+    /// its result, or rejected with its error. It gets the arguments the page passed as an array,
+    /// and is called on the tab's event handling thread. The function is available in the
+    /// current document and every new one, also after re-attaching. Enables the Runtime domain,
+    /// which the calls come through.
+    pub fn expose_function<F>(&self, name: &str, function: F) -> Result<&Self, Error>
+    where
+        F: Fn(serde_json::Value) -> Result<serde_json::Value, Error> + Send + Sync + 'static,
+    {
+        self.exposed_functions
+            .lock()
+            .unwrap()
+            .insert(name, Arc::new(function));
+        self.enable_runtime()?;
+        self.call_method(runtime::methods::AddBinding {
+            name: &binding::binding_name(name),
+            execution_context_name: None,
+        })?;
+        let wrapper = binding::page_wrapper(name);
+        self.add_script_on_new_document(&wrapper)?;
+        self.call_method(runtime::methods::Evaluate {
+            expression: &wrapper,
+            ..Default::default()
+        })?;
+        Ok(self)
+    }
+
+    /// Sets how this tab answers `alert`, `confirm`, `prompt` and `beforeunload` dialogs. By
+    /// default it accepts them.
+    pub fn set_dialog_policy(&self, policy: DialogPolicy) -> &Self {
+        *self.dialog_policy.lock().unwrap() = policy;
+        self
+    }
+
+    /// Answers the dialog the page has open, for when the policy is `DialogPolicy::Manual`.
+    pub fn handle_java_script_dialog(
+        &self,
+        accept: bool,
+        prompt_text: Option<&str>,
+    ) -> Result<&Self, Error> {
+        self.call_method(page::methods::HandleJavaScriptDialog {
+            accept,
+            prompt_text,
+        })?;
+        Ok(self)
+    }
+
+    /// Closes the tab, without running its `beforeunload` handlers. Returns whether Chrome
+    /// reported it closed.
+    pub fn close(&self) -> Result<bool, Error> {
+        Ok(self
+            .transport
+            .call_method_on_browser(target::methods::CloseTarget {
+                target_id: &self.target_id,
+            })?
+            .success)
+    }
+
     /// Enables the profiler
     pub fn enable_profiler(&self) -> Result<&Self, Error> {
         self.call_method(profiler::methods::Enable {})?;
+        self.enabled_domains.lock().unwrap().profiler = true;
 
         Ok(self)
     }
@@ -508,10 +977,129 @@ impl<'a> Tab {
     /// Disables the profiler
     pub fn disable_profiler(&self) -> Result<&Self, Error> {
         self.call_method(profiler::methods::Disable {})?;
+        self.enabled_domains.lock().unwrap().profiler = false;
+
+        Ok(self)
+    }
+
+    /// Enables network tracking; network events will now be delivered to the tab.
+    pub fn enable_network(&self) -> Result<&Self, Error> {
+        self.call_method(network::methods::Enable {
+            max_total_buffer_size: None,
+            max_resource_buffer_size: None,
+            max_post_data_size: None,
+        })?;
+        self.enabled_domains.lock().unwrap().network = true;
+
+        Ok(self)
+    }
+
+    /// Disables network tracking
+    pub fn disable_network(&self) -> Result<&Self, Error> {
+        self.call_method(network::methods::Disable {})?;
+        self.enabled_domains.lock().unwrap().network = false;
+
+        Ok(self)
+    }
 
+    /// Starts recording the tab's network traffic for `har`, enabling the Network domain if it
+    /// isn't yet; anything recorded before is dropped. With `record_bodies`, each response's body
+    /// is fetched once it finished loading, which holds up the tab's other events meanwhile.
+    pub fn start_har_recording(&self, record_bodies: bool) -> Result<&Self, Error> {
+        *self.har_recorder.lock().unwrap() = Some(HarRecorder::new(record_bodies));
+        let network_enabled = self.enabled_domains.lock().unwrap().network;
+        if !network_enabled {
+            self.enable_network()?;
+        }
         Ok(self)
     }
 
+    /// The traffic recorded since `start_har_recording`, or `None` if it's not recording.
+    pub fn har(&self) -> Option<Har> {
+        self.har_recorder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(HarRecorder::har)
+    }
+
+    /// Stops recording, returning what was recorded; the Network domain stays enabled.
+    pub fn stop_har_recording(&self) -> Option<Har> {
+        self.har_recorder
+            .lock()
+            .unwrap()
+            .take()
+            .map(|recorder| recorder.har())
+    }
+
+    /// The cookies that would be sent along with a request to the page's URL.
+    pub fn get_cookies(&self) -> Result<Vec<network::Cookie>, Error> {
+        Ok(self
+            .call_method(network::methods::GetCookies::default())?
+            .cookies)
+    }
+
+    /// Every cookie in the tab's browser context, whatever its URL.
+    pub fn get_all_cookies(&self) -> Result<Vec<network::Cookie>, Error> {
+        Ok(self.call_method(network::methods::GetAllCookies {})?.cookies)
+    }
+
+    /// Sets cookies, e.g. the ones `cookies::load` read from a file.
+    pub fn set_cookies(&self, cookies: &[network::CookieParam]) -> Result<&Self, Error> {
+        self.call_method(network::methods::SetCookies { cookies })?;
+        Ok(self)
+    }
+
+    /// Deletes the cookies matching each of `cookies`; the ones with neither a `url` nor a
+    /// `domain` are matched against the page's URL.
+    pub fn delete_cookies(
+        &self,
+        cookies: &[network::methods::DeleteCookies],
+    ) -> Result<&Self, Error> {
+        let mut page_url = None;
+        for cookie in cookies {
+            let mut cookie = cookie.clone();
+            if cookie.url.is_none() && cookie.domain.is_none() {
+                cookie.url = Some(page_url.get_or_insert_with(|| self.get_url()));
+            }
+            self.call_method(cookie)?;
+        }
+        Ok(self)
+    }
+
+    /// Deletes every cookie in the browser, not just the ones of the tab's context.
+    pub fn clear_browser_cookies(&self) -> Result<&Self, Error> {
+        self.call_method(network::methods::ClearBrowserCookies {})?;
+        Ok(self)
+    }
+
+    /// Enables the Runtime domain, from which point on what the page logs to its console and the
+    /// exceptions it doesn't catch are kept in `console()`.
+    pub fn enable_runtime(&self) -> Result<&Self, Error> {
+        self.call_method(runtime::methods::Enable {})?;
+        self.enabled_domains.lock().unwrap().runtime = true;
+
+        Ok(self)
+    }
+
+    /// Disables the Runtime domain; what was already captured stays in `console()`.
+    pub fn disable_runtime(&self) -> Result<&Self, Error> {
+        self.call_method(runtime::methods::Disable {})?;
+        self.enabled_domains.lock().unwrap().runtime = false;
+
+        Ok(self)
+    }
+
+    /// A copy of the console messages and uncaught exceptions captured since `enable_runtime`
+    /// (or the last `clear_console`), e.g. to fail a test if `console().has_errors()`.
+    pub fn console(&self) -> ConsoleBuffer {
+        self.console.lock().unwrap().clone()
+    }
+
+    pub fn clear_console(&self) {
+        self.console.lock().unwrap().clear();
+    }
+
     /// Starts tracking which lines of JS have been executed
     ///
     /// Will return error unless `enable_profiler` has been called.
@@ -573,6 +1161,82 @@ impl<'a> Tab {
         self.call_method(network::methods::SetRequestInterception {
             patterns: &patterns,
         })?;
+        self.enabled_domains.lock().unwrap().request_interception = Some(patterns.to_vec());
+        Ok(())
+    }
+
+    /// Pauses the outgoing requests matching `patterns` (`RequestPattern::default()` matches all
+    /// of them) through the Fetch domain, and lets `interceptor` decide what happens to each: it
+    /// can let the request go on with a different URL, method, headers or post data, answer it
+    /// with a response of its own, or fail it. Unlike `enable_request_interception`, which goes
+    /// through the deprecated `Network.setRequestInterception`, this doesn't need the Network
+    /// domain.
+    ///
+    /// Patterns with `request_stage: Some(RequestStage::Response)` pause requests once the
+    /// server's response headers arrived instead. `interception::paused_response` gets that
+    /// response along with its body, for answering the request with a changed version of it.
+    ///
+    /// The interceptor is called on the tab's event handling thread, so it mustn't wait on
+    /// other events from this tab.
+    pub fn enable_fetch_interception(
+        &self,
+        patterns: &[fetch::RequestPattern],
+        interceptor: FetchInterceptor,
+    ) -> Result<&Self, Error> {
+        *self.fetch_interceptor.lock().unwrap() = interceptor;
+        let mut enabled_domains = self.enabled_domains.lock().unwrap();
+        enabled_domains.fetch_interception = Some(patterns.to_vec());
+        self.enable_fetch(&enabled_domains)?;
+        Ok(self)
+    }
+
+    /// Stops pausing requests; the ones still paused are let go on. Authentication challenges
+    /// are still answered, if there's a `CredentialsProvider` for them.
+    pub fn disable_fetch_interception(&self) -> Result<&Self, Error> {
+        let mut enabled_domains = self.enabled_domains.lock().unwrap();
+        enabled_domains.fetch_interception = None;
+        if enabled_domains.handle_auth_requests {
+            self.enable_fetch(&enabled_domains)?;
+        } else {
+            self.call_method(fetch::methods::Disable {})?;
+        }
+        Ok(self)
+    }
+
+    /// Sets how this tab answers HTTP authentication challenges, from servers or proxies, which
+    /// otherwise leave the page waiting. Anything other than `CredentialsProvider::BrowserDefault`
+    /// enables the Fetch domain, pausing every request if `enable_fetch_interception` hasn't
+    /// picked which ones; they're let go on unchanged unless its interceptor says otherwise.
+    ///
+    /// Challenges coming through `enable_request_interception` are answered the same way.
+    pub fn set_credentials_provider(&self, provider: CredentialsProvider) -> Result<&Self, Error> {
+        let handle_auth_requests = !matches!(provider, CredentialsProvider::BrowserDefault);
+        *self.credentials_provider.lock().unwrap() = provider;
+        let mut enabled_domains = self.enabled_domains.lock().unwrap();
+        if enabled_domains.handle_auth_requests == handle_auth_requests {
+            return Ok(self);
+        }
+        enabled_domains.handle_auth_requests = handle_auth_requests;
+        if handle_auth_requests || enabled_domains.fetch_interception.is_some() {
+            self.enable_fetch(&enabled_domains)?;
+        } else {
+            self.call_method(fetch::methods::Disable {})?;
+        }
+        Ok(self)
+    }
+
+    /// Calls `Fetch.enable` for the interception and authentication handling switched on.
+    fn enable_fetch(&self, enabled_domains: &EnabledDomains) -> Result<(), Error> {
+        let all_requests = [fetch::RequestPattern::default()];
+        let patterns = enabled_domains
+            .fetch_interception
+            .as_deref()
+            .unwrap_or(&all_requests);
+        self.call_method(fetch::methods::Enable {
+            patterns: Some(patterns),
+            handle_auth_requests: Some(enabled_domains.handle_auth_requests)
+                .filter(|handle| *handle),
+        })?;
         Ok(())
     }
 
@@ -601,12 +1265,14 @@ impl<'a> Tab {
     /// Enables Debugger
     pub fn enable_debugger(&self) -> Result<(), Error> {
         self.call_method(protocol::debugger::methods::Enable {})?;
+        self.enabled_domains.lock().unwrap().debugger = true;
         Ok(())
     }
 
     /// Disables Debugger
     pub fn disable_debugger(&self) -> Result<(), Error> {
         self.call_method(protocol::debugger::methods::Disable {})?;
+        self.enabled_domains.lock().unwrap().debugger = false;
         Ok(())
     }
 
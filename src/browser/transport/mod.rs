@@ -3,7 +3,7 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc;
 use std::sync::mpsc::Sender;
 use std::sync::mpsc::{Receiver, RecvTimeoutError, TryRecvError};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::sync::Mutex;
 use std::time::Duration;
 
@@ -11,15 +11,22 @@ use failure::{Error, Fail};
 use log::*;
 use serde;
 
+use pipe_connection::PipeConnection;
+use recording::{Direction, Recorder, Replayer};
+use replay_connection::ReplayConnection;
 use waiting_call_registry::WaitingCallRegistry;
 use web_socket_connection::WebSocketConnection;
 
-use crate::protocol::target;
+use crate::browser::process::{DebuggingPipe, ExitMonitor};
+
 use crate::protocol::CallId;
 use crate::protocol::Event;
 use crate::protocol::Message;
-use crate::{protocol, util};
+use crate::protocol;
 
+mod pipe_connection;
+pub mod recording;
+mod replay_connection;
 mod waiting_call_registry;
 mod web_socket_connection;
 
@@ -51,30 +58,261 @@ enum ListenerId {
 
 type Listeners = Arc<Mutex<HashMap<ListenerId, Sender<Event>>>>;
 
+type SharedConnection = Arc<Mutex<Arc<dyn Connection>>>;
+
+/// The channel protocol messages travel over: a WebSocket or the pipe Chrome opens with
+/// `--remote-debugging-pipe`. Incoming messages are pushed into the channel the connection
+/// was created with.
+pub trait Connection: Send + Sync + std::fmt::Debug {
+    fn send_message(&self, message_text: &str) -> Result<(), Error>;
+
+    fn shutdown(&self);
+}
+
+/// How a `Transport` connected to a WebSocket URL behaves when that connection drops.
+///
+/// Each failed attempt doubles the delay before the next one, up to `max_backoff`.
+#[derive(Debug, Clone, Builder)]
+pub struct ReconnectPolicy {
+    /// How many times to try re-opening the connection before giving up. Defaults to 5.
+    #[builder(default = "5")]
+    max_attempts: u32,
+
+    /// Delay before the first attempt. Defaults to 500ms.
+    #[builder(default = "Duration::from_millis(500)")]
+    initial_backoff: Duration,
+
+    /// Upper bound for the delay between attempts. Defaults to 10s.
+    #[builder(default = "Duration::from_secs(10)")]
+    max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicyBuilder::default().build().unwrap()
+    }
+}
+
+type ReconnectListeners = Arc<Mutex<Vec<Sender<()>>>>;
+
+/// What the incoming message loop needs in order to open a fresh WebSocket connection.
+struct Reconnector {
+    ws_url: String,
+    policy: ReconnectPolicy,
+    messages_tx: Sender<protocol::Message>,
+    listeners: ReconnectListeners,
+    recorder: Option<Arc<Recorder>>,
+}
+
 #[derive(Debug)]
 pub struct Transport {
-    web_socket_connection: Arc<WebSocketConnection>,
+    connection: SharedConnection,
     waiting_call_registry: Arc<WaitingCallRegistry>,
     listeners: Listeners,
+    reconnect_listeners: ReconnectListeners,
     open: Arc<AtomicBool>,
     call_id_counter: Arc<AtomicUsize>,
     loop_shutdown_tx: Mutex<mpsc::Sender<()>>,
+    recorder: Option<Arc<Recorder>>,
+    exit_monitor: Option<ExitMonitor>,
 }
 
 #[derive(Debug, Fail)]
 #[fail(display = "Unable to make method calls because underlying connection is closed")]
 pub struct ConnectionClosed {}
 
+#[derive(Debug, Fail)]
+#[fail(display = "No response to {} within {:?}", method, timeout)]
+pub struct MethodCallTimeout {
+    pub method: &'static str,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "Call to {} was cancelled", method)]
+pub struct MethodCallCancelled {
+    pub method: &'static str,
+}
+
+/// How long `call_method` waits for a response.
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Lets another thread give up on calls made with `call_method_cancellable`: they stop
+/// waiting and fail with `MethodCallCancelled`.
+///
+/// A handle can be shared by several calls, and once cancelled stays that way, so calls
+/// made with it afterwards fail straight away.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationHandle {
+    state: Arc<Mutex<CancellationState>>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: bool,
+    waiting_calls: Vec<(CallId, Weak<WaitingCallRegistry>)>,
+}
+
+impl CancellationHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        for (call_id, registry) in state.waiting_calls.drain(..) {
+            if let Some(registry) = registry.upgrade() {
+                registry.unregister_call(call_id);
+            }
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    /// Returns false if the handle has already been cancelled.
+    fn watch(&self, call_id: CallId, registry: &Arc<WaitingCallRegistry>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.cancelled {
+            return false;
+        }
+        state
+            .waiting_calls
+            .push((call_id, Arc::downgrade(registry)));
+        true
+    }
+
+    fn unwatch(&self, call_id: CallId) {
+        let mut state = self.state.lock().unwrap();
+        state.waiting_calls.retain(|(id, _)| *id != call_id);
+    }
+}
+
 impl Transport {
     pub fn new(ws_url: String, process_id: Option<u32>) -> Result<Self, Error> {
+        Self::with_web_socket(&ws_url, process_id, None)
+    }
+
+    /// Like `new`, but writes every message sent and received to `recorder`.
+    pub fn with_recorder(
+        ws_url: String,
+        process_id: Option<u32>,
+        recorder: Recorder,
+    ) -> Result<Self, Error> {
+        Self::with_web_socket(&ws_url, process_id, Some(Arc::new(recorder)))
+    }
+
+    /// A transport that isn't connected to any browser, but answers method calls from a
+    /// recording made with `with_recorder`.
+    pub fn replay(replayer: Replayer) -> Self {
         let (messages_tx, messages_rx) = mpsc::channel();
-        let web_socket_connection =
-            Arc::new(WebSocketConnection::new(&ws_url, process_id, messages_tx)?);
+        let replay_connection = Arc::new(ReplayConnection::new(replayer, messages_tx));
+
+        Self::with_connection(replay_connection, messages_rx, None, None, None)
+    }
+
+    fn with_web_socket(
+        ws_url: &str,
+        process_id: Option<u32>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> Result<Self, Error> {
+        let (messages_tx, messages_rx) = mpsc::channel();
+        let web_socket_connection = Arc::new(WebSocketConnection::new(
+            ws_url,
+            process_id,
+            messages_tx,
+            recorder.clone(),
+        )?);
+
+        Ok(Self::with_connection(
+            web_socket_connection,
+            messages_rx,
+            process_id,
+            None,
+            recorder,
+        ))
+    }
+
+    /// Like `new`, but when the WebSocket drops the transport tries to connect to `ws_url` again
+    /// according to `policy` instead of closing for good.
+    ///
+    /// Method calls that were waiting for a response when the connection dropped still fail
+    /// with `ConnectionClosed`. Session IDs from before the drop are no longer valid; see
+    /// `listen_to_reconnects`.
+    pub fn with_reconnect(
+        ws_url: String,
+        process_id: Option<u32>,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, Error> {
+        let (messages_tx, messages_rx) = mpsc::channel();
+        let web_socket_connection = Arc::new(WebSocketConnection::new(
+            &ws_url,
+            process_id,
+            messages_tx.clone(),
+            None,
+        )?);
+
+        let reconnector = Reconnector {
+            ws_url,
+            policy,
+            messages_tx,
+            listeners: Arc::new(Mutex::new(Vec::new())),
+            recorder: None,
+        };
+
+        Ok(Self::with_connection(
+            web_socket_connection,
+            messages_rx,
+            process_id,
+            Some(reconnector),
+            None,
+        ))
+    }
+
+    /// Speaks the protocol over the file descriptors of a Chrome launched with
+    /// `--remote-debugging-pipe` rather than over a WebSocket.
+    pub fn with_pipe(
+        pipe: DebuggingPipe,
+        process_id: Option<u32>,
+        recorder: Option<Recorder>,
+    ) -> Result<Self, Error> {
+        let recorder = recorder.map(Arc::new);
+        let (messages_tx, messages_rx) = mpsc::channel();
+        let pipe_connection = Arc::new(PipeConnection::new(
+            pipe,
+            process_id,
+            messages_tx,
+            recorder.clone(),
+        ));
+
+        Ok(Self::with_connection(
+            pipe_connection,
+            messages_rx,
+            process_id,
+            None,
+            recorder,
+        ))
+    }
+
+    fn with_connection(
+        connection: Arc<dyn Connection>,
+        messages_rx: Receiver<protocol::Message>,
+        process_id: Option<u32>,
+        reconnector: Option<Reconnector>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> Self {
+        let connection = Arc::new(Mutex::new(connection));
 
         let waiting_call_registry = Arc::new(WaitingCallRegistry::new());
 
         let listeners = Arc::new(Mutex::new(HashMap::new()));
 
+        let reconnect_listeners = reconnector
+            .as_ref()
+            .map_or_else(|| Arc::new(Mutex::new(Vec::new())), |r| Arc::clone(&r.listeners));
+
         let open = Arc::new(AtomicBool::new(true));
 
         let (shutdown_tx, shutdown_rx) = mpsc::channel();
@@ -86,19 +324,30 @@ impl Transport {
             Arc::clone(&waiting_call_registry),
             Arc::clone(&listeners),
             Arc::clone(&open),
-            Arc::clone(&web_socket_connection),
+            Arc::clone(&connection),
             shutdown_rx,
             process_id,
+            reconnector,
         );
 
-        Ok(Self {
-            web_socket_connection,
+        Self {
+            connection,
             waiting_call_registry,
             listeners,
+            reconnect_listeners,
             open,
             call_id_counter: Arc::new(AtomicUsize::new(0)),
             loop_shutdown_tx: guarded_shutdown_tx,
-        })
+            recorder,
+            exit_monitor: None,
+        }
+    }
+
+    /// Makes method calls fail with `ChromeExited` rather than a connection error when the
+    /// process at the other end has exited.
+    pub fn watching(mut self, exit_monitor: ExitMonitor) -> Self {
+        self.exit_monitor = Some(exit_monitor);
+        self
     }
 
     /// Returns a number based on thread-safe unique counter, incrementing it so that the
@@ -112,6 +361,75 @@ impl Transport {
         method: C,
         destination: MethodDestination,
     ) -> Result<C::ReturnObject, Error>
+    where
+        C: protocol::Method + serde::Serialize,
+    {
+        self.call_method_with_timeout(method, destination, DEFAULT_CALL_TIMEOUT)
+    }
+
+    /// Like `call_method`, but gives up with `MethodCallTimeout` if there's no response
+    /// within `timeout`.
+    pub fn call_method_with_timeout<C>(
+        &self,
+        method: C,
+        destination: MethodDestination,
+        timeout: Duration,
+    ) -> Result<C::ReturnObject, Error>
+    where
+        C: protocol::Method + serde::Serialize,
+    {
+        self.send_call(method, destination, timeout, None)
+    }
+
+    /// Like `call_method_with_timeout`, but can also be aborted from another thread through
+    /// `cancellation`.
+    pub fn call_method_cancellable<C>(
+        &self,
+        method: C,
+        destination: MethodDestination,
+        timeout: Duration,
+        cancellation: &CancellationHandle,
+    ) -> Result<C::ReturnObject, Error>
+    where
+        C: protocol::Method + serde::Serialize,
+    {
+        self.send_call(method, destination, timeout, Some(cancellation))
+    }
+
+    fn send_call<C>(
+        &self,
+        method: C,
+        destination: MethodDestination,
+        timeout: Duration,
+        cancellation: Option<&CancellationHandle>,
+    ) -> Result<C::ReturnObject, Error>
+    where
+        C: protocol::Method + serde::Serialize,
+    {
+        self.send_call_unexplained(method, destination, timeout, cancellation)
+            .map_err(|err| self.explain_failure(err))
+    }
+
+    /// Swaps `err` for the more useful `ChromeExited` if Chrome has exited (or is just about to,
+    /// judging by the connection being closed).
+    fn explain_failure(&self, err: Error) -> Error {
+        let exited = self.exit_monitor.as_ref().and_then(|exit_monitor| {
+            if err.downcast_ref::<ConnectionClosed>().is_some() {
+                exit_monitor.wait_for_exit(Duration::from_secs(1))
+            } else {
+                exit_monitor.exited()
+            }
+        });
+        exited.map_or(err, Into::into)
+    }
+
+    fn send_call_unexplained<C>(
+        &self,
+        method: C,
+        destination: MethodDestination,
+        timeout: Duration,
+        cancellation: Option<&CancellationHandle>,
+    ) -> Result<C::ReturnObject, Error>
     where
         C: protocol::Method + serde::Serialize,
     {
@@ -120,7 +438,14 @@ impl Transport {
             return Err(ConnectionClosed {}.into());
         }
         let call_id = self.unique_call_id();
-        let call = method.to_method_call(call_id);
+        // targets are attached with `flatten: true`, so calls to them go over the same
+        // connection, just tagged with the session ID
+        let call = match destination {
+            MethodDestination::Target(session_id) => {
+                method.to_session_method_call(call_id, session_id.as_str())
+            }
+            MethodDestination::Browser => method.to_method_call(call_id),
+        };
 
         let message_text = serde_json::to_string(&call)?;
 
@@ -129,33 +454,25 @@ impl Transport {
         // at some time later, transport should write data to other end of channel. So we just do block receiving.
         let response_rx = self.waiting_call_registry.register_call(call.id);
 
-        match destination {
-            MethodDestination::Target(session_id) => {
-                let target_method = target::methods::SendMessageToTarget {
-                    target_id: None,
-                    session_id: Some(session_id.as_str()),
-                    message: &message_text,
-                };
-                let mut raw = message_text.clone();
-                raw.truncate(300);
-                trace!("Msg to tab: {}", &raw);
-                if let Err(e) = self.call_method_on_browser(target_method) {
-                    error!("Failed to call method on browser: {:?}", e);
-                    self.waiting_call_registry.unregister_call(call.id);
-                    trace!("Unregistered callback: {:?}", call.id);
-                    return Err(e);
-                }
-            }
-            MethodDestination::Browser => {
-                if let Err(e) = self.web_socket_connection.send_message(&message_text) {
-                    self.waiting_call_registry.unregister_call(call.id);
-                    return Err(e);
-                } else {
-                    trace!("sent method call to browser via websocket");
-                }
+        if let Some(cancellation) = cancellation {
+            if !cancellation.watch(call.id, &self.waiting_call_registry) {
+                self.waiting_call_registry.unregister_call(call.id);
+                return Err(MethodCallCancelled { method: C::NAME }.into());
             }
         }
 
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Direction::Sent, &message_text);
+        }
+        let connection = Arc::clone(&*self.connection.lock().unwrap());
+        if let Err(e) = connection.send_message(&message_text) {
+            self.waiting_call_registry.unregister_call(call.id);
+            trace!("Unregistered callback: {:?}", call.id);
+            return Err(e);
+        } else {
+            trace!("sent method call");
+        }
+
         let mut params_string = format!("{:?}", call.get_params());
         params_string.truncate(400);
         trace!(
@@ -164,10 +481,28 @@ impl Transport {
             params_string
         );
 
-        let response_result = util::Wait::new(Duration::from_secs(15), Duration::from_millis(5))
-            .until(|| response_rx.try_recv().ok());
-        trace!("received response for: {} {:?}", &call_id, params_string);
-        protocol::parse_response::<C::ReturnObject>((response_result?)?)
+        let response_result = response_rx.recv_timeout(timeout);
+        if let Some(cancellation) = cancellation {
+            cancellation.unwatch(call.id);
+        }
+        match response_result {
+            Ok(response) => {
+                trace!("received response for: {} {:?}", &call_id, params_string);
+                protocol::parse_response::<C::ReturnObject>(response?)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                self.waiting_call_registry.unregister_call(call.id);
+                Err(MethodCallTimeout {
+                    method: C::NAME,
+                    timeout,
+                }
+                .into())
+            }
+            // the registry only lets go of a call without answering it when it's cancelled
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(MethodCallCancelled { method: C::NAME }.into())
+            }
+        }
     }
 
     pub fn call_method_on_target<C>(
@@ -207,20 +542,80 @@ impl Transport {
         events_rx
     }
 
+    /// Moves the listener registered for `old_session_id` over to `new_session_id`, e.g. after
+    /// re-attaching to a target once the transport has reconnected.
+    pub fn move_target_listener(&self, old_session_id: &SessionId, new_session_id: SessionId) {
+        let mut listeners = self.listeners.lock().unwrap();
+        if let Some(events_tx) = listeners.remove(&ListenerId::SessionId(old_session_id.clone())) {
+            listeners.insert(ListenerId::SessionId(new_session_id), events_tx);
+        }
+    }
+
+    /// Receives a message every time the transport re-opens a dropped connection. Only
+    /// transports created with `with_reconnect` ever send one.
+    pub fn listen_to_reconnects(&self) -> Receiver<()> {
+        let (reconnects_tx, reconnects_rx) = mpsc::channel();
+        self.reconnect_listeners.lock().unwrap().push(reconnects_tx);
+        reconnects_rx
+    }
+
     pub fn shutdown(&self) {
-        self.web_socket_connection.shutdown();
+        // tell the loop first, so that it doesn't try to reconnect once the connection closes
         let shutdown_tx = self.loop_shutdown_tx.lock().unwrap();
         let _ = shutdown_tx.send(());
+        self.connection.lock().unwrap().shutdown();
     }
 
+    fn reconnect(
+        reconnector: &Reconnector,
+        conn: &SharedConnection,
+        shutdown_rx: &Receiver<()>,
+        process_id: Option<u32>,
+    ) -> bool {
+        let policy = &reconnector.policy;
+        let mut backoff = policy.initial_backoff;
+
+        for attempt in 1..=policy.max_attempts {
+            std::thread::sleep(backoff);
+            match shutdown_rx.try_recv() {
+                Ok(_) | Err(TryRecvError::Disconnected) => return false,
+                Err(TryRecvError::Empty) => {}
+            }
+
+            info!(
+                "Reconnecting to {} (Chrome #{:?}), attempt {} of {}",
+                reconnector.ws_url, process_id, attempt, policy.max_attempts
+            );
+            match WebSocketConnection::new(
+                &reconnector.ws_url,
+                process_id,
+                reconnector.messages_tx.clone(),
+                reconnector.recorder.clone(),
+            ) {
+                Ok(connection) => {
+                    *conn.lock().unwrap() = Arc::new(connection);
+                    return true;
+                }
+                Err(err) => {
+                    warn!("Couldn't reconnect to {}: {}", reconnector.ws_url, err);
+                    backoff = std::cmp::min(backoff * 2, policy.max_backoff);
+                }
+            }
+        }
+
+        false
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn handle_incoming_messages(
         messages_rx: Receiver<protocol::Message>,
         waiting_call_registry: Arc<WaitingCallRegistry>,
         listeners: Listeners,
         open: Arc<AtomicBool>,
-        conn: Arc<WebSocketConnection>,
+        conn: SharedConnection,
         shutdown_rx: Receiver<()>,
         process_id: Option<u32>,
+        reconnector: Option<Reconnector>,
     ) {
         trace!("Starting handle_incoming_messages");
         std::thread::spawn(move || {
@@ -258,6 +653,21 @@ impl Transport {
                         match message {
                             Message::ConnectionShutdown => {
                                 info!("Received shutdown message");
+                                if let Some(reconnector) = &reconnector {
+                                    open.store(false, Ordering::SeqCst);
+                                    waiting_call_registry.cancel_outstanding_method_calls();
+
+                                    if Self::reconnect(reconnector, &conn, &shutdown_rx, process_id)
+                                    {
+                                        open.store(true, Ordering::SeqCst);
+                                        reconnector
+                                            .listeners
+                                            .lock()
+                                            .unwrap()
+                                            .retain(|tx| tx.send(()).is_ok());
+                                        continue;
+                                    }
+                                }
                                 break;
                             }
                             Message::Response(response_to_browser_method_call) => {
@@ -270,6 +680,20 @@ impl Transport {
                                 }
                             }
 
+                            Message::SessionEvent(session_event) => {
+                                let listener_id = ListenerId::SessionId(
+                                    session_event.session_id.clone().into(),
+                                );
+                                let target_event = session_event.into_event();
+                                if let Some(tx) = listeners.lock().unwrap().get(&listener_id) {
+                                    tx.send(target_event)
+                                        .expect("Couldn't send event to listener");
+                                } else {
+                                    trace!("discard target_event {:?}", target_event);
+                                }
+                            }
+
+                            // only sent for targets attached without `flatten: true`
                             Message::Event(browser_event) => match browser_event {
                                 Event::ReceivedMessageFromTarget(target_message_event) => {
                                     let session_id = target_message_event.params.session_id.into();
@@ -299,6 +723,12 @@ impl Transport {
                                                     break;
                                                 }
                                             }
+                                            Message::SessionEvent(session_event) => {
+                                                trace!(
+                                                    "Discard nested session event: {:?}",
+                                                    session_event
+                                                );
+                                            }
                                             Message::ConnectionShutdown => {
                                                 info!("connect shutdown received.");
                                             }
@@ -341,10 +771,13 @@ impl Transport {
             info!("Shutting down message handling loop");
 
             // Need to do this because otherwise WS thread might block forever
-            conn.shutdown();
+            conn.lock().unwrap().shutdown();
 
             open.store(false, Ordering::SeqCst);
             waiting_call_registry.cancel_outstanding_method_calls();
+            if let Some(reconnector) = reconnector {
+                reconnector.listeners.lock().unwrap().clear();
+            }
             let mut listeners = listeners.lock().unwrap();
             *listeners = HashMap::new();
             info!("cleared listeners, I think");
@@ -357,3 +790,143 @@ impl Drop for Transport {
         info!("dropping transport");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use websocket::sync::{Client, Server};
+    use websocket::OwnedMessage;
+
+    use super::*;
+    use crate::protocol::browser::methods::GetVersion;
+
+    fn answer_get_version(client: &mut Client<std::net::TcpStream>) {
+        if let OwnedMessage::Text(text) = client.recv_message().unwrap() {
+            let call: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let response = json!({
+                "id": call["id"],
+                "result": {
+                    "protocolVersion": "1.3",
+                    "product": "HeadlessChrome/74.0.3729.157",
+                    "revision": "@9cd1e1c5fb4a1a9a8ab2e9d5ab1ba8e3a0a2ee28",
+                    "userAgent": "Mozilla/5.0",
+                    "jsVersion": "7.4.288.28"
+                }
+            });
+            client
+                .send_message(&OwnedMessage::Text(response.to_string()))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn times_out_and_cancels_method_calls() {
+        env_logger::try_init().unwrap_or(());
+
+        let mut server = Server::bind("127.0.0.1:0").unwrap();
+        let ws_url = format!("ws://{}", server.local_addr().unwrap());
+
+        // answers every call, but only after a while
+        std::thread::spawn(move || {
+            let client = server.accept().ok().unwrap().accept().unwrap();
+            let (mut receiver, mut sender) = client.split().unwrap();
+            for message in receiver.incoming_messages() {
+                if let Ok(OwnedMessage::Text(text)) = message {
+                    let call: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    std::thread::sleep(Duration::from_millis(300));
+                    let response = json!({
+                        "id": call["id"],
+                        "result": {
+                            "protocolVersion": "1.3",
+                            "product": "HeadlessChrome/74.0.3729.157",
+                            "revision": "@9cd1e1c5fb4a1a9a8ab2e9d5ab1ba8e3a0a2ee28",
+                            "userAgent": "Mozilla/5.0",
+                            "jsVersion": "7.4.288.28"
+                        }
+                    });
+                    if sender
+                        .send_message(&OwnedMessage::Text(response.to_string()))
+                        .is_err()
+                    {
+                        break;
+                    }
+                } else {
+                    break;
+                }
+            }
+        });
+
+        let transport = Arc::new(Transport::new(ws_url, None).unwrap());
+
+        let err = transport
+            .call_method_with_timeout(
+                GetVersion {},
+                MethodDestination::Browser,
+                Duration::from_millis(100),
+            )
+            .unwrap_err();
+        let timeout = err.downcast::<MethodCallTimeout>().unwrap();
+        assert_eq!("Browser.getVersion", timeout.method);
+        assert!(transport.waiting_call_registry.is_empty());
+
+        let cancellation = CancellationHandle::new();
+        let cancelling = {
+            let cancellation = cancellation.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                cancellation.cancel();
+            })
+        };
+        let err = transport
+            .call_method_cancellable(
+                GetVersion {},
+                MethodDestination::Browser,
+                Duration::from_secs(10),
+                &cancellation,
+            )
+            .unwrap_err();
+        assert_eq!(
+            "Browser.getVersion",
+            err.downcast::<MethodCallCancelled>().unwrap().method
+        );
+        assert!(transport.waiting_call_registry.is_empty());
+        cancelling.join().unwrap();
+
+        // the responses to both calls still turn up, and are ignored
+        let version = transport.call_method_on_browser(GetVersion {}).unwrap();
+        assert_eq!("HeadlessChrome/74.0.3729.157", version.product);
+    }
+
+    #[test]
+    fn reconnects_after_connection_drops() {
+        env_logger::try_init().unwrap_or(());
+
+        let mut server = Server::bind("127.0.0.1:0").unwrap();
+        let ws_url = format!("ws://{}", server.local_addr().unwrap());
+
+        let server_thread = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let mut client = server.accept().ok().unwrap().accept().unwrap();
+                answer_get_version(&mut client);
+                client.shutdown().unwrap();
+            }
+        });
+
+        let policy = ReconnectPolicyBuilder::default()
+            .initial_backoff(Duration::from_millis(10))
+            .max_attempts(3)
+            .build()
+            .unwrap();
+        let transport = Transport::with_reconnect(ws_url, None, policy).unwrap();
+        let reconnects_rx = transport.listen_to_reconnects();
+
+        transport.call_method_on_browser(GetVersion {}).unwrap();
+
+        reconnects_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        let version = transport.call_method_on_browser(GetVersion {}).unwrap();
+        assert_eq!("HeadlessChrome/74.0.3729.157", version.product);
+
+        server_thread.join().unwrap();
+    }
+}
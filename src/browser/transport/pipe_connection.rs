@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+
+use failure::{Error, Fail};
+use log::*;
+
+use crate::browser::process::DebuggingPipe;
+use crate::protocol;
+
+use super::recording::{Direction, Recorder};
+use super::Connection;
+
+#[derive(Debug, Fail)]
+#[fail(display = "The remote debugging pipe to Chrome has been closed")]
+pub struct PipeClosed {}
+
+/// Talks to Chrome over the pair of file descriptors it opens when launched with
+/// `--remote-debugging-pipe`. Every message in either direction is a JSON string
+/// terminated by a NUL byte.
+pub struct PipeConnection {
+    writer: Mutex<Option<File>>,
+    process_id: Option<u32>,
+}
+
+impl std::fmt::Debug for PipeConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "PipeConnection {{}}")
+    }
+}
+
+impl PipeConnection {
+    pub fn new(
+        pipe: DebuggingPipe,
+        process_id: Option<u32>,
+        messages_tx: mpsc::Sender<protocol::Message>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> Self {
+        let DebuggingPipe { reader, writer } = pipe;
+
+        std::thread::spawn(move || {
+            trace!("Starting msg dispatching loop");
+            Self::dispatch_incoming_messages(reader, messages_tx, process_id, recorder);
+            trace!("Quit loop msg dispatching loop");
+        });
+
+        Self {
+            writer: Mutex::new(Some(writer)),
+            process_id,
+        }
+    }
+
+    /// Closes our end of the pipe Chrome reads from. Chrome treats that as the client going
+    /// away; the reading side finishes once Chrome closes its own end (or exits).
+    pub fn shutdown(&self) {
+        trace!(
+            "Shutting down pipe connection for Chrome {:?}",
+            self.process_id
+        );
+        if self.writer.lock().unwrap().take().is_none() {
+            debug!(
+                "Pipe connection for Chrome {:?} was already shut down",
+                self.process_id
+            );
+        }
+    }
+
+    fn dispatch_incoming_messages<R>(
+        reader: R,
+        messages_tx: mpsc::Sender<protocol::Message>,
+        process_id: Option<u32>,
+        recorder: Option<Arc<Recorder>>,
+    ) where
+        R: Read,
+    {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut raw_message = Vec::new();
+            match reader.read_until(b'\0', &mut raw_message) {
+                Ok(0) => {
+                    debug!("Pipe closed by Chrome #{:?}", process_id);
+                    break;
+                }
+                Ok(_) => {
+                    if raw_message.last() == Some(&b'\0') {
+                        raw_message.pop();
+                    }
+                    let message_string = String::from_utf8_lossy(&raw_message);
+                    if let Some(recorder) = &recorder {
+                        recorder.record(Direction::Received, &message_string);
+                    }
+                    if let Ok(message) = protocol::parse_raw_message(&message_string) {
+                        if messages_tx.send(message).is_err() {
+                            break;
+                        }
+                    } else {
+                        trace!(
+                            "Incoming message isn't recognised as event or method response: {}",
+                            message_string
+                        );
+                    }
+                }
+                Err(err) => {
+                    debug!("Pipe IO Error for Chrome #{:?}: {}", process_id, err);
+                    break;
+                }
+            }
+        }
+
+        info!("Sending shutdown message to message handling loop");
+        if messages_tx
+            .send(protocol::Message::ConnectionShutdown)
+            .is_err()
+        {
+            warn!("Couldn't send message to transport loop telling it to shut down");
+        }
+    }
+
+    pub fn send_message(&self, message_text: &str) -> Result<(), Error> {
+        let mut writer = self.writer.lock().unwrap();
+        let writer = writer.as_mut().ok_or(PipeClosed {})?;
+        writer.write_all(message_text.as_bytes())?;
+        writer.write_all(b"\0")?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Connection for PipeConnection {
+    fn send_message(&self, message_text: &str) -> Result<(), Error> {
+        Self::send_message(self, message_text)
+    }
+
+    fn shutdown(&self) {
+        Self::shutdown(self);
+    }
+}
+
+impl Drop for PipeConnection {
+    fn drop(&mut self) {
+        info!("dropping pipe connection");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_incoming_messages_on_nul() {
+        env_logger::try_init().unwrap_or(());
+
+        let raw = "{\"id\":1,\"result\":{}}\0{\"method\":\"Target.targetDestroyed\",\"params\":{\"targetId\":\"26DEBCB2A45BEFC67A84012AC32C8B2A\"}}\0";
+        let (messages_tx, messages_rx) = mpsc::channel();
+
+        PipeConnection::dispatch_incoming_messages(raw.as_bytes(), messages_tx, None, None);
+
+        let messages: Vec<protocol::Message> = messages_rx.iter().collect();
+        assert_eq!(3, messages.len());
+        match &messages[0] {
+            protocol::Message::Response(resp) => assert_eq!(1, resp.call_id),
+            other => panic!("expected a response, got {:?}", other),
+        }
+        match &messages[1] {
+            protocol::Message::Event(protocol::Event::TargetDestroyed(_)) => {}
+            other => panic!("expected TargetDestroyed, got {:?}", other),
+        }
+        match &messages[2] {
+            protocol::Message::ConnectionShutdown => {}
+            other => panic!("expected ConnectionShutdown, got {:?}", other),
+        }
+    }
+}
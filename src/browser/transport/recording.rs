@@ -0,0 +1,352 @@
+//! Recording protocol traffic to a JSONL file, and serving such a recording back in place of
+//! a real browser.
+//!
+//! Each line of a recording is a `RecordedMessage`: the raw JSON that went over the wire, which
+//! way it went, when, and the session it belonged to (if any).
+//!
+//! A `Replayer` answers a method call with whatever Chrome answered to the first not yet
+//! replayed call with the same method and params, followed by the events that Chrome sent
+//! before the next call was made. Call IDs don't need to match the recording; responses are
+//! rewritten to the IDs of the calls being replayed.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use failure::{Error, Fail};
+use log::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const SEND_MESSAGE_TO_TARGET: &str = "Target.sendMessageToTarget";
+const RECEIVED_MESSAGE_FROM_TARGET: &str = "Target.receivedMessageFromTarget";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedMessage {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub direction: Direction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub message: Value,
+}
+
+impl RecordedMessage {
+    pub fn new(direction: Direction, raw_message: &str) -> Self {
+        let message = serde_json::from_str(raw_message)
+            .unwrap_or_else(|_| Value::String(raw_message.to_string()));
+        let session_id = message
+            .get("sessionId")
+            .or_else(|| {
+                message
+                    .get("params")
+                    .and_then(|params| params.get("sessionId"))
+            })
+            .and_then(Value::as_str)
+            .map(ToString::to_string);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| {
+                elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())
+            });
+
+        Self {
+            timestamp,
+            direction,
+            session_id,
+            message,
+        }
+    }
+}
+
+/// Appends every message it's given to a JSONL file.
+#[derive(Debug)]
+pub struct Recorder {
+    writer: Mutex<LineWriter<File>>,
+}
+
+impl Recorder {
+    /// Starts a new recording at `path`, truncating whatever was there.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self {
+            writer: Mutex::new(LineWriter::new(file)),
+        })
+    }
+
+    pub fn record(&self, direction: Direction, raw_message: &str) {
+        let recorded = RecordedMessage::new(direction, raw_message);
+        let line = serde_json::to_string(&recorded).expect("recorded message is always valid JSON");
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(err) = writeln!(writer, "{}", line) {
+            warn!("Couldn't write to protocol recording: {}", err);
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "Line {} of the protocol recording is invalid: {}",
+    line, reason
+)]
+pub struct InvalidRecording {
+    line: usize,
+    reason: String,
+}
+
+/// The IDs a call carries: its own, and for calls wrapped in `Target.sendMessageToTarget`,
+/// the one of the wrapped call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CallIds {
+    outer: Option<u64>,
+    inner: Option<u64>,
+}
+
+impl CallIds {
+    fn of(message: &Value) -> Self {
+        Self {
+            outer: message.get("id").and_then(Value::as_u64),
+            inner: wrapped_message(message)
+                .and_then(|inner| inner.get("id").and_then(Value::as_u64)),
+        }
+    }
+}
+
+fn wrapped_message(message: &Value) -> Option<Value> {
+    match message.get("method").and_then(Value::as_str) {
+        Some(SEND_MESSAGE_TO_TARGET | RECEIVED_MESSAGE_FROM_TARGET) => message
+            .get("params")
+            .and_then(|params| params.get("message"))
+            .and_then(Value::as_str)
+            .and_then(|raw| serde_json::from_str(raw).ok()),
+        _ => None,
+    }
+}
+
+/// What two calls need to have in common for one to be answered with the other's response:
+/// everything but their IDs.
+fn call_key(message: &Value) -> Value {
+    let mut key = message.clone();
+    if let Some(fields) = key.as_object_mut() {
+        fields.remove("id");
+    }
+    if let Some(inner) = wrapped_message(message) {
+        key["params"]["message"] = call_key(&inner);
+    }
+    key
+}
+
+fn is_event(message: &Value) -> bool {
+    message.get("id").is_none() && CallIds::of(message).inner.is_none()
+}
+
+/// Returns `message` with its IDs swapped for the replayed call's, if it's a response to the
+/// recorded call with IDs `recorded`.
+fn rewrite_response(message: &Value, recorded: CallIds, replayed: CallIds) -> Option<Value> {
+    let message_ids = CallIds::of(message);
+
+    if recorded.outer.is_some() && message_ids.outer == recorded.outer {
+        let mut rewritten = message.clone();
+        rewritten["id"] = json!(replayed.outer);
+        return Some(rewritten);
+    }
+
+    if recorded.inner.is_some()
+        && message_ids.outer.is_none()
+        && message_ids.inner == recorded.inner
+    {
+        let mut inner = wrapped_message(message)?;
+        inner["id"] = json!(replayed.inner);
+        let mut rewritten = message.clone();
+        rewritten["params"]["message"] = Value::String(inner.to_string());
+        return Some(rewritten);
+    }
+
+    None
+}
+
+/// Serves a recording back, one method call at a time.
+#[derive(Debug)]
+pub struct Replayer {
+    recording: Vec<RecordedMessage>,
+    replayed: Vec<bool>,
+}
+
+impl Replayer {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut recording = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let recorded = serde_json::from_str(&line).map_err(|err| InvalidRecording {
+                line: index + 1,
+                reason: err.to_string(),
+            })?;
+            recording.push(recorded);
+        }
+        Ok(Self::new(recording))
+    }
+
+    pub fn new(recording: Vec<RecordedMessage>) -> Self {
+        let replayed = vec![false; recording.len()];
+        Self {
+            recording,
+            replayed,
+        }
+    }
+
+    /// The raw messages the browser would send in response to `raw_call`, in order.
+    ///
+    /// A call that isn't in the recording gets an error response, so that callers fail fast
+    /// instead of waiting for a response that will never come.
+    pub fn reply_to(&mut self, raw_call: &str) -> Vec<String> {
+        let call: Value = match serde_json::from_str(raw_call) {
+            Ok(call) => call,
+            Err(err) => {
+                warn!(
+                    "Can't replay a call that isn't JSON ({}): {}",
+                    err, raw_call
+                );
+                return vec![];
+            }
+        };
+        let key = call_key(&call);
+
+        let position = self
+            .recording
+            .iter()
+            .enumerate()
+            .position(|(index, recorded)| {
+                !self.replayed[index]
+                    && recorded.direction == Direction::Sent
+                    && call_key(&recorded.message) == key
+            });
+
+        let position = match position {
+            Some(position) => position,
+            None => {
+                warn!("No recorded response for call: {}", raw_call);
+                let method = call.get("method").and_then(Value::as_str).unwrap_or("");
+                return vec![json!({
+                    "id": call.get("id"),
+                    "error": {
+                        "code": -32601,
+                        "message": format!("'{}' wasn't found in the protocol recording", method)
+                    }
+                })
+                .to_string()];
+            }
+        };
+        self.replayed[position] = true;
+
+        let recorded_ids = CallIds::of(&self.recording[position].message);
+        let replayed_ids = CallIds::of(&call);
+
+        let is_sent = |recorded: &RecordedMessage| recorded.direction == Direction::Sent;
+        let window_start = self.recording[..position]
+            .iter()
+            .rposition(is_sent)
+            .map_or(0, |index| index + 1);
+        let window_end = self.recording[position + 1..]
+            .iter()
+            .position(is_sent)
+            .map_or(self.recording.len(), |index| position + 1 + index);
+
+        let mut replies = vec![];
+        for index in window_start..self.recording.len() {
+            let recorded = &self.recording[index];
+            if self.replayed[index] || recorded.direction != Direction::Received {
+                continue;
+            }
+            let reply = if let Some(response) =
+                rewrite_response(&recorded.message, recorded_ids, replayed_ids)
+            {
+                Some(response)
+            } else if index < window_end && is_event(&recorded.message) {
+                Some(recorded.message.clone())
+            } else {
+                None
+            };
+            if let Some(reply) = reply {
+                self.replayed[index] = true;
+                replies.push(reply.to_string());
+            }
+        }
+        replies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(direction: Direction, message: Value) -> RecordedMessage {
+        RecordedMessage::new(direction, &message.to_string())
+    }
+
+    #[test]
+    fn replays_responses_and_events_with_new_ids() {
+        let inner_call =
+            json!({"id": 3, "method": "Page.navigate", "params": {"url": "https://example.com"}});
+        let inner_response = json!({"id": 3, "result": {"frameId": "F1"}});
+        let mut replayer = Replayer::new(vec![
+            recorded(
+                Direction::Sent,
+                json!({"id": 4, "method": SEND_MESSAGE_TO_TARGET, "params": {"sessionId": "S1", "message": inner_call.to_string()}}),
+            ),
+            recorded(Direction::Received, json!({"id": 4, "result": {}})),
+            recorded(
+                Direction::Received,
+                json!({"method": RECEIVED_MESSAGE_FROM_TARGET, "params": {"sessionId": "S1", "targetId": "T1", "message": inner_response.to_string()}}),
+            ),
+            recorded(
+                Direction::Received,
+                json!({"method": "Target.targetInfoChanged", "params": {}}),
+            ),
+            recorded(
+                Direction::Sent,
+                json!({"id": 5, "method": "Browser.getVersion", "params": {}}),
+            ),
+            recorded(
+                Direction::Received,
+                json!({"id": 5, "result": {"product": "HeadlessChrome"}}),
+            ),
+        ]);
+        assert_eq!(Some("S1".to_string()), replayer.recording[0].session_id);
+
+        let inner_call =
+            json!({"id": 41, "method": "Page.navigate", "params": {"url": "https://example.com"}});
+        let replies: Vec<Value> = replayer
+            .reply_to(&json!({"id": 42, "method": SEND_MESSAGE_TO_TARGET, "params": {"sessionId": "S1", "message": inner_call.to_string()}}).to_string())
+            .iter()
+            .map(|reply| serde_json::from_str(reply).unwrap())
+            .collect();
+
+        assert_eq!(3, replies.len());
+        assert_eq!(json!(42), replies[0]["id"]);
+        assert_eq!(Some(41), CallIds::of(&replies[1]).inner);
+        assert_eq!(json!("Target.targetInfoChanged"), replies[2]["method"]);
+
+        let unknown = replayer
+            .reply_to(&json!({"id": 43, "method": "Page.reload", "params": {}}).to_string());
+        let unknown: Value = serde_json::from_str(&unknown[0]).unwrap();
+        assert_eq!(json!(43), unknown["id"]);
+        assert!(unknown.get("error").is_some());
+    }
+}
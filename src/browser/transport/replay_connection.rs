@@ -0,0 +1,54 @@
+use std::sync::mpsc;
+use std::sync::Mutex;
+
+use failure::Error;
+use log::*;
+
+use crate::protocol;
+
+use super::recording::Replayer;
+use super::Connection;
+
+/// Stands in for a connection to Chrome, answering every message sent with the replies
+/// found in a protocol recording.
+#[derive(Debug)]
+pub struct ReplayConnection {
+    replayer: Mutex<Replayer>,
+    messages_tx: Mutex<mpsc::Sender<protocol::Message>>,
+}
+
+impl ReplayConnection {
+    pub fn new(replayer: Replayer, messages_tx: mpsc::Sender<protocol::Message>) -> Self {
+        Self {
+            replayer: Mutex::new(replayer),
+            messages_tx: Mutex::new(messages_tx),
+        }
+    }
+}
+
+impl Connection for ReplayConnection {
+    fn send_message(&self, message_text: &str) -> Result<(), Error> {
+        let replies = self.replayer.lock().unwrap().reply_to(message_text);
+        let messages_tx = self.messages_tx.lock().unwrap();
+        for reply in replies {
+            if let Ok(message) = protocol::parse_raw_message(&reply) {
+                messages_tx.send(message)?;
+            } else {
+                trace!(
+                    "Replayed message isn't recognised as event or method response: {}",
+                    reply
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn shutdown(&self) {
+        trace!("Shutting down replay connection");
+        let _ = self
+            .messages_tx
+            .lock()
+            .unwrap()
+            .send(protocol::Message::ConnectionShutdown);
+    }
+}
@@ -37,11 +37,15 @@ impl WaitingCallRegistry {
 
     pub fn resolve_call(&self, response: Response) -> Result<(), Error> {
         trace!("Resolving call");
-        let waiting_call_tx: mpsc::Sender<Result<Response, Error>> = {
-            let mut waiting_calls = self.calls.lock().unwrap();
-            waiting_calls.remove(&response.call_id()).unwrap()
-        };
-        waiting_call_tx.send(Ok(response))?;
+        let waiting_call_tx = self.calls.lock().unwrap().remove(&response.call_id());
+        if let Some(waiting_call_tx) = waiting_call_tx {
+            if waiting_call_tx.send(Ok(response)).is_err() {
+                trace!("Caller stopped waiting just before the response came");
+            }
+        } else {
+            // the caller timed out or cancelled the call before the response came
+            trace!("Nobody waiting for response to {:?}", response.call_id());
+        }
         Ok(())
     }
 
@@ -53,10 +57,17 @@ impl WaitingCallRegistry {
         rx
     }
 
+    /// Stops waiting for a response to `call_id`. The receiver returned by `register_call`
+    /// gets disconnected, unless the response already arrived.
     pub fn unregister_call(&self, call_id: CallId) {
         trace!("Deregistering call");
         let mut calls = self.calls.lock().unwrap();
-        calls.remove(&call_id).unwrap();
+        calls.remove(&call_id);
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.calls.lock().unwrap().is_empty()
     }
 
     // TODO: make it so we can pass in whatever error we want here
@@ -96,6 +107,7 @@ mod tests {
             call_id: 431,
             result: Some(json! {true}),
             error: None,
+            session_id: None,
         };
         let resp_clone = resp.clone();
 
@@ -104,6 +116,7 @@ mod tests {
             call_id: 123,
             result: Some(json! {false}),
             error: None,
+            session_id: None,
         };
         let resp2_clone = resp2.clone();
 
@@ -9,7 +9,10 @@ use websocket::WebSocketError;
 use websocket::{ClientBuilder, OwnedMessage};
 
 use crate::protocol;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+
+use super::recording::{Direction, Recorder};
+use super::Connection;
 
 pub struct WebSocketConnection {
     sender: Mutex<websocket::sender::Writer<TcpStream>>,
@@ -28,13 +31,14 @@ impl WebSocketConnection {
         ws_url: &str,
         process_id: Option<u32>,
         messages_tx: mpsc::Sender<protocol::Message>,
+        recorder: Option<Arc<Recorder>>,
     ) -> Result<Self, Error> {
         let connection = Self::websocket_connection(&ws_url)?;
         let (websocket_receiver, sender) = connection.split()?;
 
         std::thread::spawn(move || {
             trace!("Starting msg dispatching loop");
-            Self::dispatch_incoming_messages(websocket_receiver, messages_tx, process_id);
+            Self::dispatch_incoming_messages(websocket_receiver, messages_tx, process_id, recorder);
             trace!("Quit loop msg dispatching loop");
         });
 
@@ -61,6 +65,7 @@ impl WebSocketConnection {
         mut receiver: websocket::receiver::Reader<TcpStream>,
         messages_tx: mpsc::Sender<protocol::Message>,
         process_id: Option<u32>,
+        recorder: Option<Arc<Recorder>>,
     ) {
         for ws_message in receiver.incoming_messages() {
             match ws_message {
@@ -80,6 +85,9 @@ impl WebSocketConnection {
                 },
                 Ok(message) => {
                     if let OwnedMessage::Text(message_string) = message {
+                        if let Some(recorder) = &recorder {
+                            recorder.record(Direction::Received, &message_string);
+                        }
                         if let Ok(message) = protocol::parse_raw_message(&message_string) {
                             if messages_tx.send(message).is_err() {
                                 break;
@@ -122,6 +130,16 @@ impl WebSocketConnection {
     }
 }
 
+impl Connection for WebSocketConnection {
+    fn send_message(&self, message_text: &str) -> Result<(), Error> {
+        Self::send_message(self, message_text)
+    }
+
+    fn shutdown(&self) {
+        Self::shutdown(self);
+    }
+}
+
 impl Drop for WebSocketConnection {
     fn drop(&mut self) {
         info!("dropping websocket connection");
@@ -0,0 +1,269 @@
+//! Checking, on connect, that Chrome speaks the protocol the way this crate expects.
+//!
+//! The structs in `protocol` follow a particular version of Chrome, and newer ones sometimes
+//! rename or drop fields, which otherwise only shows up as deserialization failures.
+//! `Browser` and `ChromeBrowser` call `Browser.getVersion` when they connect and check the
+//! result against `MIN_MAJOR_VERSION` and `MAX_TESTED_MAJOR_VERSION`, warning about (or,
+//! with `VersionCheck::Require`, refusing) versions outside that range.
+
+use std::fmt;
+
+use failure::{Error, Fail};
+use log::*;
+
+use crate::protocol::browser::methods::VersionInformationReturnObject;
+
+/// The DevTools protocol version this crate speaks.
+pub const SUPPORTED_PROTOCOL_VERSION: &str = "1.3";
+
+/// The oldest major version of Chrome this crate works with.
+pub const MIN_MAJOR_VERSION: u32 = 70;
+
+/// The newest major version of Chrome this crate has been tested with. Newer ones mostly work,
+/// but may have changed methods or events this crate relies on.
+pub const MAX_TESTED_MAJOR_VERSION: u32 = 76;
+
+/// What to do about a Chrome that fails the version check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionCheck {
+    /// Don't call `Browser.getVersion` at all.
+    Skip,
+    /// Log a warning, and carry on.
+    #[default]
+    Warn,
+    /// Fail to connect, with an `IncompatibleChrome` error (or whatever error
+    /// `Browser.getVersion` failed with).
+    Require,
+}
+
+/// Why a version of Chrome isn't supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatibility {
+    /// The product string isn't of the form "Chrome/75.0.3770.142".
+    UnknownProduct,
+    ProtocolVersion,
+    TooOld,
+    TooNew,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Incompatibility::UnknownProduct => write!(f, "its version can't be parsed"),
+            Incompatibility::ProtocolVersion => write!(
+                f,
+                "only protocol version {} is supported",
+                SUPPORTED_PROTOCOL_VERSION
+            ),
+            Incompatibility::TooOld => write!(
+                f,
+                "versions older than {} aren't supported",
+                MIN_MAJOR_VERSION
+            ),
+            Incompatibility::TooNew => write!(
+                f,
+                "versions newer than {} haven't been tested",
+                MAX_TESTED_MAJOR_VERSION
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "{} (protocol {}) isn't supported: {}",
+    product, protocol_version, incompatibility
+)]
+pub struct IncompatibleChrome {
+    pub product: String,
+    pub protocol_version: String,
+    pub incompatibility: Incompatibility,
+}
+
+/// The version of the Chrome on the other end of a connection, as parsed from
+/// `Browser.getVersion`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChromeVersion {
+    /// The product name without its version, e.g. "HeadlessChrome" or "Chrome".
+    pub browser: String,
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub patch: u32,
+    pub protocol_version: String,
+    pub revision: String,
+    pub user_agent: String,
+    pub js_version: String,
+}
+
+impl ChromeVersion {
+    pub fn parse(info: &VersionInformationReturnObject) -> Result<Self, IncompatibleChrome> {
+        let unknown = || IncompatibleChrome {
+            product: info.product.clone(),
+            protocol_version: info.protocol_version.clone(),
+            incompatibility: Incompatibility::UnknownProduct,
+        };
+
+        let mut parts = info.product.splitn(2, '/');
+        let browser = parts.next().ok_or_else(unknown)?;
+        let numbers = parts
+            .next()
+            .ok_or_else(unknown)?
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<u32>, _>>()
+            .map_err(|_| unknown())?;
+        if numbers.len() != 4 {
+            return Err(unknown());
+        }
+
+        Ok(Self {
+            browser: browser.to_string(),
+            major: numbers[0],
+            minor: numbers[1],
+            build: numbers[2],
+            patch: numbers[3],
+            protocol_version: info.protocol_version.clone(),
+            revision: info.revision.clone(),
+            user_agent: info.user_agent.clone(),
+            js_version: info.js_version.clone(),
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.browser.starts_with("Headless")
+    }
+
+    /// Whether this is at least the given major version, for branching on features.
+    pub fn at_least(&self, major: u32) -> bool {
+        self.major >= major
+    }
+
+    /// The version as (major, minor, build, patch), which compares the way versions do.
+    pub fn number(&self) -> (u32, u32, u32, u32) {
+        (self.major, self.minor, self.build, self.patch)
+    }
+
+    /// Whether this crate supports this version.
+    pub fn check(&self) -> Result<(), IncompatibleChrome> {
+        let incompatibility = if self.protocol_version != SUPPORTED_PROTOCOL_VERSION {
+            Incompatibility::ProtocolVersion
+        } else if self.major < MIN_MAJOR_VERSION {
+            Incompatibility::TooOld
+        } else if self.major > MAX_TESTED_MAJOR_VERSION {
+            Incompatibility::TooNew
+        } else {
+            return Ok(());
+        };
+        Err(IncompatibleChrome {
+            product: self.to_string(),
+            protocol_version: self.protocol_version.clone(),
+            incompatibility,
+        })
+    }
+}
+
+impl fmt::Display for ChromeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}.{}.{}.{}",
+            self.browser, self.major, self.minor, self.build, self.patch
+        )
+    }
+}
+
+/// Applies `policy` to the outcome of calling `Browser.getVersion`, returning the version if
+/// it could be parsed.
+pub(crate) fn check_version(
+    version_info: Result<VersionInformationReturnObject, Error>,
+    policy: VersionCheck,
+) -> Result<Option<ChromeVersion>, Error> {
+    let checked = version_info.and_then(|info| {
+        let version = ChromeVersion::parse(&info)?;
+        match version.check() {
+            Ok(()) => Ok(version),
+            Err(incompatible) if policy == VersionCheck::Require => Err(incompatible.into()),
+            Err(incompatible) => {
+                warn!("{}", incompatible);
+                Ok(version)
+            }
+        }
+    });
+
+    match checked {
+        Ok(version) => {
+            debug!("Connected to {}", version);
+            Ok(Some(version))
+        }
+        Err(err) if policy == VersionCheck::Require => Err(err),
+        Err(err) => {
+            warn!("Couldn't check Chrome's version: {}", err);
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version_info(product: &str, protocol_version: &str) -> VersionInformationReturnObject {
+        VersionInformationReturnObject {
+            protocol_version: protocol_version.to_string(),
+            product: product.to_string(),
+            revision: "@a1b2c3".to_string(),
+            user_agent: format!("Mozilla/5.0 {}", product),
+            js_version: "7.5.288.30".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_and_checks_versions() {
+        let version =
+            ChromeVersion::parse(&version_info("HeadlessChrome/75.0.3770.142", "1.3")).unwrap();
+        assert_eq!((75, 0, 3770, 142), version.number());
+        assert!(version.is_headless());
+        assert!(version.at_least(74) && !version.at_least(76));
+        assert!(version.check().is_ok());
+
+        let incompatibility = |product, protocol_version| {
+            ChromeVersion::parse(&version_info(product, protocol_version))
+                .and_then(|version| version.check())
+                .unwrap_err()
+                .incompatibility
+        };
+        assert_eq!(
+            Incompatibility::UnknownProduct,
+            incompatibility("Chrome/75.0", "1.3")
+        );
+        assert_eq!(
+            Incompatibility::ProtocolVersion,
+            incompatibility("Chrome/75.0.3770.142", "1.2")
+        );
+        assert_eq!(
+            Incompatibility::TooOld,
+            incompatibility("Chrome/65.0.3325.181", "1.3")
+        );
+        assert_eq!(
+            Incompatibility::TooNew,
+            incompatibility("Chrome/120.0.6099.109", "1.3")
+        );
+
+        let too_new = || Ok(version_info("Chrome/120.0.6099.109", "1.3"));
+        assert_eq!(
+            120,
+            check_version(too_new(), VersionCheck::Warn)
+                .unwrap()
+                .unwrap()
+                .major
+        );
+        let err = check_version(too_new(), VersionCheck::Require).unwrap_err();
+        assert!(err.downcast::<IncompatibleChrome>().is_ok());
+        assert!(
+            check_version(Err(failure::err_msg("no")), VersionCheck::Warn)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
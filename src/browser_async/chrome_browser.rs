@@ -8,7 +8,11 @@ use std::fmt;
 pub use crate::protocol::browser::methods::VersionInformationReturnObject;
 
 pub use crate::browser::process::{LaunchOptionsBuilder, LaunchOptions};
-use crate::browser::process::Process;
+use crate::browser::chrome_output::ChromeOutput;
+use crate::browser::discovery;
+use crate::browser::process::{ChromeExited, Process};
+use crate::browser::transport::recording::{Direction, Recorder, Replayer};
+use crate::browser::version::VersionCheck;
 pub use crate::browser::tab::Tab;
 use futures::AsyncSink;
 use std::default::Default;
@@ -27,6 +31,14 @@ enum BrowserState {
     Receiving,
     StartSend(String),
     Sending,
+    StartReplaying,
+    Replaying,
+    /// The connection ended (with `ended_with`, if it failed), and Chrome has until `deadline`
+    /// to exit, so that the stream can end with its `ChromeExited` instead.
+    WaitingForExit {
+        ended_with: Option<failure::Error>,
+        deadline: Instant,
+    },
     // CheckPendingTask,
 }
 
@@ -37,6 +49,9 @@ impl fmt::Debug for BrowserState {
             BrowserState::Receiving => write!(f, "Receiving"),
             BrowserState::StartSend(content) => write!(f, "start sending: {}", content),
             BrowserState::Sending => write!(f, "Sending"),
+            BrowserState::StartReplaying => write!(f, "start replaying"),
+            BrowserState::Replaying => write!(f, "Replaying"),
+            BrowserState::WaitingForExit { .. } => write!(f, "waiting for Chrome to exit"),
         }
     }
 }
@@ -49,6 +64,10 @@ pub struct ChromeBrowser {
     last_be_polled: Instant,
     waiting_to_send: VecDeque<String>,
     just_send: bool,
+    recorder: Option<Recorder>,
+    replayer: Option<Replayer>,
+    replayed: VecDeque<String>,
+    version_check: VersionCheck,
 }
 
 impl std::fmt::Debug for ChromeBrowser {
@@ -67,11 +86,62 @@ impl ChromeBrowser {
         Self::new(options)
     }
     pub fn new(launch_options: LaunchOptions) -> Self {
+        let recorder = launch_options
+            .record_protocol_to
+            .as_ref()
+            .map(|path| Recorder::create(path).expect("protocol recording should created."));
+        let version_check = launch_options.version_check;
         let chrome_process = Process::new(launch_options).expect("process should created.");
         let web_socket_debugger_url = chrome_process.debug_ws_url.clone();
-        let process = Some(chrome_process);
+        let mut browser = Self::with_ws_url(&web_socket_debugger_url, Some(chrome_process));
+        browser.recorder = recorder;
+        browser.version_check = version_check;
+        browser
+    }
+
+    /// Like `connect`, but records all protocol traffic to the JSONL file at `recording_path`.
+    pub fn connect_and_record<P: AsRef<std::path::Path>>(
+        web_socket_debugger_url: &str,
+        recording_path: P,
+    ) -> Result<Self, failure::Error> {
+        let mut browser = Self::connect(web_socket_debugger_url);
+        browser.recorder = Some(Recorder::create(recording_path)?);
+        Ok(browser)
+    }
+
+    /// A browser that doesn't talk to Chrome at all, but is served a protocol recording.
+    /// Messages sent are answered by matching them against the recorded ones by method
+    /// and params.
+    pub fn replay<P: AsRef<std::path::Path>>(recording_path: P) -> Result<Self, failure::Error> {
+        Ok(Self {
+            state: BrowserState::StartReplaying,
+            ws_client: None,
+            process: None,
+            last_be_polled: Instant::now(),
+            waiting_to_send: VecDeque::new(),
+            just_send: false,
+            recorder: None,
+            replayer: Some(Replayer::open(recording_path)?),
+            replayed: VecDeque::new(),
+            version_check: VersionCheck::default(),
+        })
+    }
+
+    /// Connects to an already running Chrome at its browser-level WebSocket URL.
+    pub fn connect(web_socket_debugger_url: &str) -> Self {
+        Self::with_ws_url(web_socket_debugger_url, None)
+    }
+
+    /// Connects to an already running Chrome with its debugging port at `host_port`
+    /// (e.g. "127.0.0.1:9222"), looking up the WebSocket URL via `/json/version`.
+    pub fn discover_and_connect(host_port: &str) -> Result<Self, failure::Error> {
+        let version = discovery::get_version(host_port)?;
+        Ok(Self::connect(&version.web_socket_debugger_url))
+    }
+
+    fn with_ws_url(web_socket_debugger_url: &str, process: Option<Process>) -> Self {
         let state = BrowserState::Connecting(
-            ClientBuilder::new(&web_socket_debugger_url)
+            ClientBuilder::new(web_socket_debugger_url)
                 .expect("client build should work.")
                 .add_protocol("rust-websocket")
                 .async_connect_insecure(),
@@ -85,8 +155,28 @@ impl ChromeBrowser {
             last_be_polled: Instant::now(),
             waiting_to_send: VecDeque::new(),
             just_send: false,
+            recorder: None,
+            replayer: None,
+            replayed: VecDeque::new(),
+            version_check: VersionCheck::default(),
         }
     }
+
+    /// What to do if Chrome's version turns out not to be supported once connected. See
+    /// `browser::version`; `ChromeBrowser::new` takes it from its `LaunchOptions`.
+    pub fn with_version_check(mut self, version_check: VersionCheck) -> Self {
+        self.version_check = version_check;
+        self
+    }
+
+    pub(crate) fn version_check(&self) -> VersionCheck {
+        self.version_check
+    }
+    /// The last lines Chrome wrote to its stdout and stderr, if this browser launched it.
+    pub fn chrome_output(&self) -> Option<&ChromeOutput> {
+        self.process.as_ref().map(Process::output)
+    }
+
     pub fn send_message(&mut self, method_str: String) {
         self.waiting_to_send.push_back(method_str);
     }
@@ -109,6 +199,72 @@ impl ChromeBrowser {
     pub fn have_not_be_polled_for(&self, duration: Duration) -> bool {
         (self.last_be_polled - Instant::now()) > duration
     }
+
+    /// The connection ended, with `err` if it failed. If this browser launched Chrome, it's
+    /// given a second to exit first, as the connection tends to close just before the exit is
+    /// noticed.
+    fn connection_ended(
+        &mut self,
+        err: Option<failure::Error>,
+    ) -> Poll<Option<protocol::Message>, failure::Error> {
+        if self.process.is_none() {
+            return err.map_or(Ok(Async::Ready(None)), Err);
+        }
+        self.state = BrowserState::WaitingForExit {
+            ended_with: err,
+            deadline: Instant::now() + Duration::from_secs(1),
+        };
+        self.poll_exit()
+    }
+
+    fn poll_exit(&mut self) -> Poll<Option<protocol::Message>, failure::Error> {
+        let exited: Option<ChromeExited> = self
+            .process
+            .as_ref()
+            .and_then(|process| process.exit_monitor().exited());
+        if let Some(exited) = exited {
+            return Err(exited.into());
+        }
+        let BrowserState::WaitingForExit {
+            ended_with,
+            deadline,
+        } = &mut self.state
+        else {
+            unreachable!("only called while waiting for Chrome to exit");
+        };
+        if Instant::now() < *deadline {
+            // nothing wakes us when Chrome exits, so ask to be polled again rather than
+            // blocking the executor until it does.
+            futures::task::current().notify();
+            return Ok(Async::NotReady);
+        }
+        ended_with.take().map_or(Ok(Async::Ready(None)), Err)
+    }
+
+    fn poll_replay(&mut self) -> Poll<Option<protocol::Message>, failure::Error> {
+        if let BrowserState::StartReplaying = self.state {
+            self.state = BrowserState::Replaying;
+            return Ok(Some(protocol::Message::Connected).into());
+        }
+        let replayer = self.replayer.as_mut().expect("only called when replaying.");
+        while let Some(message_to_send) = self.waiting_to_send.pop_front() {
+            self.replayed.extend(replayer.reply_to(&message_to_send));
+        }
+        while let Some(msg) = self.replayed.pop_front() {
+            match protocol::parse_raw_message(&msg) {
+                Ok(success_parsed_message) => {
+                    return Ok(Async::Ready(Some(success_parsed_message)));
+                }
+                Err(err) => {
+                    error!("parse replayed message failed: {:?}", err);
+                }
+            }
+        }
+        // nothing wakes us when a call is queued in `waiting_to_send`, so ask to be polled again
+        // rather than stalling the replay.
+        futures::task::current().notify();
+        Ok(Async::NotReady)
+    }
 }
 
 impl Stream for ChromeBrowser {
@@ -138,6 +294,9 @@ impl Stream for ChromeBrowser {
                         Ok(Async::Ready(Some(message))) => {
                             self.just_send = false;
                             if let OwnedMessage::Text(msg) = message {
+                                if let Some(recorder) = &self.recorder {
+                                    recorder.record(Direction::Received, &msg);
+                                }
                                 if msg.contains("Network.requestIntercepted") || msg.len() < 1000 {
                                     trace!("got message (***every message***): {:?}", msg);
                                 } else {
@@ -159,7 +318,7 @@ impl Stream for ChromeBrowser {
                         }
                         Ok(Async::Ready(None)) => {
                             trace!("enter receiving None, end?");
-                            return Ok(Async::Ready(None));
+                            return self.connection_ended(None);
                         }
                         Ok(Async::NotReady) => {
                             // if return not ready, when to pull again is job of underlying. is out of our controls.
@@ -168,7 +327,7 @@ impl Stream for ChromeBrowser {
                         }
                         Err(e) => {
                             trace!("enter receiving err");
-                            return Err(e.into());
+                            return self.connection_ended(Some(e.into()));
                         }
                     }
                 }
@@ -181,6 +340,9 @@ impl Stream for ChromeBrowser {
                         .start_send(OwnedMessage::Text(message_to_send.clone()))
                     {
                         Ok(AsyncSink::Ready) => {
+                            if let Some(recorder) = &self.recorder {
+                                recorder.record(Direction::Sent, message_to_send);
+                            }
                             trace!("entered start send. switching to **sending: {:?}", message_to_send);
                             self.state = BrowserState::Sending;
                         }
@@ -190,7 +352,7 @@ impl Stream for ChromeBrowser {
                         }
                         Err(e) => {
                             error!("StartSend error: {:?}", e);
-                            return Err(e.into());
+                            return self.connection_ended(Some(e.into()));
                         }
                     }
                 }
@@ -208,10 +370,16 @@ impl Stream for ChromeBrowser {
                         }
                         Err(e) => {
                             error!("sending error: {:?}", e);
-                            return Err(e.into());
+                            return self.connection_ended(Some(e.into()));
                         }
                     }
                 }
+                BrowserState::StartReplaying | BrowserState::Replaying => {
+                    return self.poll_replay();
+                }
+                BrowserState::WaitingForExit { .. } => {
+                    return self.poll_exit();
+                }
             }
         }
     }
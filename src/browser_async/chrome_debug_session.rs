@@ -4,14 +4,18 @@ use super::task_describe::{
 };
 
 use super::embedded_events::{self, EmbeddedEvent};
-use crate::browser_async::{chrome_browser::ChromeBrowser, TaskId};
+use crate::browser_async::{chrome_browser::ChromeBrowser, create_unique_usize, TaskId};
+use crate::browser::version::{self, ChromeVersion, VersionCheck};
+use crate::protocol::Method;
 
 use super::task_manager;
 use super::super::browser::tab::element::{BoxModel, ElementQuad};
-use super::super::protocol::{self, dom, emulation, network, page, runtime, target, browser};
+use super::super::protocol::{self, dom, emulation, fetch, network, page, runtime, target, browser};
 
 use failure::Error;
 use log::*;
+use serde_json::json;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::atomic::AtomicUsize;
 use websocket::futures::{Poll, Stream};
@@ -22,6 +26,9 @@ pub struct ChromeDebugSession {
     session_id: Option<String>,
     unique_number: AtomicUsize,
     task_manager: task_manager::TaskManager,
+    targets_by_session: HashMap<target::SessionID, target::TargetId>,
+    version_call_id: Option<protocol::CallId>,
+    chrome_version: Option<ChromeVersion>,
 }
 
 impl ChromeDebugSession {
@@ -31,6 +38,43 @@ impl ChromeDebugSession {
             session_id: None,
             unique_number: AtomicUsize::new(10000),
             task_manager: task_manager::TaskManager::new(),
+            targets_by_session: HashMap::new(),
+            version_call_id: None,
+            chrome_version: None,
+        }
+    }
+
+    /// The version of Chrome, as checked on connect. Known by the time `ChromeConnected` is
+    /// yielded, unless the check was skipped or the version couldn't be determined.
+    pub fn chrome_version(&self) -> Option<&ChromeVersion> {
+        self.chrome_version.as_ref()
+    }
+
+    /// Holds back `Connected` until Chrome has told us its version and it has been checked,
+    /// then passes it on. Other messages go through untouched.
+    fn check_version(
+        &mut self,
+        message: protocol::Message,
+    ) -> Result<Option<protocol::Message>, Error> {
+        let policy = self.chrome_browser.version_check();
+        match message {
+            protocol::Message::Connected if policy != VersionCheck::Skip => {
+                let call_id = create_unique_usize();
+                self.version_call_id = Some(call_id);
+                self.send_message_direct(
+                    json!({"id": call_id, "method": browser::methods::GetVersion::NAME, "params": {}})
+                        .to_string(),
+                );
+                Ok(None)
+            }
+            protocol::Message::Response(resp) if Some(resp.call_id) == self.version_call_id => {
+                self.version_call_id = None;
+                let version_info =
+                    protocol::parse_response::<browser::methods::VersionInformationReturnObject>(resp);
+                self.chrome_version = version::check_version(version_info, policy)?;
+                Ok(Some(protocol::Message::Connected))
+            }
+            other => Ok(Some(other)),
         }
     }
 
@@ -98,30 +142,10 @@ impl ChromeDebugSession {
                 let event = runtime_events::ExecutionContextDestroyed::new(embedded_event);
                 TaskDescribe::from(event).into()
             }
-            EmbeddedEvent::ConsoleAPICalled(embedded_event) => {
-                let event = runtime_events::ConsoleAPICalled::new(embedded_event);
-                TaskDescribe::from(event).into()
-            }
             EmbeddedEvent::DomContentEventFired(embedded_event) => {
                 let event = page_events::DomContentEventFired::new(embedded_event);
                 TaskDescribe::from(event).into()
             }
-            EmbeddedEvent::ResponseReceived(embedded_event) => {
-                let event = network_events::ResponseReceived::new(embedded_event);
-                TaskDescribe::from(event).into()
-            }
-            EmbeddedEvent::DataReceived(embedded_event) => {
-                let event = network_events::DataReceived::new(embedded_event);
-                TaskDescribe::from(event).into()
-            }
-            EmbeddedEvent::LoadingFinished(embedded_event) => {
-                let event = network_events::LoadingFinished::new(embedded_event);
-                TaskDescribe::from(event).into()
-            }
-            EmbeddedEvent::RequestWillBeSent(embedded_event) => {
-                let event = network_events::RequestWillBeSent::new(embedded_event);
-                TaskDescribe::from(event).into()
-            }
             EmbeddedEvent::ChildNodeCountUpdated(embedded_event) => {
                 let event = dom_events::ChildNodeCountUpdated::new(embedded_event);
                 TaskDescribe::from(event).into()
@@ -134,10 +158,6 @@ impl ChromeDebugSession {
                 let event = page_events::FrameRequestedNavigation::new(embedded_event);
                 TaskDescribe::from(event).into()
             }
-            EmbeddedEvent::LoadingFailed(embedded_event) => {
-                let event = network_events::LoadingFailed::new(embedded_event);
-                TaskDescribe::from(event).into()
-            }
             EmbeddedEvent::WindowOpen(embedded_event) => {
                 let event = page_events::WindowOpen::new(embedded_event);
                 TaskDescribe::from(event).into()
@@ -154,6 +174,7 @@ impl ChromeDebugSession {
                 let event = page_events::FrameResized::new(embedded_event);
                 TaskDescribe::from(event).into()
             }
+            EmbeddedEvent::Other => None,
         }
     }
 
@@ -326,6 +347,33 @@ impl ChromeDebugSession {
                 TargetCallMethodTask::ContinueInterceptedRequest(_task) => {
                     info!("continue_intercepted_request done.");
                 }
+                TargetCallMethodTask::FetchEnable(task) => {
+                    info!("fetch enabled. {:?}", task);
+                }
+                TargetCallMethodTask::ContinueRequest(_task) => {
+                    info!("continue_request done.");
+                }
+                TargetCallMethodTask::FulfillRequest(_task) => {
+                    info!("fulfill_request done.");
+                }
+                TargetCallMethodTask::FailRequest(_task) => {
+                    info!("fail_request done.");
+                }
+                TargetCallMethodTask::ContinueWithAuth(_task) => {
+                    info!("continue_with_auth done.");
+                }
+                TargetCallMethodTask::NetworkGetResponseBody(task) => {
+                    let return_object = protocol::parse_response::<
+                        network::methods::GetResponseBodyReturnObject,
+                    >(resp)?;
+                    task.task_result.replace(return_object);
+                }
+                TargetCallMethodTask::GetResponseBody(task) => {
+                    let return_object = protocol::parse_response::<
+                        fetch::methods::GetResponseBodyReturnObject,
+                    >(resp)?;
+                    task.task_result.replace(return_object);
+                }
                 TargetCallMethodTask::GetLayoutMetrics(task) => {
                     let task_return_object = protocol::parse_response::<
                         page::methods::GetLayoutMetricsReturnObject,
@@ -335,6 +383,21 @@ impl ChromeDebugSession {
                 TargetCallMethodTask::BringToFront(_task) => {
                     info!("bring_to_front done.");
                 }
+                TargetCallMethodTask::HandleJavaScriptDialog(_task) => {
+                    info!("handle_java_script_dialog done.");
+                }
+                TargetCallMethodTask::AddScriptToEvaluateOnNewDocument(task) => {
+                    let return_object = protocol::parse_response::<
+                        page::methods::AddScriptToEvaluateOnNewDocumentReturnObject,
+                    >(resp)?;
+                    task.task_result.replace(return_object.identifier);
+                }
+                TargetCallMethodTask::RemoveScriptToEvaluateOnNewDocument(_task) => {
+                    info!("remove_script_to_evaluate_on_new_document done.");
+                }
+                TargetCallMethodTask::AddBinding(_task) => {
+                    info!("add_binding done.");
+                }
                 TargetCallMethodTask::DispatchMouseEvent(_task) => {
                     info!("dispatch_mouse_event done.");
                 }
@@ -350,7 +413,15 @@ impl ChromeDebugSession {
             },
             TaskDescribe::BrowserCallMethod(browser_call) => match browser_call {
                 BrowserCallMethodTask::CreateTarget(task) => {
-                    info!("nothing to full fill CreateTarget:: {:?}", task);
+                    let task_return_object =
+                        protocol::parse_response::<target::methods::CreateTargetReturnObject>(resp)?;
+                    task.task_result.replace(task_return_object.target_id);
+                }
+                BrowserCallMethodTask::CreateBrowserContext(task) => {
+                    let task_return_object = protocol::parse_response::<
+                        target::methods::CreateBrowserContextReturnObject,
+                    >(resp)?;
+                    task.task_result.replace(task_return_object.browser_context_id);
                 }
                 BrowserCallMethodTask::SetDiscoverTargets(task) => {
                     info!("nothing to full fill SetDiscoverTargets:: {:?}", task);
@@ -399,10 +470,25 @@ impl ChromeDebugSession {
     fn handle_protocol_event(
         &mut self,
         protocol_event: protocol::Event,
-        _maybe_session_id: Option<String>,
-        _maybe_target_id: Option<String>,
+        maybe_session_id: Option<String>,
+        maybe_target_id: Option<String>,
     ) -> Option<TaskDescribe> {
         match protocol_event {
+            protocol::Event::TargetCrashed(raw_event) => {
+                let event = target_events::TargetCrashed::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::InspectorTargetCrashed(_) => {
+                if let Some(target_id) = maybe_target_id {
+                    let event = target_events::TargetCrashed::inspected(target_id);
+                    return Some(event.into());
+                }
+                warn!("a target we don't know crashed.");
+            }
+            protocol::Event::DetachedFromTarget(raw_event) => {
+                let event = target_events::DetachedFromTarget::new(raw_event);
+                return Some(event.into());
+            }
             protocol::Event::FrameNavigated(raw_event) => {
                 let event = page_events::FrameNavigated::new(raw_event);
                 return Some(event.into());
@@ -439,6 +525,34 @@ impl ChromeDebugSession {
                 let event = network_events::RequestIntercepted::new(raw_event);
                 return Some(event.into());
             }
+            protocol::Event::RequestPaused(raw_event) => {
+                let event = network_events::RequestPaused::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::AuthRequired(raw_event) => {
+                let event = network_events::AuthRequired::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::RequestWillBeSent(raw_event) => {
+                let event = network_events::RequestWillBeSent::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::ResponseReceived(raw_event) => {
+                let event = network_events::ResponseReceived::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::DataReceived(raw_event) => {
+                let event = network_events::DataReceived::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::LoadingFinished(raw_event) => {
+                let event = network_events::LoadingFinished::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::LoadingFailed(raw_event) => {
+                let event = network_events::LoadingFailed::new(raw_event);
+                return Some(event.into());
+            }
             protocol::Event::TargetDestroyed(raw_event) => {
                 let event = target_events::TargetDestroyed::new(raw_event);
                 return Some(event.into());
@@ -447,6 +561,37 @@ impl ChromeDebugSession {
                 let event = page_events::LifeCycle::new(raw_event);
                 return Some(event.into());
             }
+            protocol::Event::JavascriptDialogOpening(raw_event) => {
+                let event = page_events::JavascriptDialogOpening::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::ConsoleAPICalled(raw_event) => {
+                let event = runtime_events::ConsoleAPICalled::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::ExceptionThrown(raw_event) => {
+                let event = runtime_events::ExceptionThrown::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::BindingCalled(raw_event) => {
+                let event = runtime_events::BindingCalled::new(raw_event);
+                return Some(event.into());
+            }
+            protocol::Event::Raw(raw_event) => {
+                return self.handle_raw_event(raw_event, maybe_session_id, maybe_target_id);
+            }
+            #[cfg(feature = "cdp")]
+            protocol::Event::Protocol(event) => {
+                // the generated bindings cover the embedded events too
+                let raw_event = serde_json::to_value(&event)
+                    .and_then(serde_json::from_value)
+                    .expect("generated events convert to raw ones");
+                let raw_event = protocol::RawEvent {
+                    session_id: maybe_session_id.clone(),
+                    ..raw_event
+                };
+                return self.handle_raw_event(raw_event, maybe_session_id, maybe_target_id);
+            }
             _ => {
                 warn!("unprocessed inner event: {:?}", protocol_event);
             }
@@ -454,6 +599,25 @@ impl ChromeDebugSession {
         None
     }
 
+    /// Raw events are either one of the `EmbeddedEvent`s, or passed on as they are.
+    fn handle_raw_event(
+        &mut self,
+        raw_event: protocol::RawEvent,
+        maybe_session_id: Option<String>,
+        maybe_target_id: Option<String>,
+    ) -> Option<TaskDescribe> {
+        if raw_event.decode_error.is_some() {
+            return Some(TaskDescribe::RawEvent(raw_event));
+        }
+        match raw_event.decode::<EmbeddedEvent>() {
+            Ok(EmbeddedEvent::Other) => Some(TaskDescribe::RawEvent(raw_event)),
+            Ok(embedded_event) => {
+                self.handle_inner_target_events(embedded_event, maybe_session_id, maybe_target_id)
+            }
+            Err(err) => Some(TaskDescribe::RawEvent(raw_event.undecodable(&err))),
+        }
+    }
+
     fn process_message(
         &mut self,
         value: protocol::Message,
@@ -464,12 +628,29 @@ impl ChromeDebugSession {
     )> {
         match value {
             protocol::Message::Response(resp) => {
-                if let Some(page_message) = self.handle_response(resp, None, None) {
-                    (None, None, page_message).into()
+                let session_id = resp.session_id.clone();
+                let target_id = session_id
+                    .as_ref()
+                    .and_then(|s_id| self.targets_by_session.get(s_id).cloned());
+                if let Some(page_message) =
+                    self.handle_response(resp, session_id.clone(), target_id.clone())
+                {
+                    (session_id, target_id, page_message).into()
                 } else {
                     None
                 }
             }
+            protocol::Message::SessionEvent(session_event) => {
+                let session_id = session_event.session_id.clone();
+                let target_id = self.targets_by_session.get(&session_id).cloned();
+                let page_message = self.handle_protocol_event(
+                    session_event.into_event(),
+                    Some(session_id.clone()),
+                    target_id.clone(),
+                );
+                page_message.map(|page_message| (Some(session_id), target_id, page_message))
+            }
+            // only targets attached without flatten: true send these.
             protocol::Message::Event(protocol::Event::ReceivedMessageFromTarget(
                 target_message_event,
             )) => {
@@ -526,6 +707,18 @@ impl ChromeDebugSession {
                 }
             }
             protocol::Message::Event(protocol_event) => {
+                match &protocol_event {
+                    protocol::Event::AttachedToTarget(event) => {
+                        self.targets_by_session.insert(
+                            event.params.session_id.clone(),
+                            event.params.target_info.target_id.clone(),
+                        );
+                    }
+                    protocol::Event::DetachedFromTarget(event) => {
+                        self.targets_by_session.remove(&event.params.session_id);
+                    }
+                    _ => {}
+                }
                 if let Some(page_message) = self.handle_protocol_event(protocol_event, None, None) {
                     (None, None, page_message).into()
                 } else {
@@ -559,13 +752,17 @@ impl Stream for ChromeDebugSession {
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
         loop {
             if let Some(value) = try_ready!(self.chrome_browser.poll()) {
+                let Some(value) = self.check_version(value)? else {
+                    continue;
+                };
                 if let Some(task_describe) = self.process_message(value) {
                     break Ok(Some(task_describe).into());
                 } else {
                     info!("discard intermediate tasks.");
                 }
             } else {
-                error!("got None, was stream ended?");
+                error!("got None, the stream ended.");
+                break Ok(None.into());
             }
         }
     }
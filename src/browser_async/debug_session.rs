@@ -1,7 +1,7 @@
 use super::chrome_browser::ChromeBrowser;
 use super::chrome_debug_session::ChromeDebugSession;
 use super::interval_page_message::IntervalPageMessage;
-use super::page_message::{PageResponse, PageResponseWrapper};
+use super::page_message::{PageResponse, PageResponseWrapper, ReceivedEvent};
 use super::task_describe::{
     handle_browser_method_call, handle_dom_event, handle_network_event, handle_page_event,
     handle_runtime_event, handle_target_event, handle_target_method_call, target_tasks, handle_log_event,
@@ -11,6 +11,7 @@ use super::task_describe::{
 use super::{BrowserContexts, Tab};
 
 use super::super::browser::process::{LaunchOptions, LaunchOptionsBuilder};
+use super::super::browser::version::ChromeVersion;
 use super::protocol::target;
 use super::ChromePageError;
 use failure;
@@ -83,7 +84,8 @@ impl DebugSession {
         Self::new_default(browser)
     }
 
-    fn new_default(browser: ChromeBrowser) -> Self {
+    /// A session driving an already created browser, e.g. one from `ChromeBrowser::connect`.
+    pub fn new_default(browser: ChromeBrowser) -> Self {
         let chrome_debug_session = ChromeDebugSession::new(browser);
         let interval_page_message = IntervalPageMessage::new();
         let arc_cds = Arc::new(Mutex::new(chrome_debug_session));
@@ -118,6 +120,15 @@ impl DebugSession {
         }
     }
 
+    /// The version of Chrome, as checked on connect; see `ChromeDebugSession::chrome_version`.
+    pub fn chrome_version(&self) -> Option<ChromeVersion> {
+        self.chrome_debug_session
+            .lock()
+            .expect("obtain chrome_debug_session should success.")
+            .chrome_version()
+            .cloned()
+    }
+
     pub fn tab_count(&self) -> usize {
         self.tabs.len()
     }
@@ -524,6 +535,12 @@ impl DebugSession {
                     .ok()
                     .into())                
             }
+            TaskDescribe::RawEvent(raw_event) => Ok(Some(PageResponseWrapper {
+                target_id,
+                task_id: None,
+                page_response: PageResponse::ReceivedEvent(ReceivedEvent::RawEvent(raw_event)),
+            })
+            .into()),
             TaskDescribe::ChromeConnected => {
                 let resp = Some(PageResponseWrapper::new(PageResponse::ChromeConnected));
                 Ok(resp.into())
@@ -550,7 +567,8 @@ impl Stream for DebugSession {
     type Error = failure::Error;
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        let (a, b) = if self.flag {
+        let wrapper_first = self.flag;
+        let (a, b) = if wrapper_first {
             (
                 &mut self.wrapper as &mut Stream<Item = _, Error = _>,
                 &mut self.interval_page_message as &mut Stream<Item = _, Error = _>,
@@ -566,6 +584,8 @@ impl Stream for DebugSession {
             Async::Ready(Some(item)) => {
                 return self.send_page_message(item);
             }
+            // the interval never ends, so without chrome there's nothing left to wait for.
+            Async::Ready(None) if wrapper_first => return Ok(None.into()),
             Async::Ready(None) => true,
             Async::NotReady => false,
         };
@@ -579,7 +599,7 @@ impl Stream for DebugSession {
                 }
                 self.send_page_message(item)
             }
-            Async::Ready(None) if a_done => Ok(None.into()),
+            Async::Ready(None) if a_done || !wrapper_first => Ok(None.into()),
             Async::Ready(None) | Async::NotReady => Ok(Async::NotReady),
         }
     }
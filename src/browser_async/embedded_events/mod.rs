@@ -6,7 +6,8 @@ use page::events::{WindowOpen, FrameRequestedNavigation, FrameResized};
 
 pub mod network_raw_event;
 pub use network_raw_event::{RequestWillBeSent, RequestWillBeSentParams, ResourceChangedPriority,
- ResponseReceived, DataReceived, LoadingFinished, ResponseReceivedParams, LoadingFailed, RequestServedFromCache};
+ ResponseReceived, DataReceived, DataReceivedParams, LoadingFinished, LoadingFinishedParams,
+ ResponseReceivedParams, LoadingFailed, LoadingFailedParams, RequestServedFromCache};
 
 
 
@@ -84,23 +85,6 @@ pub struct ExecutionContextDestroyedParams {
     pub execution_context_id: runtime::ExecutionContextId,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct ConsoleAPICalled {
-    pub params: ConsoleAPICalledParams,
-}
-
-#[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct ConsoleAPICalledParams {
-    #[serde(rename = "type")]
-    pub object_type: String,
-    pub args: Vec<runtime::RemoteObject>,
-    pub execution_context_id: runtime::ExecutionContextId,
-    pub stack_trace: Option<runtime::StackTrace>,
-    pub context: Option<String>,
-}
-
 
 // https://serde.rs/enum-representations.html
 
@@ -118,20 +102,8 @@ pub enum EmbeddedEvent {
     ExecutionContextCreated(ExecutionContextCreated),
     #[serde(rename = "Runtime.executionContextDestroyed")]
     ExecutionContextDestroyed(ExecutionContextDestroyed),
-    #[serde(rename = "Runtime.consoleAPICalled")]
-    ConsoleAPICalled(ConsoleAPICalled),
     #[serde(rename = "Page.domContentEventFired")]
     DomContentEventFired(DomContentEventFired),
-    #[serde(rename = "Network.requestWillBeSent")]
-    RequestWillBeSent(RequestWillBeSent),
-    #[serde(rename = "Network.responseReceived")]
-    ResponseReceived(ResponseReceived),
-    #[serde(rename = "Network.dataReceived")]
-    DataReceived(DataReceived),
-    #[serde(rename = "Network.loadingFinished")]
-    LoadingFinished(LoadingFinished),
-    #[serde(rename = "Network.loadingFailed")]
-    LoadingFailed(LoadingFailed),
     #[serde(rename = "Network.requestServedFromCache")]
     RequestServedFromCache(RequestServedFromCache),
     #[serde(rename = "Log.entryAdded")]
@@ -144,6 +116,9 @@ pub enum EmbeddedEvent {
     FrameResized(FrameResized),
     #[serde(rename = "Network.resourceChangedPriority")]
     ResourceChangedPriority(ResourceChangedPriority),
+    /// Any other method, which is left to `protocol::Event`.
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -15,12 +15,14 @@ pub mod network_statistics;
 pub mod task_queue;
 pub mod browser_context;
 pub mod task_manager;
+pub mod pool;
 
 pub use event_statistics::{EventStatistics, EventName};
 pub use network_statistics::{NetworkStatistics};
 pub use chrome_debug_session::{ChromeDebugSession};
 pub use task_queue::{TaskQueue, TaskQueueItem};
 pub use debug_session::{DebugSession};
+pub use pool::{DebugSessionPool, PoolResponse, TabLease};
 
 use failure;
 use task_describe::{self as tasks};
@@ -105,6 +107,43 @@ pub enum ChromePageError {
     },
     #[fail(display = "cannot find tab.")]
     TabNotFound,
+    #[fail(display = "target {} is lost: {}.", target_id, reason)]
+    TargetLost {
+        target_id: target::TargetId,
+        reason: TargetLostReason,
+    },
+}
+
+/// Why a tab can't be driven anymore.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TargetLostReason {
+    /// The renderer went away. Chrome reports a status (e.g. "crashed", "killed") and error code
+    /// only through `Target.targetCrashed`, not through `Inspector.targetCrashed`.
+    Crashed {
+        status: Option<String>,
+        error_code: Option<i64>,
+    },
+    /// Our session with the target ended without us closing it.
+    Detached,
+}
+
+impl std::fmt::Display for TargetLostReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TargetLostReason::Crashed { status, error_code } => {
+                write!(f, "crashed")?;
+                if let Some(status) = status {
+                    write!(f, " ({}", status)?;
+                    if let Some(error_code) = error_code {
+                        write!(f, ", error code {}", error_code)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            TargetLostReason::Detached => write!(f, "detached"),
+        }
+    }
 }
 
 
@@ -137,18 +176,11 @@ pub fn next_call_id() -> protocol::CallId {
     where
         C: protocol::Method + serde::Serialize,
     {
-        // If call method to target, it will not response with result, instead we will receive a message afterward. with the message id equal to call_id.
+        // targets are attached with flatten: true, so a call to a target just carries its session_id.
         match destination {
             MethodDestination::Target(session_id) => {
-                let call = method.to_method_call(call_id);
-                let message_text = serde_json::to_string(&call).expect("message_text should created.");
-                let target_method = target::methods::SendMessageToTarget {
-                    target_id: None,
-                    session_id: Some(session_id.as_str()),
-                    message: &message_text,
-                };
-                let nid = next_call_id();
-                create_msg_to_send(target_method, MethodDestination::Browser, nid)
+                let call = method.to_session_method_call(call_id, &session_id);
+                serde_json::to_string(&call).expect("message_text should created.")
             }
             MethodDestination::Browser => {
                 let call = method.to_method_call(call_id);
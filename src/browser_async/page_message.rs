@@ -2,8 +2,9 @@ use super::task_describe::{
     browser_tasks, dom_tasks, emulation_tasks, network_events, network_tasks, page_events,
     page_tasks, runtime_events, runtime_tasks, target_tasks,
 };
-use super::TaskId;
-use crate::protocol::{dom, network, page, target};
+use super::{TargetLostReason, TaskId};
+use crate::browser::tab::console::{ConsoleMessage, UncaughtException};
+use crate::protocol::{self, dom, fetch, network, page, target};
 use log::*;
 use std::fs::OpenOptions;
 use std::io::Write;
@@ -65,12 +66,23 @@ pub enum ReceivedEvent {
     ExecutionContextCreated(runtime_events::ExecutionContextCreated),
     ResponseReceived(network::RequestId),
     RequestIntercepted(network::RequestId),
+    /// Kept in the tab's `request_paused` until answered with `Tab::answer_paused_request`.
+    RequestPaused(fetch::RequestId),
+    /// Already answered according to the tab's `credentials_provider`.
+    AuthRequired(fetch::events::AuthRequiredParams),
     RequestWillBeSent(network::RequestId),
     LoadingFinished(network_events::LoadingFinished),
     DataReceived(network_events::DataReceived),
     LoadingFailed(network::RequestId),
     ResourceChangedPriority(network_events::ResourceChangedPriority),
     RequestServedFromCache(network_events::RequestServedFromCache),
+    /// Already answered according to the tab's `dialog_policy`, unless that's `Manual`.
+    JavascriptDialogOpening(page::events::JavascriptDialogOpeningParams),
+    /// Also kept in the tab's `console`, as are uncaught exceptions.
+    ConsoleMessage(ConsoleMessage),
+    UncaughtException(UncaughtException),
+    /// An event none of the above cover, or that couldn't be decoded (see its `decode_error`).
+    RawEvent(protocol::RawEvent),
 }
 
 #[derive(Debug)]
@@ -81,6 +93,8 @@ pub enum MethodCallDone {
     QuerySelector(dom_tasks::QuerySelectorTask),
     PrintToPdf(page_tasks::PrintToPdfTask),
     GetLayoutMetrics(page_tasks::GetLayoutMetricsTask),
+    /// Its `task_result` is the script's identifier, for `Tab::remove_script_on_new_document`.
+    AddScriptToEvaluateOnNewDocument(page_tasks::AddScriptToEvaluateOnNewDocumentTask),
     BringToFront(page_tasks::BringToFrontTask),
     DescribeNode(dom_tasks::DescribeNodeTask),
     GetBoxModel(dom_tasks::GetBoxModelTask),
@@ -92,10 +106,17 @@ pub enum MethodCallDone {
     CallFunctionOn(runtime_tasks::CallFunctionOnTask),
     SetIgnoreCertificateErrors(bool),
     GetResponseBodyForInterception(network_tasks::GetResponseBodyForInterceptionTask),
+    /// See `GetResponseBodyTask::get_response` for the response it's the body of.
+    GetResponseBody(network_tasks::GetResponseBodyTask),
+    NetworkGetResponseBody(network_tasks::NetworkGetResponseBodyTask),
     TargetAttached(page_tasks::AttachToTargetTask),
     CanEmulate(emulation_tasks::CanEmulateTask),
     SetDeviceMetricsOverride(emulation_tasks::SetDeviceMetricsOverrideTask),
     GetTargets(target_tasks::GetTargetsTask),
+    /// Its `task_result` is the ID of the new target.
+    TargetCreated(target_tasks::CreateTargetTask),
+    /// Its `task_result` is the ID of the new browser context.
+    BrowserContextCreated(target_tasks::CreateBrowserContextTask),
     GetBrowserCommandLine(browser_tasks::GetBrowserCommandLineTask),
 }
 
@@ -106,6 +127,8 @@ pub enum PageResponse {
     SecondsElapsed(usize),
     ReceivedEvent(ReceivedEvent),
     MethodCallDone(MethodCallDone),
+    /// The tab of the wrapper's `target_id` crashed or got detached; see `Tab::ensure_not_lost`.
+    TargetLost(TargetLostReason),
     Fail,
 }
 
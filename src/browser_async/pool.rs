@@ -0,0 +1,503 @@
+//! The async counterpart of `browser::pool`: tabs in a few `DebugSession`s, shared by many jobs.
+//!
+//! A `DebugSessionPool` runs up to `browsers` sessions from its factory, creates
+//! `contexts_per_browser` browser contexts in each, and leases out tabs in them. `lease` never
+//! blocks: it returns a `TabLease` straight away (or `None` while `max_leases` are out), and the
+//! pool's stream yields `PoolResponse::TabReady` once the lease's tab is open and attached,
+//! after which `DebugSessionPool::tab_mut` gets it.
+//!
+//! Dropping a lease wakes the pool, which closes the tab or, with `OnRelease::ResetToBlank`,
+//! keeps it for the next lease in its context, navigating it to about:blank first. A session
+//! that errors (e.g. because Chrome exited), ends, or has handed out `max_leases_per_browser`
+//! tabs is replaced by a fresh one from the factory; leases whose tab was in the old one are
+//! left dangling, which `tab_mut` reports with `None`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use futures::task::{self, AtomicTask};
+use futures::{Async, Poll, Stream};
+use log::*;
+
+use super::page_message::{MethodCallDone, PageResponse, PageResponseWrapper, ReceivedEvent};
+use super::protocol::target;
+use super::task_describe::{
+    target_tasks, CommonDescribeFieldsBuilder, CreateBrowserContextTaskBuilder,
+    CreateTargetTaskBuilder, HasTaskId,
+};
+use super::{DebugSession, Tab};
+use crate::browser::pool::{OnRelease, PoolOptions};
+
+/// The task ID of the `Target.createBrowserContext` calls the pool makes.
+const CONTEXT_TASK_ID: &str = "pool-context";
+/// Prefixes the lease ID in the task ID of the `Target.createTarget` calls the pool makes.
+const LEASE_TASK_PREFIX: &str = "pool-lease-";
+
+/// What the pool's stream yields.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum PoolResponse {
+    /// The tab of the lease with this ID (see `TabLease::id`) is open and attached.
+    TabReady(usize),
+    /// Something a session yielded, with the session's index.
+    Session(usize, PageResponseWrapper),
+}
+
+/// How leases hand themselves back to the pool.
+#[derive(Debug, Default)]
+struct Releases {
+    ids: Mutex<Vec<usize>>,
+    pool_task: AtomicTask,
+}
+
+/// A tab leased from a `DebugSessionPool`, which closes or keeps it once this is dropped.
+#[derive(Debug)]
+pub struct TabLease {
+    id: usize,
+    releases: Arc<Releases>,
+}
+
+impl TabLease {
+    /// The ID `PoolResponse::TabReady` reports this lease's tab with.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Drop for TabLease {
+    fn drop(&mut self) {
+        self.releases.ids.lock().unwrap().push(self.id);
+        self.releases.pool_task.notify();
+    }
+}
+
+/// A lease, as the pool sees it.
+struct Leased {
+    slot: usize,
+    generation: usize,
+    context_id: Option<target::BrowserContextID>,
+    /// Known once `Target.createTarget` answers, or straight away for a kept tab.
+    target_id: Option<target::TargetId>,
+    opening: bool,
+    attaching: bool,
+    ready: bool,
+}
+
+struct Slot {
+    session: DebugSession,
+    /// Bumped every time the session is replaced, so that leases on the old one can be told apart.
+    generation: usize,
+    context_ids: Vec<target::BrowserContextID>,
+    next_context: usize,
+    /// Whether Chrome is connected and every context is created, so that tabs can be opened.
+    ready: bool,
+    leased: usize,
+    handed_out: usize,
+    retired: bool,
+    /// Tabs of dropped leases, with the context they're in.
+    idle_tabs: Vec<(Option<target::BrowserContextID>, target::TargetId)>,
+}
+
+impl Slot {
+    fn new(session: DebugSession, generation: usize) -> Self {
+        Self {
+            session,
+            generation,
+            context_ids: vec![],
+            next_context: 0,
+            ready: false,
+            leased: 0,
+            handed_out: 0,
+            retired: false,
+            idle_tabs: vec![],
+        }
+    }
+
+    fn next_context_id(&mut self) -> Option<target::BrowserContextID> {
+        if self.context_ids.is_empty() {
+            return None;
+        }
+        let context_id = self.context_ids[self.next_context % self.context_ids.len()].clone();
+        self.next_context += 1;
+        Some(context_id)
+    }
+
+    fn close_tab(&mut self, target_id: &target::TargetId) {
+        if let Ok(tab) = self.session.find_tab_by_id_mut(Some(target_id)) {
+            tab.close();
+            return;
+        }
+        // not reported as created yet
+        let common_fields = CommonDescribeFieldsBuilder::default()
+            .target_id(target_id.clone())
+            .build()
+            .expect("build common_fields should success.");
+        let task = target_tasks::CloseTargetTaskBuilder::default()
+            .common_fields(common_fields)
+            .build()
+            .expect("build CloseTargetTaskBuilder should success.");
+        self.session.execute_one_task(task.into());
+    }
+}
+
+/// Leases tabs in a few `DebugSession`s; a Stream of what they yield, plus
+/// `PoolResponse::TabReady` for every leased tab that's ready to use.
+#[must_use = "streams do nothing unless polled"]
+pub struct DebugSessionPool {
+    options: PoolOptions,
+    factory: Box<dyn FnMut() -> DebugSession + Send>,
+    slots: Vec<Slot>,
+    leases: HashMap<usize, Leased>,
+    next_lease_id: usize,
+    releases: Arc<Releases>,
+    ready: VecDeque<usize>,
+    next_to_poll: usize,
+}
+
+impl DebugSessionPool {
+    /// A pool that gets its sessions from `factory`, which it only calls when leasing.
+    pub fn new<F>(options: PoolOptions, factory: F) -> Self
+    where
+        F: FnMut() -> DebugSession + Send + 'static,
+    {
+        Self {
+            options,
+            factory: Box::new(factory),
+            slots: vec![],
+            leases: HashMap::new(),
+            next_lease_id: 0,
+            releases: Arc::new(Releases::default()),
+            ready: VecDeque::new(),
+            next_to_poll: 0,
+        }
+    }
+
+    /// Leases a tab in the session with the fewest leases, starting a new one if fewer than
+    /// `browsers` are running. `None` if `max_leases` tabs are leased, or every session is
+    /// retired.
+    pub fn lease(&mut self) -> Option<TabLease> {
+        self.handle_releases();
+        if self.leases.len() >= self.options.max_leases {
+            return None;
+        }
+
+        let index = if self.slots.len() < self.options.browsers.max(1) {
+            let session = (self.factory)();
+            self.slots.push(Slot::new(session, 0));
+            self.slots.len() - 1
+        } else {
+            self.slots
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| !slot.retired)
+                .min_by_key(|(_, slot)| slot.leased)
+                .map(|(index, _)| index)?
+        };
+
+        let slot = &mut self.slots[index];
+        slot.leased += 1;
+        slot.handed_out += 1;
+        if self
+            .options
+            .max_leases_per_browser
+            .is_some_and(|max| slot.handed_out >= max)
+        {
+            info!(
+                "Retiring pooled session {}: it has handed out as many tabs as it may",
+                index
+            );
+            slot.retired = true;
+        }
+        let slot_ready = slot.ready;
+
+        let id = self.next_lease_id;
+        self.next_lease_id += 1;
+        self.leases.insert(
+            id,
+            Leased {
+                slot: index,
+                generation: self.slots[index].generation,
+                context_id: None,
+                target_id: None,
+                opening: false,
+                attaching: false,
+                ready: false,
+            },
+        );
+        if slot_ready {
+            self.open_tab(id);
+        }
+
+        Some(TabLease {
+            id,
+            releases: Arc::clone(&self.releases),
+        })
+    }
+
+    /// The tab `lease` got, once `PoolResponse::TabReady` reported it, unless its session has
+    /// been replaced since.
+    pub fn tab_mut(&mut self, lease: &TabLease) -> Option<&mut Tab> {
+        let leased = self.leases.get(&lease.id).filter(|leased| leased.ready)?;
+        let slot = self
+            .slots
+            .get_mut(leased.slot)
+            .filter(|slot| slot.generation == leased.generation)?;
+        slot.session
+            .find_tab_by_id_mut(leased.target_id.as_ref())
+            .ok()
+    }
+
+    /// The session `lease` is in, unless it has been replaced since.
+    pub fn session_mut(&mut self, lease: &TabLease) -> Option<&mut DebugSession> {
+        let leased = self.leases.get(&lease.id)?;
+        self.slots
+            .get_mut(leased.slot)
+            .filter(|slot| slot.generation == leased.generation)
+            .map(|slot| &mut slot.session)
+    }
+
+    pub fn session_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// How many tabs are leased, counting the ones dropped since the pool was last polled.
+    pub fn leased(&self) -> usize {
+        self.leases.len()
+    }
+
+    /// Reuses a kept tab of the lease's session, or opens one in its next context.
+    fn open_tab(&mut self, id: usize) {
+        let leased = self.leases.get_mut(&id).expect("only called for leases");
+        let slot = &mut self.slots[leased.slot];
+        leased.opening = true;
+
+        while let Some((context_id, target_id)) = slot.idle_tabs.pop() {
+            if let Ok(tab) = slot.session.find_tab_by_id_mut(Some(&target_id)) {
+                tab.navigate_to("about:blank");
+                leased.context_id = context_id;
+                leased.target_id = Some(target_id);
+                leased.ready = true;
+                self.ready.push_back(id);
+                self.releases.pool_task.notify();
+                return;
+            }
+        }
+
+        leased.context_id = slot.next_context_id();
+        let common_fields = CommonDescribeFieldsBuilder::default()
+            .task_id(format!("{}{}", LEASE_TASK_PREFIX, id))
+            .build()
+            .expect("build common_fields should success.");
+        let task = CreateTargetTaskBuilder::default()
+            .common_fields(common_fields)
+            .url("about:blank")
+            .browser_context_id(leased.context_id.clone())
+            .build()
+            .expect("build CreateTargetTaskBuilder should success.");
+        slot.session.execute_one_task(task.into());
+    }
+
+    /// Attaches to the lease's tab once it's both created and known to the session.
+    fn attach(&mut self, id: usize) {
+        let leased = self.leases.get_mut(&id).expect("only called for leases");
+        if leased.attaching {
+            return;
+        }
+        let slot = &mut self.slots[leased.slot];
+        if let Ok(tab) = slot.session.find_tab_by_id_mut(leased.target_id.as_ref()) {
+            tab.attach_to_page();
+            leased.attaching = true;
+        }
+    }
+
+    fn lease_of_target(&self, index: usize, target_id: &target::TargetId) -> Option<usize> {
+        let generation = self.slots[index].generation;
+        self.leases
+            .iter()
+            .find(|(_, leased)| {
+                leased.slot == index
+                    && leased.generation == generation
+                    && leased.target_id.as_ref() == Some(target_id)
+            })
+            .map(|(id, _)| *id)
+    }
+
+    /// Creates the session's contexts once it's connected, then opens the tabs of the leases
+    /// that were waiting for them, and attaches to those tabs.
+    fn handle_response(&mut self, index: usize, response: &PageResponseWrapper) {
+        match &response.page_response {
+            PageResponse::ChromeConnected => {
+                let slot = &mut self.slots[index];
+                slot.session.set_discover_targets(true);
+                for _ in 0..self.options.contexts_per_browser {
+                    let common_fields = CommonDescribeFieldsBuilder::default()
+                        .task_id(CONTEXT_TASK_ID.to_string())
+                        .build()
+                        .expect("build common_fields should success.");
+                    let task = CreateBrowserContextTaskBuilder::default()
+                        .common_fields(common_fields)
+                        .build()
+                        .expect("build CreateBrowserContextTaskBuilder should success.");
+                    slot.session.execute_one_task(task.into());
+                }
+                if self.options.contexts_per_browser == 0 {
+                    self.slot_ready(index);
+                }
+            }
+            PageResponse::MethodCallDone(MethodCallDone::BrowserContextCreated(task))
+                if task.get_task_id() == CONTEXT_TASK_ID =>
+            {
+                let slot = &mut self.slots[index];
+                slot.context_ids.extend(task.task_result.clone());
+                if slot.context_ids.len() == self.options.contexts_per_browser {
+                    self.slot_ready(index);
+                }
+            }
+            PageResponse::MethodCallDone(MethodCallDone::TargetCreated(task)) => {
+                let id = task
+                    .get_task_id()
+                    .strip_prefix(LEASE_TASK_PREFIX)
+                    .and_then(|id| id.parse().ok());
+                let (Some(id), Some(target_id)) = (id, task.task_result.clone()) else {
+                    return;
+                };
+                let generation = self.slots[index].generation;
+                match self.leases.get_mut(&id) {
+                    Some(leased) if leased.slot == index && leased.generation == generation => {
+                        leased.target_id = Some(target_id);
+                        self.attach(id);
+                    }
+                    // the lease was dropped before its tab was open
+                    _ => self.slots[index].close_tab(&target_id),
+                }
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::PageCreated) => {
+                let target_id = response.target_id.as_ref();
+                if let Some(id) = target_id.and_then(|id| self.lease_of_target(index, id)) {
+                    self.attach(id);
+                }
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                if let Some(id) = self.lease_of_target(index, &target_info.target_id) {
+                    let leased = self.leases.get_mut(&id).expect("just found");
+                    if !leased.ready {
+                        leased.ready = true;
+                        self.ready.push_back(id);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn slot_ready(&mut self, index: usize) {
+        self.slots[index].ready = true;
+        let generation = self.slots[index].generation;
+        let mut waiting: Vec<usize> = self
+            .leases
+            .iter()
+            .filter(|(_, leased)| {
+                leased.slot == index && leased.generation == generation && !leased.opening
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        waiting.sort_unstable();
+        for id in waiting {
+            self.open_tab(id);
+        }
+    }
+
+    fn handle_releases(&mut self) {
+        let released: Vec<usize> = self.releases.ids.lock().unwrap().drain(..).collect();
+        for id in released {
+            let Some(leased) = self.leases.remove(&id) else {
+                continue;
+            };
+            self.ready.retain(|ready| *ready != id);
+            let slot = match self.slots.get_mut(leased.slot) {
+                Some(slot) if slot.generation == leased.generation => slot,
+                _ => continue,
+            };
+            slot.leased -= 1;
+            // without a target, the tab is closed once `Target.createTarget` answers
+            if let Some(target_id) = leased.target_id {
+                let keep = self.options.on_release == OnRelease::ResetToBlank
+                    && leased.ready
+                    && !slot.retired;
+                if keep {
+                    slot.idle_tabs.push((leased.context_id, target_id));
+                } else {
+                    slot.close_tab(&target_id);
+                }
+            }
+        }
+
+        for index in 0..self.slots.len() {
+            if self.slots[index].retired && self.slots[index].leased == 0 {
+                self.replace(index);
+            }
+        }
+    }
+
+    /// Starts a fresh session in place of the one at `index`. Leases that didn't get their
+    /// tab yet move over to it; the others are left dangling.
+    fn replace(&mut self, index: usize) {
+        debug!("Replacing pooled session {}", index);
+        let generation = self.slots[index].generation;
+        let session = (self.factory)();
+        self.slots[index] = Slot::new(session, generation + 1);
+        for leased in self.leases.values_mut() {
+            if leased.slot == index && leased.generation == generation && !leased.ready {
+                leased.generation += 1;
+                leased.context_id = None;
+                leased.target_id = None;
+                leased.opening = false;
+                leased.attaching = false;
+                self.slots[index].leased += 1;
+                self.slots[index].handed_out += 1;
+            }
+        }
+    }
+}
+
+impl Stream for DebugSessionPool {
+    type Item = PoolResponse;
+    type Error = failure::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.releases.pool_task.register();
+        self.handle_releases();
+        if let Some(id) = self.ready.pop_front() {
+            return Ok(Async::Ready(Some(PoolResponse::TabReady(id))));
+        }
+
+        let count = self.slots.len();
+        let mut replaced = false;
+        for offset in 0..count {
+            let index = (self.next_to_poll + offset) % count;
+            match self.slots[index].session.poll() {
+                Ok(Async::Ready(Some(response))) => {
+                    self.next_to_poll = (index + 1) % count;
+                    self.handle_response(index, &response);
+                    return Ok(Async::Ready(Some(PoolResponse::Session(index, response))));
+                }
+                Ok(Async::Ready(None)) => {
+                    warn!("Pooled session {} ended", index);
+                    self.replace(index);
+                    replaced = true;
+                }
+                Err(err) => {
+                    warn!("Pooled session {} failed: {}", index, err);
+                    self.replace(index);
+                    replaced = true;
+                }
+                Ok(Async::NotReady) => {}
+            }
+        }
+        if replaced {
+            // the new sessions haven't been polled yet, so nothing would wake us up for them
+            task::current().notify();
+        }
+        Ok(Async::NotReady)
+    }
+}
@@ -1,14 +1,21 @@
-use super::super::browser::tab::{element::BoxModel, point::Point};
-use super::super::browser_async::{embedded_events, ChromeDebugSession, NetworkStatistics, TaskId};
+use super::super::browser::tab::{
+    auth::{AnsweredChallenges, AuthResponse, CredentialsProvider},
+    har::HarRecorder,
+    binding::{self, ExposedFunctions},
+    console::ConsoleBuffer, dialog::DialogPolicy,
+    interception::FetchDecision, element::BoxModel, point::Point,
+    NewDocumentScript,
+};
+use super::super::browser_async::{ChromeDebugSession, NetworkStatistics, TaskId};
 
-use super::super::protocol::{self, dom, network, page, runtime, target};
+use super::super::protocol::{self, dom, fetch, network, page, runtime, target};
 use super::page_message::ChangingFrame;
 use super::task_describe::{
     dom_tasks, input_tasks, log_tasks, network_events, network_tasks, page_events, page_tasks,
     runtime_tasks, target_tasks, ActivateTargetTaskBuilder, CommonDescribeFields,
     CommonDescribeFieldsBuilder, HasSessionId, TaskDescribe,
 };
-use super::{EventName, EventStatistics, TaskQueue, TaskQueueItem};
+use super::{ChromePageError, EventName, EventStatistics, TargetLostReason, TaskQueue, TaskQueueItem};
 use log::*;
 use rand::{thread_rng, Rng};
 use std::collections::HashMap;
@@ -44,6 +51,10 @@ impl ClosingState {
             true
         }
     }
+
+    pub fn is_closing(&self) -> bool {
+        self.issued_at.is_some()
+    }
 }
 
 #[derive(Debug)]
@@ -60,6 +71,9 @@ pub struct Tab {
     pub execution_context_descriptions:
         HashMap<page::FrameId, runtime::ExecutionContextDescription>,
     pub request_intercepted: HashMap<network::RequestId, network_events::RequestIntercepted>,
+    /// The requests paused by the Fetch domain (see `fetch_enable`) that haven't been answered
+    /// yet.
+    pub request_paused: HashMap<fetch::RequestId, network_events::RequestPaused>,
     pub response_received: HashMap<network::RequestId, network_events::ResponseReceived>,
     pub event_statistics: EventStatistics,
     pub task_queue: TaskQueue,
@@ -69,7 +83,24 @@ pub struct Tab {
     pub explicitly_close: bool,
     pub life_cycles: PageLifeCycles,
     pub network_statistics: NetworkStatistics,
+    /// Set by `start_har_recording`, from which point on the Network domain's events are fed
+    /// to it.
+    pub har_recorder: Option<HarRecorder>,
     pub box_model: Option<BoxModel>,
+    /// What the page logged to its console and the exceptions it didn't catch, captured once
+    /// the Runtime domain is enabled (see `runtime_enable`).
+    pub console: ConsoleBuffer,
+    /// How `alert`, `confirm`, `prompt` and `beforeunload` dialogs get answered; accepted by
+    /// default, so that e.g. `page_close` isn't held up by a `beforeunload` handler.
+    pub dialog_policy: DialogPolicy,
+    /// How HTTP authentication challenges get answered, once `fetch_enable` asked for them, or
+    /// when they come through `Network.setRequestInterception`.
+    pub credentials_provider: CredentialsProvider,
+    answered_challenges: AnsweredChallenges,
+    scripts_on_new_document: Vec<NewDocumentScript>,
+    exposed_functions: ExposedFunctions,
+    /// Set once the page crashed or got detached, after which it won't respond to anything.
+    pub lost: Option<TargetLostReason>,
     mouse_random_move_limit: Option<(u64, u64)>,
     next_mouse_move_task: Option<TaskQueueItem>,
 }
@@ -101,6 +132,7 @@ impl Tab {
             temporary_node_holder: HashMap::new(),
             execution_context_descriptions: HashMap::new(),
             request_intercepted: HashMap::new(),
+            request_paused: HashMap::new(),
             response_received: HashMap::new(),
             created_at: Instant::now(),
             activated_at: None,
@@ -112,8 +144,16 @@ impl Tab {
             life_cycles: PageLifeCycles{life_cycles: Vec::new()},
             event_statistics: EventStatistics::new(),
             network_statistics: NetworkStatistics::default(),
+            har_recorder: None,
             task_queue: TaskQueue::new(),
             box_model: None,
+            console: ConsoleBuffer::default(),
+            dialog_policy: DialogPolicy::default(),
+            credentials_provider: CredentialsProvider::default(),
+            answered_challenges: AnsweredChallenges::default(),
+            scripts_on_new_document: Vec::new(),
+            exposed_functions: ExposedFunctions::default(),
+            lost: None,
             mouse_random_move_limit: None,
             next_mouse_move_task: None,
         }
@@ -174,6 +214,17 @@ impl Tab {
         }
     }
 
+    pub fn ensure_not_lost(&self) -> Result<(), ChromePageError> {
+        if let Some(reason) = &self.lost {
+            Err(ChromePageError::TargetLost {
+                target_id: self.target_info.target_id.clone(),
+                reason: reason.clone(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn url_in(&self, urls: Vec<&str>) -> bool {
         urls.contains(&self.get_url())
     }
@@ -196,6 +247,117 @@ impl Tab {
         }
     }
 
+    /// Evaluates `source` in every frame of this page as soon as it gets a new document, before
+    /// the page's own scripts run. Its identifier comes back with
+    /// `MethodCallDone::AddScriptToEvaluateOnNewDocument`, and it's added again whenever the page
+    /// is attached to under a new session.
+    pub fn add_script_on_new_document(&mut self, source: &str, manual_task_id: Option<TaskId>) {
+        let task = page_tasks::AddScriptToEvaluateOnNewDocumentTaskBuilder::default()
+            .common_fields(self.get_common_field(manual_task_id))
+            .source(source)
+            .build()
+            .expect("build AddScriptToEvaluateOnNewDocumentTaskBuilder should success.");
+        self.execute_one_task(task.into());
+    }
+
+    pub fn remove_script_on_new_document(&mut self, identifier: &str) {
+        let current_identifier = match self
+            .scripts_on_new_document
+            .iter()
+            .position(|script| script.identifier == identifier)
+        {
+            Some(index) => self.scripts_on_new_document.remove(index).current_identifier,
+            None => identifier.to_string(),
+        };
+        let task = page_tasks::RemoveScriptToEvaluateOnNewDocumentTaskBuilder::default()
+            .common_fields(self.get_common_field(None))
+            .identifier(current_identifier)
+            .build()
+            .expect("build RemoveScriptToEvaluateOnNewDocumentTaskBuilder should success.");
+        self.execute_one_task(task.into());
+    }
+
+    pub fn script_on_new_document_added(
+        &mut self,
+        task: &page_tasks::AddScriptToEvaluateOnNewDocumentTask,
+    ) {
+        let identifier = match &task.task_result {
+            Some(identifier) => identifier.clone(),
+            None => return,
+        };
+        match &task.re_adding {
+            Some(first_identifier) => {
+                if let Some(script) = self
+                    .scripts_on_new_document
+                    .iter_mut()
+                    .find(|script| script.identifier == *first_identifier)
+                {
+                    script.current_identifier = identifier;
+                }
+            }
+            None => self.scripts_on_new_document.push(NewDocumentScript {
+                identifier: identifier.clone(),
+                current_identifier: identifier,
+                source: task.source.clone(),
+            }),
+        }
+    }
+
+    /// Makes `function` callable from the page as `window.<name>(...)`, returning a promise of
+    /// its result, or rejected with its error. It gets the arguments the page passed as an
+    /// array, and is called while polling the session. The function is available in the current
+    /// document and every new one, also after re-attaching. Enables the Runtime domain, which
+    /// the calls come through.
+    pub fn expose_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(serde_json::Value) -> Result<serde_json::Value, failure::Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.exposed_functions.insert(name, Arc::new(function));
+        self.runtime_enable();
+        let task = self.add_binding_task(binding::binding_name(name));
+        self.execute_one_task(task);
+        let wrapper = binding::page_wrapper(name);
+        self.add_script_on_new_document(&wrapper, None);
+        self.evaluate_expression(wrapper);
+    }
+
+    fn add_binding_task(&self, name: String) -> TaskDescribe {
+        runtime_tasks::AddBindingTaskBuilder::default()
+            .common_fields(self.get_common_field(None))
+            .name(name)
+            .build()
+            .expect("build AddBindingTaskBuilder should success.")
+            .into()
+    }
+
+    /// Calls the exposed function the page called, settling the promise it got with the result.
+    pub fn binding_called(&mut self, params: &runtime::events::BindingCalledParams) {
+        let call = self.exposed_functions.called(params);
+        if let Some(expression) = call.and_then(binding::ExposedCall::call) {
+            let task = runtime_tasks::EvaluateTaskBuilder::default()
+                .common_fields(self.get_common_field(None))
+                .expression(expression)
+                .context_id(Some(params.execution_context_id))
+                .build()
+                .expect("build EvaluateTaskBuilder should success.");
+            self.execute_one_task(task.into());
+        }
+    }
+
+    /// Answers the dialog the page has open, for when `dialog_policy` is `DialogPolicy::Manual`.
+    pub fn handle_java_script_dialog(&mut self, accept: bool, prompt_text: Option<String>) {
+        let task = page_tasks::HandleJavaScriptDialogTaskBuilder::default()
+            .common_fields(self.get_common_field(None))
+            .accept(accept)
+            .prompt_text(prompt_text)
+            .build()
+            .expect("build HandleJavaScriptDialogTaskBuilder should success.");
+        self.execute_one_task(task.into());
+    }
+
     pub fn close(&mut self) {
         let b = self.closing.continue_sending();
         if b {
@@ -314,6 +476,110 @@ impl Tab {
         self.execute_one_task(task.into());
     }
 
+    /// Pauses the requests matching `patterns` through the Fetch domain, each reported with
+    /// `ReceivedEvent::RequestPaused` and held until it's answered with `answer_paused_request`.
+    /// Unless `credentials_provider` is `CredentialsProvider::BrowserDefault`, the
+    /// authentication challenges the requests run into get answered with it too, and reported
+    /// with `ReceivedEvent::AuthRequired`.
+    pub fn fetch_enable(&mut self, patterns: Vec<fetch::RequestPattern>) {
+        let handle_auth_requests =
+            !matches!(self.credentials_provider, CredentialsProvider::BrowserDefault);
+        let task = network_tasks::FetchEnableTaskBuilder::default()
+            .common_fields(self.get_common_field(None))
+            .patterns(patterns)
+            .handle_auth_requests(Some(handle_auth_requests).filter(|handle| *handle))
+            .build()
+            .expect("FetchEnableTaskBuilder should work.");
+        self.execute_one_task(task.into());
+    }
+
+    /// Gets the body of a request paused at the `Response` stage, which comes with
+    /// `MethodCallDone::GetResponseBody`.
+    pub fn get_paused_response_body(&mut self, request_id: &str, manual_task_id: Option<TaskId>) {
+        let task = network_tasks::GetResponseBodyTaskBuilder::default()
+            .common_fields(self.get_common_field(manual_task_id))
+            .request_id(request_id)
+            .build()
+            .expect("GetResponseBodyTaskBuilder should work.");
+        self.execute_one_task(task.into());
+    }
+
+    /// Answers an authentication challenge with `credentials_provider`, or cancels it if the
+    /// request was given credentials before, which Chrome rejected.
+    pub fn respond_to_auth_challenge(
+        &mut self,
+        request_id: &str,
+        challenge: &network::events::AuthChallenge,
+    ) -> AuthResponse {
+        self.answered_challenges
+            .respond(&self.credentials_provider, request_id, challenge)
+    }
+
+    pub fn continue_with_auth(&mut self, request_id: &str, auth_response: AuthResponse) {
+        let task = network_tasks::ContinueWithAuthTaskBuilder::default()
+            .common_fields(self.get_common_field(None))
+            .request_id(request_id)
+            .auth_response(auth_response)
+            .build()
+            .expect("ContinueWithAuthTaskBuilder should work.");
+        self.execute_one_task(task.into());
+    }
+
+    pub fn continue_intercepted_request_with_auth(
+        &mut self,
+        interception_id: String,
+        auth_response: &AuthResponse,
+    ) {
+        let param = auth_response.as_method_param();
+        let auth_challenge_response = network_tasks::continue_intercepted_request::AuthChallengeResponse {
+            response: param.response.to_string(),
+            username: param.username.map(str::to_string),
+            password: param.password.map(str::to_string),
+        };
+        let task = network_tasks::ContinueInterceptedRequestTaskBuilder::default()
+            .common_fields(self.get_common_field(None))
+            .interception_id(interception_id)
+            .auth_challenge_response(Some(auth_challenge_response))
+            .build()
+            .expect("ContinueInterceptedRequestTaskBuilder should work.");
+        self.execute_one_task(task.into());
+    }
+
+    /// Lets the paused request go on, answers it with a response of our own, or fails it.
+    pub fn answer_paused_request(&mut self, request_id: &str, decision: FetchDecision) {
+        self.request_paused.remove(request_id);
+        let common_fields = self.get_common_field(None);
+        let task: TaskDescribe = match decision {
+            FetchDecision::Continue(overrides) => network_tasks::ContinueRequestTaskBuilder::default()
+                .common_fields(common_fields)
+                .request_id(request_id)
+                .url(overrides.url)
+                .method(overrides.method)
+                .post_data(overrides.post_data)
+                .headers(overrides.headers)
+                .build()
+                .expect("ContinueRequestTaskBuilder should work.")
+                .into(),
+            FetchDecision::Fulfill(response) => network_tasks::FulfillRequestTaskBuilder::default()
+                .common_fields(common_fields)
+                .request_id(request_id)
+                .response_code(response.status)
+                .response_headers(response.headers)
+                .body(response.body)
+                .build()
+                .expect("FulfillRequestTaskBuilder should work.")
+                .into(),
+            FetchDecision::Fail(error_reason) => network_tasks::FailRequestTaskBuilder::default()
+                .common_fields(common_fields)
+                .request_id(request_id)
+                .error_reason(error_reason)
+                .build()
+                .expect("FailRequestTaskBuilder should work.")
+                .into(),
+        };
+        self.execute_one_task(task);
+    }
+
     pub fn node_arrived(&mut self, parent_node_id: dom::NodeId, mut nodes: Vec<dom::Node>) {
         self.temporary_node_holder
             .entry(parent_node_id)
@@ -372,7 +638,7 @@ impl Tab {
 
     pub fn verify_execution_context_id(
         &self,
-        console_api_called: &embedded_events::ConsoleAPICalledParams,
+        console_api_called: &runtime::events::ConsoleAPICalledParams,
     ) {
         let ex = self
             .execution_context_descriptions
@@ -888,6 +1154,23 @@ impl Tab {
         }
     }
 
+    /// Starts recording the network traffic into `har_recorder` (dropping what it had), and
+    /// enables the Network domain. With `record_bodies`, the body of each response that
+    /// finished loading is fetched too, coming back as `MethodCallDone::NetworkGetResponseBody`.
+    pub fn start_har_recording(&mut self, record_bodies: bool) {
+        self.har_recorder = Some(HarRecorder::new(record_bodies));
+        self.network_enable();
+    }
+
+    pub fn network_get_response_body(&mut self, request_id: &str, manual_task_id: Option<TaskId>) {
+        let task = network_tasks::NetworkGetResponseBodyTaskBuilder::default()
+            .common_fields(self.get_common_field(manual_task_id))
+            .request_id(request_id)
+            .build()
+            .expect("NetworkGetResponseBodyTaskBuilder should work.");
+        self.execute_one_task(task.into());
+    }
+
     pub fn network_enable_named(&mut self, name: &str) {
         let task = self.network_enable_task_impl(Some(name));
         self.execute_one_task(task);
@@ -1010,7 +1293,34 @@ impl Tab {
 
     pub fn page_attached(&mut self, session_id: target::SessionID) {
         let session_id_cloned = session_id.clone();
+        let re_attached = self
+            .session_id
+            .as_ref()
+            .is_some_and(|previous| *previous != session_id);
         self.session_id.replace(session_id);
+        if re_attached {
+            // the bindings have to be there before the scripts wrapping them run
+            let mut tasks: Vec<TaskDescribe> = self
+                .exposed_functions
+                .binding_names()
+                .map(|name| self.add_binding_task(name))
+                .collect();
+            let script_tasks: Vec<TaskDescribe> = self
+                .scripts_on_new_document
+                .iter()
+                .map(|script| {
+                    page_tasks::AddScriptToEvaluateOnNewDocumentTaskBuilder::default()
+                        .common_fields(self.get_common_field(None))
+                        .source(script.source.clone())
+                        .re_adding(Some(script.identifier.clone()))
+                        .build()
+                        .expect("build AddScriptToEvaluateOnNewDocumentTaskBuilder should success.")
+                        .into()
+                })
+                .collect();
+            tasks.extend(script_tasks);
+            self.execute_tasks(tasks);
+        }
         if !self.waiting_for_page_attach_tasks.is_empty() {
             let tasks: Vec<TaskDescribe> = self
                 .waiting_for_page_attach_tasks
@@ -9,6 +9,7 @@ pub enum BrowserCallMethodTask {
     ActivateTarget(target_tasks::ActivateTargetTask),
     GetTargets(target_tasks::GetTargetsTask),
     CreateTarget(target_tasks::CreateTargetTask),
+    CreateBrowserContext(target_tasks::CreateBrowserContextTask),
     SetDiscoverTargets(target_tasks::SetDiscoverTargetsTask),
     SetIgnoreCertificateErrors(security_tasks::SetIgnoreCertificateErrorsTask),
     SecurityEnable(security_tasks::SecurityEnableTask),
@@ -33,10 +34,18 @@ pub fn handle_browser_method_call(
             trace!("TargetSetDiscoverTargets returned. {:?}", task);
             Ok(PageResponseWrapper::default())
         }
-        BrowserCallMethodTask::CreateTarget(task) => {
-            trace!("CreateTarget returned. {:?}", task);
-            Ok(PageResponseWrapper::default())
-        }
+        BrowserCallMethodTask::CreateTarget(task) => Ok(PageResponseWrapper {
+            target_id: task.task_result.clone(),
+            task_id: Some(task.get_task_id()),
+            page_response: PageResponse::MethodCallDone(MethodCallDone::TargetCreated(task)),
+        }),
+        BrowserCallMethodTask::CreateBrowserContext(task) => Ok(PageResponseWrapper {
+            target_id: maybe_target_id,
+            task_id: Some(task.get_task_id()),
+            page_response: PageResponse::MethodCallDone(MethodCallDone::BrowserContextCreated(
+                task,
+            )),
+        }),
         BrowserCallMethodTask::SetIgnoreCertificateErrors(task) => Ok(PageResponseWrapper {
             target_id: maybe_target_id,
             task_id: Some(task.get_task_id()),
@@ -43,15 +43,20 @@ pub use security_tasks::{
 
 pub use target_tasks::{
     handle_target_event, target_events, ActivateTargetTask, ActivateTargetTaskBuilder,
-    CreateTargetTask, CreateTargetTaskBuilder, GetTargetsTask, SetDiscoverTargetsTask,
+    CreateBrowserContextTask, CreateBrowserContextTaskBuilder, CreateTargetTask,
+    CreateTargetTaskBuilder, GetTargetsTask, SetDiscoverTargetsTask,
     SetDiscoverTargetsTaskBuilder, TargetEvent,
 };
 
 pub use network_tasks::{
     handle_network_event, network_events, ContinueInterceptedRequestTask,
-    ContinueInterceptedRequestTaskBuilder, GetResponseBodyForInterceptionTask,
+    ContinueInterceptedRequestTaskBuilder, ContinueRequestTask, ContinueRequestTaskBuilder,
+    ContinueWithAuthTask, ContinueWithAuthTaskBuilder,
+    FailRequestTask, FailRequestTaskBuilder, FetchEnableTask, FetchEnableTaskBuilder,
+    FulfillRequestTask, FulfillRequestTaskBuilder, GetResponseBodyTask,
+    GetResponseBodyTaskBuilder, GetResponseBodyForInterceptionTask,
     GetResponseBodyForInterceptionTaskBuilder, NetworkEnableTask, NetworkEnableTaskBuilder,
-    NetworkEvent, SetRequestInterceptionTask, SetRequestInterceptionTaskBuilder,
+    NetworkEvent, NetworkGetResponseBodyTask, NetworkGetResponseBodyTaskBuilder, SetRequestInterceptionTask, SetRequestInterceptionTaskBuilder,
 };
 
 pub use browser_call_methods::{handle_browser_method_call, BrowserCallMethodTask};
@@ -163,6 +168,7 @@ pub enum TaskDescribe {
     DomEvent(DomEvent),
     LogEvent(LogEvent),
     NetworkEvent(NetworkEvent),
+    RawEvent(protocol::RawEvent),
     Interval,
     ChromeConnected,
 }
@@ -179,16 +185,27 @@ impl_iro_iro_for_task_describe!(
         TargetCallMethodTask::NavigateTo,
         TargetCallMethodTask::PageEnable,
         TargetCallMethodTask::RuntimeEnable,
+        TargetCallMethodTask::AddBinding,
         TargetCallMethodTask::Evaluate,
         TargetCallMethodTask::GetProperties,
         TargetCallMethodTask::RuntimeCallFunctionOn,
         TargetCallMethodTask::NetworkEnable,
         TargetCallMethodTask::SetRequestInterception,
         TargetCallMethodTask::GetResponseBodyForInterception,
+        TargetCallMethodTask::NetworkGetResponseBody,
         TargetCallMethodTask::ContinueInterceptedRequest,
+        TargetCallMethodTask::FetchEnable,
+        TargetCallMethodTask::ContinueRequest,
+        TargetCallMethodTask::FulfillRequest,
+        TargetCallMethodTask::FailRequest,
+        TargetCallMethodTask::GetResponseBody,
+        TargetCallMethodTask::ContinueWithAuth,
         TargetCallMethodTask::PageReload,
         TargetCallMethodTask::GetLayoutMetrics,
         TargetCallMethodTask::BringToFront,
+        TargetCallMethodTask::HandleJavaScriptDialog,
+        TargetCallMethodTask::AddScriptToEvaluateOnNewDocument,
+        TargetCallMethodTask::RemoveScriptToEvaluateOnNewDocument,
         TargetCallMethodTask::PageClose,
         TargetCallMethodTask::DispatchMouseEvent,
         TargetCallMethodTask::CanEmulate,
@@ -198,6 +215,7 @@ impl_iro_iro_for_task_describe!(
     ],
     [
         BrowserCallMethodTask::CreateTarget,
+        BrowserCallMethodTask::CreateBrowserContext,
         BrowserCallMethodTask::SetDiscoverTargets,
         BrowserCallMethodTask::SetIgnoreCertificateErrors,
         BrowserCallMethodTask::SecurityEnable,
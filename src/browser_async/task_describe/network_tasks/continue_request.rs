@@ -0,0 +1,36 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask, HasCommonField, CanCreateMethodString, };
+use crate::protocol::{fetch};
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct ContinueRequestTask {
+    pub common_fields: CommonDescribeFields,
+    pub request_id: fetch::RequestId,
+    #[builder(default = "None")]
+    pub url: Option<String>,
+    #[builder(default = "None")]
+    pub method: Option<String>,
+    #[builder(default = "None")]
+    pub post_data: Option<Vec<u8>>,
+    #[builder(default = "None")]
+    pub headers: Option<Vec<fetch::HeaderEntry>>,
+}
+
+impl_has_common_fields!(ContinueRequestTask, "ContinueRequestTask");
+
+impl AsMethodCallString for ContinueRequestTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let post_data = self.post_data.as_ref().map(base64::encode);
+        let method = fetch::methods::ContinueRequest {
+            request_id: &self.request_id,
+            url: self.url.as_deref(),
+            method: self.method.as_deref(),
+            post_data: post_data.as_deref(),
+            headers: self.headers.as_deref(),
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::ContinueRequest, ContinueRequestTask);
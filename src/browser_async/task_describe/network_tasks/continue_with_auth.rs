@@ -0,0 +1,33 @@
+use super::super::{
+    AsMethodCallString, CanCreateMethodString, CommonDescribeFields, HasCommonField,
+    TargetCallMethodTask, TaskDescribe,
+};
+use crate::browser::tab::auth::AuthResponse;
+use crate::protocol::fetch;
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct ContinueWithAuthTask {
+    pub common_fields: CommonDescribeFields,
+    pub request_id: fetch::RequestId,
+    pub auth_response: AuthResponse,
+}
+
+impl_has_common_fields!(ContinueWithAuthTask, "ContinueWithAuthTask");
+
+impl AsMethodCallString for ContinueWithAuthTask {
+    fn get_method_str(&self) -> Result<String, failure::Error> {
+        let method = fetch::methods::ContinueWithAuth {
+            request_id: &self.request_id,
+            auth_challenge_response: self.auth_response.as_method_param(),
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(
+    TaskDescribe::TargetCallMethod,
+    TargetCallMethodTask::ContinueWithAuth,
+    ContinueWithAuthTask
+);
@@ -0,0 +1,25 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask, HasCommonField, CanCreateMethodString, };
+use crate::protocol::{fetch, network};
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct FailRequestTask {
+    pub common_fields: CommonDescribeFields,
+    pub request_id: fetch::RequestId,
+    pub error_reason: network::ErrorReason,
+}
+
+impl_has_common_fields!(FailRequestTask, "FailRequestTask");
+
+impl AsMethodCallString for FailRequestTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let method = fetch::methods::FailRequest {
+            request_id: &self.request_id,
+            error_reason: self.error_reason,
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::FailRequest, FailRequestTask);
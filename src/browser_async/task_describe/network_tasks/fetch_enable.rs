@@ -0,0 +1,27 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask, HasCommonField, CanCreateMethodString, };
+use crate::protocol::{fetch};
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct FetchEnableTask {
+    pub common_fields: CommonDescribeFields,
+    #[builder(default)]
+    pub patterns: Vec<fetch::RequestPattern>,
+    #[builder(default = "None")]
+    pub handle_auth_requests: Option<bool>,
+}
+
+impl_has_common_fields!(FetchEnableTask, "FetchEnableTask");
+
+impl AsMethodCallString for FetchEnableTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let method = fetch::methods::Enable {
+            patterns: Some(&self.patterns),
+            handle_auth_requests: self.handle_auth_requests,
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::FetchEnable, FetchEnableTask);
@@ -0,0 +1,35 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask, HasCommonField, CanCreateMethodString, };
+use crate::protocol::{fetch};
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct FulfillRequestTask {
+    pub common_fields: CommonDescribeFields,
+    pub request_id: fetch::RequestId,
+    pub response_code: u32,
+    #[builder(default)]
+    pub response_headers: Vec<fetch::HeaderEntry>,
+    #[builder(default)]
+    pub body: Vec<u8>,
+    #[builder(default = "None")]
+    pub response_phrase: Option<String>,
+}
+
+impl_has_common_fields!(FulfillRequestTask, "FulfillRequestTask");
+
+impl AsMethodCallString for FulfillRequestTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let body = base64::encode(&self.body);
+        let method = fetch::methods::FulfillRequest {
+            request_id: &self.request_id,
+            response_code: self.response_code,
+            response_headers: Some(&self.response_headers),
+            body: Some(&body),
+            response_phrase: self.response_phrase.as_deref(),
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::FulfillRequest, FulfillRequestTask);
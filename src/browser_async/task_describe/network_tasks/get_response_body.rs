@@ -0,0 +1,46 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask, HasCommonField, CanCreateMethodString, };
+use crate::browser::tab::interception::{self, FulfilledResponse};
+use crate::protocol::{fetch};
+use failure;
+
+/// Gets the body of a request the Fetch domain paused at the `Response` stage.
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct GetResponseBodyTask {
+    pub common_fields: CommonDescribeFields,
+    pub request_id: fetch::RequestId,
+    #[builder(default = "None")]
+    pub task_result: Option<fetch::methods::GetResponseBodyReturnObject>,
+}
+
+impl_has_common_fields!(GetResponseBodyTask, "GetResponseBodyTask");
+
+impl AsMethodCallString for GetResponseBodyTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let method = fetch::methods::GetResponseBody {
+            request_id: &self.request_id,
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl GetResponseBodyTask {
+    /// The body, decoded from base64 if it came that way.
+    pub fn get_body(&self) -> Result<Vec<u8>, failure::Error> {
+        match &self.task_result {
+            Some(task_result) => interception::decode_body(task_result),
+            None => failure::bail!("no task_result: {:?}", self),
+        }
+    }
+
+    /// The server's response to `paused` with this body, to change and answer the request with
+    /// as `FetchDecision::Fulfill`.
+    pub fn get_response(
+        &self,
+        paused: &fetch::events::RequestPausedParams,
+    ) -> Result<Option<FulfilledResponse>, failure::Error> {
+        Ok(FulfilledResponse::from_paused(paused, self.get_body()?))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::GetResponseBody, GetResponseBodyTask);
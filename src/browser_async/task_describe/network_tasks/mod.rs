@@ -1,19 +1,35 @@
 pub mod continue_intercepted_request;
+pub mod continue_request;
+pub mod continue_with_auth;
+pub mod fail_request;
+pub mod fetch_enable;
+pub mod fulfill_request;
+pub mod get_response_body;
 pub mod get_response_body_for_interception;
 pub mod network_events;
 pub mod set_request_interception;
 pub mod network_enable;
+pub mod network_get_response_body;
 
 
 use crate::browser_async::debug_session::DebugSession;
 pub use continue_intercepted_request::{
     ContinueInterceptedRequestTask, ContinueInterceptedRequestTaskBuilder,
 };
+pub use continue_request::{ContinueRequestTask, ContinueRequestTaskBuilder};
+pub use continue_with_auth::{ContinueWithAuthTask, ContinueWithAuthTaskBuilder};
+pub use fail_request::{FailRequestTask, FailRequestTaskBuilder};
+pub use fetch_enable::{FetchEnableTask, FetchEnableTaskBuilder};
+pub use fulfill_request::{FulfillRequestTask, FulfillRequestTaskBuilder};
+pub use get_response_body::{GetResponseBodyTask, GetResponseBodyTaskBuilder};
 pub use get_response_body_for_interception::{
     GetResponseBodyForInterceptionTask, GetResponseBodyForInterceptionTaskBuilder,
 };
 
 pub use network_enable::{NetworkEnableTask, NetworkEnableTaskBuilder};
+pub use network_get_response_body::{
+    NetworkGetResponseBodyTask, NetworkGetResponseBodyTaskBuilder,
+};
 pub use set_request_interception::{SetRequestInterceptionTask, SetRequestInterceptionTaskBuilder};
 
 use super::super::protocol::target;
@@ -28,6 +44,8 @@ pub enum NetworkEvent {
     DataReceived(network_events::DataReceived),
     LoadingFinished(network_events::LoadingFinished),
     RequestIntercepted(network_events::RequestIntercepted),
+    RequestPaused(network_events::RequestPaused),
+    AuthRequired(network_events::AuthRequired),
     LoadingFailed(network_events::LoadingFailed),
 }
 
@@ -42,6 +60,9 @@ pub fn handle_network_event(
         NetworkEvent::ResponseReceived(event) => {
             let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
             let request_id = event.get_request_id();
+            if let Some(recorder) = tab.har_recorder.as_mut() {
+                recorder.response_received(event.get_raw_parameters());
+            }
             tab.response_received.insert(request_id.clone(), event);
             Ok(PageResponseWrapper {
                 target_id: maybe_target_id,
@@ -54,6 +75,11 @@ pub fn handle_network_event(
         NetworkEvent::RequestIntercepted(event) => {
             let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
             let request_id = event.get_interception_id();
+            if let Some(challenge) = &event.get_raw_parameters().auth_challenge {
+                let response = tab.respond_to_auth_challenge(&request_id, challenge);
+                tab.continue_intercepted_request_with_auth(request_id, &response);
+                return Ok(PageResponseWrapper::default());
+            }
             tab.request_intercepted.insert(request_id.clone(), event);
             Ok(PageResponseWrapper {
                 target_id: maybe_target_id,
@@ -63,9 +89,36 @@ pub fn handle_network_event(
                 )),
             })
         }
+        NetworkEvent::RequestPaused(event) => {
+            let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
+            let request_id = event.get_request_id();
+            tab.request_paused.insert(request_id.clone(), event);
+            Ok(PageResponseWrapper {
+                target_id: maybe_target_id,
+                task_id: None,
+                page_response: PageResponse::ReceivedEvent(ReceivedEvent::RequestPaused(
+                    request_id,
+                )),
+            })
+        }
+        NetworkEvent::AuthRequired(event) => {
+            let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
+            let params = event.into_raw_parameters();
+            let response =
+                tab.respond_to_auth_challenge(&params.request_id, &params.auth_challenge);
+            tab.continue_with_auth(&params.request_id, response);
+            Ok(PageResponseWrapper {
+                target_id: maybe_target_id,
+                task_id: None,
+                page_response: PageResponse::ReceivedEvent(ReceivedEvent::AuthRequired(params)),
+            })
+        }
         NetworkEvent::RequestWillBeSent(event) => {
             let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
             let request_id = event.get_request_id();
+            if let Some(recorder) = tab.har_recorder.as_mut() {
+                recorder.request_will_be_sent(event.get_request_will_sent_params());
+            }
             tab.network_statistics.request_will_be_sent(event);
             Ok(PageResponseWrapper {
                 target_id: maybe_target_id,
@@ -76,6 +129,16 @@ pub fn handle_network_event(
             })
         }
         NetworkEvent::LoadingFinished(event) => {
+            // passed on for any target, whether or not there's a tab recording it.
+            if let Ok(tab) = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref()) {
+                if let Some(recorder) = tab.har_recorder.as_mut() {
+                    recorder.loading_finished(event.get_raw_parameters());
+                    let request_id = event.get_request_id();
+                    if recorder.wants_body(&request_id) {
+                        tab.network_get_response_body(&request_id, None);
+                    }
+                }
+            }
             Ok(PageResponseWrapper {
                 target_id: maybe_target_id,
                 task_id: None,
@@ -83,6 +146,11 @@ pub fn handle_network_event(
             })
         }
         NetworkEvent::DataReceived(event) => {
+            if let Ok(tab) = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref()) {
+                if let Some(recorder) = tab.har_recorder.as_mut() {
+                    recorder.data_received(event.get_raw_parameters());
+                }
+            }
             Ok(PageResponseWrapper {
                 target_id: maybe_target_id,
                 task_id: None,
@@ -99,6 +167,9 @@ pub fn handle_network_event(
         NetworkEvent::LoadingFailed(event) => {
             let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
             let request_id = event.get_request_id();
+            if let Some(recorder) = tab.har_recorder.as_mut() {
+                recorder.loading_failed(event.get_raw_parameters());
+            }
             tab.network_statistics.loading_failed(event);
             Ok(PageResponseWrapper {
                 target_id: maybe_target_id,
@@ -1,7 +1,7 @@
 use super::super::super::embedded_events;
 use super::super::TaskDescribe;
 use super::NetworkEvent;
-use crate::protocol::network;
+use crate::protocol::{fetch, network};
 use std::collections::HashMap;
 use failure;
 
@@ -116,6 +116,10 @@ wrapper_raw_event!(
 );
 
 impl LoadingFailed {
+    pub fn get_raw_parameters(&self) -> &embedded_events::LoadingFailedParams {
+        &self.raw_event.params
+    }
+
     pub fn get_request_id(&self) -> network::RequestId {
         self.raw_event.params.request_id.clone()
     }
@@ -128,6 +132,12 @@ wrapper_raw_event!(
     embedded_events::DataReceived
 );
 
+impl DataReceived {
+    pub fn get_raw_parameters(&self) -> &embedded_events::DataReceivedParams {
+        &self.raw_event.params
+    }
+}
+
 wrapper_raw_event!(
     TaskDescribe::NetworkEvent,
     NetworkEvent::LoadingFinished,
@@ -136,12 +146,46 @@ wrapper_raw_event!(
 );
 
 impl LoadingFinished {
+    pub fn get_raw_parameters(&self) -> &embedded_events::LoadingFinishedParams {
+        &self.raw_event.params
+    }
+
     pub fn get_request_id(&self) -> network::RequestId {
         self.raw_event.params.request_id.clone()
     }
 }
 
 
+wrapper_raw_event!(
+    TaskDescribe::NetworkEvent,
+    NetworkEvent::RequestPaused,
+    RequestPaused,
+    fetch::events::RequestPausedEvent
+);
+
+impl RequestPaused {
+    pub fn get_raw_parameters(&self) -> &fetch::events::RequestPausedParams {
+        &self.raw_event.params
+    }
+
+    pub fn get_request_id(&self) -> fetch::RequestId {
+        self.raw_event.params.request_id.clone()
+    }
+}
+
+wrapper_raw_event!(
+    TaskDescribe::NetworkEvent,
+    NetworkEvent::AuthRequired,
+    AuthRequired,
+    fetch::events::AuthRequiredEvent
+);
+
+impl AuthRequired {
+    pub fn into_raw_parameters(self) -> fetch::events::AuthRequiredParams {
+        self.raw_event.params
+    }
+}
+
 wrapper_raw_event!(
     TaskDescribe::NetworkEvent,
     NetworkEvent::RequestIntercepted,
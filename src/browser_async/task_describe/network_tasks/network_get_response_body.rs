@@ -0,0 +1,46 @@
+use super::super::{
+    AsMethodCallString, CanCreateMethodString, CommonDescribeFields, HasCommonField,
+    TargetCallMethodTask, TaskDescribe,
+};
+use crate::protocol::network;
+use failure;
+
+/// Gets the body of a request that finished loading, e.g. for the tab's `har_recorder`.
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct NetworkGetResponseBodyTask {
+    pub common_fields: CommonDescribeFields,
+    pub request_id: network::RequestId,
+    #[builder(default = "None")]
+    pub task_result: Option<network::methods::GetResponseBodyReturnObject>,
+}
+
+impl_has_common_fields!(NetworkGetResponseBodyTask, "NetworkGetResponseBodyTask");
+
+impl AsMethodCallString for NetworkGetResponseBodyTask {
+    fn get_method_str(&self) -> Result<String, failure::Error> {
+        let method = network::methods::GetResponseBody {
+            request_id: &self.request_id,
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl NetworkGetResponseBodyTask {
+    /// The body, decoded from base64 if it came that way.
+    pub fn get_body(&self) -> Result<Vec<u8>, failure::Error> {
+        match &self.task_result {
+            Some(task_result) if task_result.base64_encoded => {
+                Ok(base64::decode(&task_result.body)?)
+            }
+            Some(task_result) => Ok(task_result.body.clone().into_bytes()),
+            None => failure::bail!("no task_result: {:?}", self),
+        }
+    }
+}
+
+impl_into_task_describe!(
+    TaskDescribe::TargetCallMethod,
+    TargetCallMethodTask::NetworkGetResponseBody,
+    NetworkGetResponseBodyTask
+);
@@ -0,0 +1,30 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask, HasCommonField, CanCreateMethodString, };
+use crate::protocol::{page};
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct AddScriptToEvaluateOnNewDocumentTask {
+    pub common_fields: CommonDescribeFields,
+    pub source: String,
+    /// Set when the tab adds a script again after re-attaching: the identifier it was first
+    /// added under.
+    #[builder(default = "None")]
+    pub re_adding: Option<page::ScriptIdentifier>,
+    #[builder(default = "None")]
+    pub task_result: Option<page::ScriptIdentifier>,
+}
+
+impl_has_common_fields!(AddScriptToEvaluateOnNewDocumentTask, "AddScriptToEvaluateOnNewDocumentTask");
+
+impl AsMethodCallString for AddScriptToEvaluateOnNewDocumentTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let method = page::methods::AddScriptToEvaluateOnNewDocument {
+            source: &self.source,
+            world_name: None,
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::AddScriptToEvaluateOnNewDocument, AddScriptToEvaluateOnNewDocumentTask);
@@ -15,7 +15,7 @@ impl AsMethodCallString for AttachToTargetTask  {
     fn get_method_str(&self) -> Result<String, failure::Error> {
         let method = target::methods::AttachToTarget {
                 target_id: self.common_fields.target_id.as_ref().expect("target_id should exists in CommonDescribeFields."),
-                flatten: None,
+                flatten: Some(true),
             };
         Ok(create_msg_to_send(method, MethodDestination::Browser, self.get_call_id()))
     }
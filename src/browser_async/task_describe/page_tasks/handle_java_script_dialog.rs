@@ -0,0 +1,26 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask,  HasCommonField, CanCreateMethodString, };
+use crate::protocol::{page};
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct HandleJavaScriptDialogTask {
+    pub common_fields: CommonDescribeFields,
+    pub accept: bool,
+    #[builder(default = "None")]
+    pub prompt_text: Option<String>,
+}
+
+impl_has_common_fields!(HandleJavaScriptDialogTask, "HandleJavaScriptDialogTask");
+
+impl AsMethodCallString for HandleJavaScriptDialogTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let method = page::methods::HandleJavaScriptDialog {
+            accept: self.accept,
+            prompt_text: self.prompt_text.as_deref(),
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::HandleJavaScriptDialog, HandleJavaScriptDialogTask);
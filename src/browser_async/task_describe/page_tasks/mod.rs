@@ -10,6 +10,9 @@ pub mod get_layout_metrics;
 pub mod bring_to_front;
 pub mod attach_to_target;
 pub mod set_life_cycle_events_enable;
+pub mod handle_java_script_dialog;
+pub mod add_script_to_evaluate_on_new_document;
+pub mod remove_script_to_evaluate_on_new_document;
 
 use crate::browser_async::page_message::{PageResponseWrapper, PageResponse, ReceivedEvent,};
 pub use print_to_pdf::{PrintToPdfTask, PrintToPdfTaskBuilder};
@@ -22,6 +25,9 @@ pub use get_layout_metrics::{GetLayoutMetricsTask, GetLayoutMetricsTaskBuilder};
 pub use bring_to_front::{BringToFrontTask, BringToFrontTaskBuilder};
 pub use attach_to_target::{AttachToTargetTask, AttachToTargetTaskBuilder};
 pub use set_life_cycle_events_enable::{SetLifecycleEventsEnabledTask, SetLifecycleEventsEnabledTaskBuilder};
+pub use handle_java_script_dialog::{HandleJavaScriptDialogTask, HandleJavaScriptDialogTaskBuilder};
+pub use add_script_to_evaluate_on_new_document::{AddScriptToEvaluateOnNewDocumentTask, AddScriptToEvaluateOnNewDocumentTaskBuilder};
+pub use remove_script_to_evaluate_on_new_document::{RemoveScriptToEvaluateOnNewDocumentTask, RemoveScriptToEvaluateOnNewDocumentTaskBuilder};
 
 use super::super::protocol::{target};
 use super::super::EventName;
@@ -41,6 +47,7 @@ pub enum PageEvent {
     WindowOpen(page_events::WindowOpen),
     FrameRequestedNavigation(page_events::FrameRequestedNavigation),
     FrameResized(page_events::FrameResized),
+    JavascriptDialogOpening(page_events::JavascriptDialogOpening),
 }
 
 fn handle_event_return(
@@ -152,5 +159,15 @@ pub fn handle_page_event(
                 handle_event_return(maybe_target_id,
                  PageResponse::ReceivedEvent(ReceivedEvent::FrameResized(event)))
             }
+            PageEvent::JavascriptDialogOpening(event) => {
+                let params = event.into_raw_parameters();
+                let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
+                info!("{:?} dialog opened on {}: {:?}", params.dialog_type, tab, params.message);
+                if let Some(response) = tab.dialog_policy.respond(&params) {
+                    tab.handle_java_script_dialog(response.accept, response.prompt_text);
+                }
+                handle_event_return(maybe_target_id,
+                 PageResponse::ReceivedEvent(ReceivedEvent::JavascriptDialogOpening(params)))
+            }
         }
 }
\ No newline at end of file
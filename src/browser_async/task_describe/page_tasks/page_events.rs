@@ -19,6 +19,19 @@ wrapper_raw_event!(
     page::events::FrameResized
 );
 
+wrapper_raw_event!(
+    TaskDescribe::PageEvent,
+    PageEvent::JavascriptDialogOpening,
+    JavascriptDialogOpening,
+    page::events::JavascriptDialogOpeningEvent
+);
+
+impl JavascriptDialogOpening {
+    pub fn into_raw_parameters(self) -> page::events::JavascriptDialogOpeningParams {
+        self.raw_event.params
+    }
+}
+
 wrapper_raw_event!(
     TaskDescribe::PageEvent,
     PageEvent::FrameRequestedNavigation,
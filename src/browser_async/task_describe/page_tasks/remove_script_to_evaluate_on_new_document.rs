@@ -0,0 +1,23 @@
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, TargetCallMethodTask, HasCommonField, CanCreateMethodString, };
+use crate::protocol::{page};
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct RemoveScriptToEvaluateOnNewDocumentTask {
+    pub common_fields: CommonDescribeFields,
+    pub identifier: page::ScriptIdentifier,
+}
+
+impl_has_common_fields!(RemoveScriptToEvaluateOnNewDocumentTask, "RemoveScriptToEvaluateOnNewDocumentTask");
+
+impl AsMethodCallString for RemoveScriptToEvaluateOnNewDocumentTask {
+    fn get_method_str(&self) -> Result<String, failure::Error>{
+        let method = page::methods::RemoveScriptToEvaluateOnNewDocument {
+            identifier: &self.identifier,
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::TargetCallMethod, TargetCallMethodTask::RemoveScriptToEvaluateOnNewDocument, RemoveScriptToEvaluateOnNewDocumentTask);
@@ -0,0 +1,31 @@
+use super::super::{
+    AsMethodCallString, CanCreateMethodString, CommonDescribeFields, HasCommonField,
+    TargetCallMethodTask, TaskDescribe,
+};
+use crate::protocol::runtime;
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct AddBindingTask {
+    pub common_fields: CommonDescribeFields,
+    pub name: String,
+}
+
+impl_has_common_fields!(AddBindingTask, "AddBindingTask");
+
+impl AsMethodCallString for AddBindingTask {
+    fn get_method_str(&self) -> Result<String, failure::Error> {
+        let method = runtime::methods::AddBinding {
+            name: &self.name,
+            execution_context_name: None,
+        };
+        Ok(self.create_method_str(method))
+    }
+}
+
+impl_into_task_describe!(
+    TaskDescribe::TargetCallMethod,
+    TargetCallMethodTask::AddBinding,
+    AddBindingTask
+);
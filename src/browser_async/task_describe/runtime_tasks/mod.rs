@@ -1,15 +1,18 @@
 // place holder.
+pub mod add_binding;
 pub mod call_function_on;
 pub mod evaluate;
 pub mod get_properties;
 pub mod runtime_enable;
 pub mod runtime_events;
 
+pub use add_binding::{AddBindingTask, AddBindingTaskBuilder};
 pub use call_function_on::{CallFunctionOnTask, CallFunctionOnTaskBuilder};
 pub use evaluate::{EvaluateTask, EvaluateTaskBuilder};
 pub use get_properties::{GetPropertiesTask, GetPropertiesTaskBuilder};
 pub use runtime_enable::{RuntimeEnableTask, RuntimeEnableTaskBuilder};
 
+use crate::browser::tab::console::{ConsoleMessage, UncaughtException};
 use crate::browser_async::{DebugSession};
 use crate::browser_async::page_message::{PageResponse, PageResponseWrapper, ReceivedEvent};
 use crate::protocol::{target};
@@ -17,6 +20,7 @@ use log::*;
 
 #[derive(Debug)]
 pub enum RuntimeEvent {
+    BindingCalled(runtime_events::BindingCalled),
     ConsoleAPICalled(runtime_events::ConsoleAPICalled),
     ExceptionRevoked(runtime_events::ExceptionRevoked),
     ExceptionThrown(runtime_events::ExceptionThrown),
@@ -33,18 +37,36 @@ pub    fn handle_runtime_event(
         maybe_target_id: Option<target::TargetId>,
     ) -> Result<PageResponseWrapper, failure::Error> {
         match runtime_event {
+            RuntimeEvent::BindingCalled(event) => {
+                let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
+                tab.binding_called(&event.into_raw_parameters());
+                Ok(PageResponseWrapper::default())
+            }
             RuntimeEvent::ConsoleAPICalled(event) => {
                 let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
                 let console_call_parameters = event.into_raw_parameters();
                 tab.verify_execution_context_id(&console_call_parameters);
-                Ok(PageResponseWrapper::default())
+                let message = ConsoleMessage::from(console_call_parameters);
+                tab.console.push_message(message.clone());
+                Ok(PageResponseWrapper {
+                    target_id: maybe_target_id,
+                    task_id: None,
+                    page_response: PageResponse::ReceivedEvent(ReceivedEvent::ConsoleMessage(message)),
+                })
             }
             RuntimeEvent::ExceptionRevoked(_event) => {
                 Ok(PageResponseWrapper::default())
             }
             RuntimeEvent::ExceptionThrown(event) => {
-                warn!("ExceptionThrown: {:?}", event);
-                Ok(PageResponseWrapper::default())
+                let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
+                let exception = UncaughtException::from(event.into_raw_parameters());
+                warn!("uncaught exception in {}: {}", tab, exception.text);
+                tab.console.push_exception(exception.clone());
+                Ok(PageResponseWrapper {
+                    target_id: maybe_target_id,
+                    task_id: None,
+                    page_response: PageResponse::ReceivedEvent(ReceivedEvent::UncaughtException(exception)),
+                })
             }
             RuntimeEvent::ExecutionContextCreated(event) => {
                 let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
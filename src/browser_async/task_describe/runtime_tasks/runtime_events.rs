@@ -6,21 +6,44 @@ use crate::protocol::{runtime};
 #[derive(Debug)]
 pub struct ExceptionRevoked {}
 
-#[derive(Debug)]
-pub struct ExceptionThrown {}
+wrapper_raw_event!(
+    TaskDescribe::RuntimeEvent,
+    RuntimeEvent::ExceptionThrown,
+    ExceptionThrown,
+    runtime::events::ExceptionThrownEvent
+);
+
+impl ExceptionThrown {
+    pub fn into_raw_parameters(self) -> runtime::events::ExceptionThrownParams {
+        self.raw_event.params
+    }
+}
 
 wrapper_raw_event!(
     TaskDescribe::RuntimeEvent,
     RuntimeEvent::ConsoleAPICalled,
     ConsoleAPICalled,
-    embedded_events::ConsoleAPICalled
+    runtime::events::ConsoleAPICalledEvent
 );
 
 impl ConsoleAPICalled {
-    pub fn into_raw_parameters(self) -> embedded_events::ConsoleAPICalledParams {
+    pub fn into_raw_parameters(self) -> runtime::events::ConsoleAPICalledParams {
         self.raw_event.params
     }
 }
+wrapper_raw_event!(
+    TaskDescribe::RuntimeEvent,
+    RuntimeEvent::BindingCalled,
+    BindingCalled,
+    runtime::events::BindingCalledEvent
+);
+
+impl BindingCalled {
+    pub fn into_raw_parameters(self) -> runtime::events::BindingCalledParams {
+        self.raw_event.params
+    }
+}
+
 wrapper_raw_event!(
     TaskDescribe::RuntimeEvent,
     RuntimeEvent::ExecutionContextCreated,
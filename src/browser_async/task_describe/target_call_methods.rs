@@ -23,7 +23,11 @@ pub enum TargetCallMethodTask {
     PageClose(page_tasks::PageCloseTask),
     GetLayoutMetrics(page_tasks::GetLayoutMetricsTask),
     BringToFront(page_tasks::BringToFrontTask),
+    HandleJavaScriptDialog(page_tasks::HandleJavaScriptDialogTask),
+    AddScriptToEvaluateOnNewDocument(page_tasks::AddScriptToEvaluateOnNewDocumentTask),
+    RemoveScriptToEvaluateOnNewDocument(page_tasks::RemoveScriptToEvaluateOnNewDocumentTask),
     RuntimeEnable(runtime_tasks::RuntimeEnableTask),
+    AddBinding(runtime_tasks::AddBindingTask),
     CaptureScreenshot(page_tasks::CaptureScreenshotTask),
     Evaluate(runtime_tasks::EvaluateTask),
     GetProperties(runtime_tasks::GetPropertiesTask),
@@ -31,7 +35,14 @@ pub enum TargetCallMethodTask {
     NetworkEnable(network_tasks::NetworkEnableTask),
     SetRequestInterception(network_tasks::SetRequestInterceptionTask),
     ContinueInterceptedRequest(network_tasks::ContinueInterceptedRequestTask),
+    FetchEnable(network_tasks::FetchEnableTask),
+    ContinueRequest(network_tasks::ContinueRequestTask),
+    FulfillRequest(network_tasks::FulfillRequestTask),
+    FailRequest(network_tasks::FailRequestTask),
+    GetResponseBody(network_tasks::GetResponseBodyTask),
+    ContinueWithAuth(network_tasks::ContinueWithAuthTask),
     GetResponseBodyForInterception(network_tasks::GetResponseBodyForInterceptionTask),
+    NetworkGetResponseBody(network_tasks::NetworkGetResponseBodyTask),
     PageReload(page_tasks::PageReloadTask),
     DispatchMouseEvent(input_tasks::DispatchMouseEventTask),
     CanEmulate(emulation_tasks::CanEmulateTask),
@@ -160,10 +171,56 @@ pub fn handle_target_method_call(
             trace!("ignored method return. ContinueInterceptedRequest");
             Ok(PageResponseWrapper::default())
         }
+        TargetCallMethodTask::FetchEnable(_task) => {
+            trace!("ignored method return. FetchEnable");
+            Ok(PageResponseWrapper::default())
+        }
+        TargetCallMethodTask::ContinueRequest(_task) => {
+            trace!("ignored method return. ContinueRequest");
+            Ok(PageResponseWrapper::default())
+        }
+        TargetCallMethodTask::FulfillRequest(_task) => {
+            trace!("ignored method return. FulfillRequest");
+            Ok(PageResponseWrapper::default())
+        }
+        TargetCallMethodTask::FailRequest(_task) => {
+            trace!("ignored method return. FailRequest");
+            Ok(PageResponseWrapper::default())
+        }
+        TargetCallMethodTask::ContinueWithAuth(_task) => {
+            trace!("ignored method return. ContinueWithAuth");
+            Ok(PageResponseWrapper::default())
+        }
         TargetCallMethodTask::PageReload(_task) => {
             trace!("ignored method return. PageReload");
             Ok(PageResponseWrapper::default())
         }
+        TargetCallMethodTask::HandleJavaScriptDialog(_task) => {
+            trace!("ignored method return. HandleJavaScriptDialog");
+            Ok(PageResponseWrapper::default())
+        }
+        TargetCallMethodTask::AddScriptToEvaluateOnNewDocument(task) => {
+            let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
+            tab.script_on_new_document_added(&task);
+            if task.re_adding.is_some() {
+                return Ok(PageResponseWrapper::default());
+            }
+            Ok(PageResponseWrapper {
+                target_id: maybe_target_id,
+                task_id: Some(task.get_task_id()),
+                page_response: PageResponse::MethodCallDone(
+                    MethodCallDone::AddScriptToEvaluateOnNewDocument(task),
+                ),
+            })
+        }
+        TargetCallMethodTask::RemoveScriptToEvaluateOnNewDocument(_task) => {
+            trace!("ignored method return. RemoveScriptToEvaluateOnNewDocument");
+            Ok(PageResponseWrapper::default())
+        }
+        TargetCallMethodTask::AddBinding(_task) => {
+            trace!("ignored method return. AddBinding");
+            Ok(PageResponseWrapper::default())
+        }
         TargetCallMethodTask::SetLifecycleEventsEnabled(_task) => {
             trace!("ignored method return. SetLifecycleEventsEnabled");
             Ok(PageResponseWrapper::default())
@@ -192,6 +249,24 @@ pub fn handle_target_method_call(
                 MethodCallDone::GetResponseBodyForInterception(task),
             ),
         }),
+        TargetCallMethodTask::NetworkGetResponseBody(task) => {
+            let tab = debug_session.find_tab_by_id_mut(maybe_target_id.as_ref())?;
+            if let (Some(recorder), Some(body)) = (tab.har_recorder.as_mut(), &task.task_result) {
+                recorder.set_response_body(&task.request_id, body.clone());
+            }
+            Ok(PageResponseWrapper {
+                target_id: maybe_target_id,
+                task_id: Some(task.get_task_id()),
+                page_response: PageResponse::MethodCallDone(
+                    MethodCallDone::NetworkGetResponseBody(task),
+                ),
+            })
+        }
+        TargetCallMethodTask::GetResponseBody(task) => Ok(PageResponseWrapper {
+            target_id: maybe_target_id,
+            task_id: Some(task.get_task_id()),
+            page_response: PageResponse::MethodCallDone(MethodCallDone::GetResponseBody(task)),
+        }),
         TargetCallMethodTask::CanEmulate(task) => Ok(PageResponseWrapper {
             target_id: maybe_target_id,
             task_id: Some(task.get_task_id()),
@@ -0,0 +1,23 @@
+use crate::browser_async::{create_msg_to_send, MethodDestination};
+use super::super::{TaskDescribe, CommonDescribeFields, AsMethodCallString, BrowserCallMethodTask,  HasCommonField, HasCallId, };
+use crate::protocol::target;
+use failure;
+
+#[derive(Debug, Builder, Clone)]
+#[builder(setter(into))]
+pub struct CreateBrowserContextTask {
+    pub common_fields: CommonDescribeFields,
+    #[builder(default = "None")]
+    pub task_result: Option<target::BrowserContextID>,
+}
+
+impl_has_common_fields!(CreateBrowserContextTask, "CreateBrowserContextTask");
+
+impl AsMethodCallString for CreateBrowserContextTask {
+    fn get_method_str(&self) ->  Result<String, failure::Error> {
+        let method = target::methods::CreateBrowserContext {};
+        Ok(create_msg_to_send(method, MethodDestination::Browser, self.get_call_id()))
+    }
+}
+
+impl_into_task_describe!(TaskDescribe::BrowserCallMethod, BrowserCallMethodTask::CreateBrowserContext, CreateBrowserContextTask);
\ No newline at end of file
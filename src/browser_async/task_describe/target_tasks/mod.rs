@@ -1,4 +1,5 @@
 pub mod create_target;
+pub mod create_browser_context;
 pub mod set_discover_target_task;
 pub mod target_events;
 pub mod close_target;
@@ -6,6 +7,7 @@ pub mod activate_target;
 pub mod get_targets;
 
 pub use create_target::{CreateTargetTask, CreateTargetTaskBuilder};
+pub use create_browser_context::{CreateBrowserContextTask, CreateBrowserContextTaskBuilder};
 pub use close_target::{CloseTargetTask, CloseTargetTaskBuilder};
 pub use set_discover_target_task::{SetDiscoverTargetsTask, SetDiscoverTargetsTaskBuilder};
 pub use activate_target::{ActivateTargetTask, ActivateTargetTaskBuilder};
@@ -13,6 +15,7 @@ pub use get_targets::{GetTargetsTask, GetTargetsTaskBuilder};
 
 use super::super::{DebugSession, Tab};
 use super::super::page_message::{PageResponse, PageResponseWrapper, ReceivedEvent};
+use super::super::TargetLostReason;
 use super::super::super::protocol::{target};
 use log::*;
 use std::sync::Arc;
@@ -27,6 +30,7 @@ pub enum TargetEvent {
     TargetDestroyed(target_events::TargetDestroyed),
     TargetInfoChanged(target_events::TargetInfoChanged),
     AttachedToTarget(target_events::AttachedToTarget),
+    DetachedFromTarget(target_events::DetachedFromTarget),
 }
 
 pub  fn handle_target_event(
@@ -58,8 +62,21 @@ pub  fn handle_target_event(
                 }
             }
             TargetEvent::TargetCrashed(event) => {
-                warn!("unhandled TargetCrashed: {:?}", event);
-                Ok(PageResponseWrapper::default())
+                warn!("target crashed: {:?}", event);
+                Ok(target_lost(debug_session, event.get_target_id(), event.to_reason()))
+            }
+            TargetEvent::DetachedFromTarget(event) => {
+                let target_id = event.get_target_id();
+                let closing = debug_session
+                    .find_tab_by_id_mut(Some(target_id))
+                    .map_or(true, |tab| tab.explicitly_close || tab.closing.is_closing());
+                if closing {
+                    trace!("detached from target: {:?}", event);
+                    Ok(PageResponseWrapper::default())
+                } else {
+                    warn!("detached from target unexpectedly: {:?}", event);
+                    Ok(target_lost(debug_session, target_id, TargetLostReason::Detached))
+                }
             }
             TargetEvent::AttachedToTarget(event) => {
                 if event.is_page_attached() {
@@ -98,4 +115,27 @@ pub  fn handle_target_event(
                 Ok(PageResponseWrapper::default())
             }
         }
-    }
\ No newline at end of file
+    }
+
+/// Marks the tab as lost, once; it crashing also gets it detached, which isn't news then.
+fn target_lost(
+    debug_session: &mut DebugSession,
+    target_id: &target::TargetId,
+    reason: TargetLostReason,
+) -> PageResponseWrapper {
+    match debug_session.find_tab_by_id_mut(Some(target_id)) {
+        Ok(tab) if tab.lost.is_none() => {
+            tab.lost = Some(reason.clone());
+            PageResponseWrapper {
+                target_id: Some(target_id.clone()),
+                task_id: None,
+                page_response: PageResponse::TargetLost(reason),
+            }
+        }
+        Ok(_) => PageResponseWrapper::default(),
+        Err(_) => {
+            warn!("target lost, no correspond tab. {:?}", target_id);
+            PageResponseWrapper::default()
+        }
+    }
+}
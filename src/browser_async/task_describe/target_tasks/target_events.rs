@@ -2,6 +2,7 @@ use super::TargetEvent;
 use super::super::TaskDescribe;
 use crate::protocol::{self, target};
 use crate::browser_async::page_message::{PageResponse, PageResponseWrapper, ReceivedEvent,};
+use crate::browser_async::TargetLostReason;
 
 #[derive(Debug)]
 pub struct ReceivedMessageFromTarget {}
@@ -24,8 +25,62 @@ impl TargetCreated {
     }
 }
 
+/// A page's renderer crashed. Chrome tells about it twice: browser-wide with
+/// `Target.targetCrashed`, and on the page's own session with `Inspector.targetCrashed`.
 #[derive(Debug)]
-pub struct TargetCrashed {}
+pub struct TargetCrashed {
+    pub target_id: target::TargetId,
+    pub status: Option<String>,
+    pub error_code: Option<i64>,
+}
+
+impl TargetCrashed {
+    pub fn new(raw_event: target::events::TargetCrashedEvent) -> Self {
+        Self {
+            target_id: raw_event.params.target_id,
+            status: Some(raw_event.params.status),
+            error_code: Some(raw_event.params.error_code),
+        }
+    }
+
+    pub fn inspected(target_id: target::TargetId) -> Self {
+        Self {
+            target_id,
+            status: None,
+            error_code: None,
+        }
+    }
+
+    pub fn get_target_id(&self) -> &target::TargetId {
+        &self.target_id
+    }
+
+    pub fn to_reason(&self) -> TargetLostReason {
+        TargetLostReason::Crashed {
+            status: self.status.clone(),
+            error_code: self.error_code,
+        }
+    }
+}
+
+impl std::convert::From<TargetCrashed> for TaskDescribe {
+    fn from(event: TargetCrashed) -> Self {
+        TaskDescribe::TargetEvent(TargetEvent::TargetCrashed(event))
+    }
+}
+
+wrapper_raw_event!(
+    TaskDescribe::TargetEvent,
+    TargetEvent::DetachedFromTarget,
+    DetachedFromTarget,
+    target::events::DetachedFromTarget
+);
+
+impl DetachedFromTarget {
+    pub fn get_target_id(&self) -> &target::TargetId {
+        &self.raw_event.params.target_id
+    }
+}
 
 // {\"method\":\"Target.attachedToTarget\",\"params\":{\"sessionId\":\"1B34295E2E49181EC18E08C21FD08148\",\"targetInfo\":{\"targetId\":\"74FEEFE9CACC814F52F89930129A15ED\",\"type\":\"page\",\"title\":\"\",\"url\":\"about:blank\",\"attached\":true,\"browserContextId\":\"6CEFE43CB35F53A22DB4009118D8978C\"},\"waitingForDebugger\":false}}
 wrapper_raw_event!(
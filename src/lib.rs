@@ -27,6 +27,9 @@ pub mod util;
 
 pub mod browser_async;
 
+#[cfg(feature = "testing")]
+pub mod testing;
+
 
 pub use browser::{Browser, LaunchOptionsBuilder, Tab};
 
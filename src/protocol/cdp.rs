@@ -0,0 +1,31 @@
+//! Bindings for every domain of the DevTools protocol, generated by `build.rs` from the
+//! protocol JSON in `json/` when the "cdp" feature is on.
+//!
+//! Each domain is a module of its types, with `methods` (a struct implementing `Method` per
+//! command, and its `...ReturnObject`) and `events` submodules, like the hand-written modules
+//! alongside this one. Events this crate doesn't model itself decode into
+//! `protocol::Event::Protocol`, holding an `Event` from here.
+//!
+//! ```rust,no_run
+//! # use failure::Error;
+//! # fn main() -> Result<(), Error> {
+//! use headless_chrome::protocol::cdp::dom_storage;
+//! use headless_chrome::{Browser, LaunchOptionsBuilder};
+//!
+//! let browser = Browser::new(LaunchOptionsBuilder::default().build().unwrap())?;
+//! let tab = browser.wait_for_initial_tab()?;
+//! tab.call_method(dom_storage::methods::Enable {})?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![allow(
+    clippy::all,
+    clippy::pedantic,
+    deprecated,
+    missing_docs,
+    non_camel_case_types,
+    non_snake_case
+)]
+
+include!(concat!(env!("OUT_DIR"), "/cdp.rs"));
@@ -0,0 +1,200 @@
+use crate::protocol::{network, page};
+use serde::{Deserialize, Serialize};
+
+/// Identifies a request paused by `Fetch.requestPaused`. It's different from the request's
+/// `network::RequestId`, which comes as the event's `network_id`.
+pub type RequestId = String;
+
+/// Whether a request is paused before it's sent, or once its response headers arrived.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RequestStage {
+    Request,
+    Response,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestPattern {
+    /// Wildcards ('*' -> zero or more, '?' -> exactly one) are allowed.
+    /// Escape character is backslash. Omitting is equivalent to "*".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_type: Option<network::ResourceType>,
+    /// Omitting is equivalent to `Request`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_stage: Option<RequestStage>,
+}
+
+/// A header as the Fetch domain lists them: unlike `network::Headers`, the same name can come
+/// more than once.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HeaderEntry {
+    pub name: String,
+    pub value: String,
+}
+
+impl HeaderEntry {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct RequestPausedEvent {
+        pub params: RequestPausedParams,
+    }
+
+    /// A request matching one of the patterns passed to `Fetch.enable`, held until it's
+    /// answered with `ContinueRequest`, `FulfillRequest` or `FailRequest`. The response fields
+    /// are only set at the `Response` stage.
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestPausedParams {
+        pub request_id: RequestId,
+        pub request: network::Request,
+        pub frame_id: page::FrameId,
+        pub resource_type: network::ResourceType,
+        pub response_error_reason: Option<network::ErrorReason>,
+        pub response_status_code: Option<u32>,
+        pub response_headers: Option<Vec<HeaderEntry>>,
+        pub network_id: Option<network::RequestId>,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct AuthRequiredEvent {
+        pub params: AuthRequiredParams,
+    }
+
+    /// A request held up by an HTTP authentication challenge, until it's answered with
+    /// `ContinueWithAuth`. Only comes if `Fetch.enable` was called with `handle_auth_requests`.
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AuthRequiredParams {
+        pub request_id: RequestId,
+        pub request: network::Request,
+        pub frame_id: page::FrameId,
+        pub resource_type: network::ResourceType,
+        pub auth_challenge: network::events::AuthChallenge,
+    }
+
+    impl RequestPausedParams {
+        /// Whether the request was paused once its response came (or failed to), rather than
+        /// before it was sent.
+        pub fn is_response_stage(&self) -> bool {
+            self.response_status_code.is_some() || self.response_error_reason.is_some()
+        }
+    }
+}
+
+pub mod methods {
+    use super::*;
+    use crate::protocol::{EmptyReturnObject, Method};
+
+    /// Starts pausing the requests matching `patterns`, or all of them if it's `None`.
+    #[derive(Serialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Enable<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub patterns: Option<&'a [RequestPattern]>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub handle_auth_requests: Option<bool>,
+    }
+    impl Method for Enable<'_> {
+        const NAME: &'static str = "Fetch.enable";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    #[derive(Serialize, Debug)]
+    pub struct Disable {}
+    impl Method for Disable {
+        const NAME: &'static str = "Fetch.disable";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    /// Lets a paused request go on, changed by whichever of the fields are set.
+    #[derive(Serialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ContinueRequest<'a> {
+        pub request_id: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub url: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub method: Option<&'a str>,
+        /// Base64 encoded.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub post_data: Option<&'a str>,
+        /// Replaces all of the request's headers.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub headers: Option<&'a [HeaderEntry]>,
+    }
+    impl Method for ContinueRequest<'_> {
+        const NAME: &'static str = "Fetch.continueRequest";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    /// Answers a paused request with a response of our own.
+    #[derive(Serialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct FulfillRequest<'a> {
+        pub request_id: &'a str,
+        pub response_code: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub response_headers: Option<&'a [HeaderEntry]>,
+        /// Base64 encoded.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub body: Option<&'a str>,
+        /// Defaults to the standard phrase for `response_code`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub response_phrase: Option<&'a str>,
+    }
+    impl Method for FulfillRequest<'_> {
+        const NAME: &'static str = "Fetch.fulfillRequest";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct FailRequest<'a> {
+        pub request_id: &'a str,
+        pub error_reason: network::ErrorReason,
+    }
+    impl Method for FailRequest<'_> {
+        const NAME: &'static str = "Fetch.failRequest";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ContinueWithAuth<'a> {
+        pub request_id: &'a str,
+        pub auth_challenge_response: network::methods::AuthChallengeResponse<'a>,
+    }
+    impl Method for ContinueWithAuth<'_> {
+        const NAME: &'static str = "Fetch.continueWithAuth";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    /// The body of a request paused at the `Response` stage.
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetResponseBody<'a> {
+        pub request_id: &'a str,
+    }
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetResponseBodyReturnObject {
+        pub body: String,
+        pub base64_encoded: bool,
+    }
+    impl Method for GetResponseBody<'_> {
+        const NAME: &'static str = "Fetch.getResponseBody";
+        type ReturnObject = GetResponseBodyReturnObject;
+    }
+}
@@ -0,0 +1,7 @@
+pub mod events {
+    use serde::Deserialize;
+
+    /// Sent to a target's own session when its renderer crashes.
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct TargetCrashedEvent {}
+}
@@ -1,21 +1,28 @@
 //! For (de)serializing method calls and events from the Chrome DevTools Protocol.
 
 use failure::{Error, Fail};
+use log::*;
 use serde;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::fmt::Debug;
 
 pub mod browser;
+#[cfg(feature = "cdp")]
+pub mod cdp;
 pub mod debugger;
 pub mod dom;
+pub mod fetch;
 pub mod input;
+pub mod inspector;
 pub mod network;
 pub mod page;
 pub mod profiler;
 pub mod runtime;
 pub mod target;
 pub mod security;
+pub mod storage;
 pub mod emulation;
 pub mod chrome_log;
 
@@ -36,6 +43,8 @@ where
     method_name: &'static str,
     pub id: CallId,
     params: T,
+    #[serde(rename = "sessionId", skip_serializing_if = "Option::is_none")]
+    session_id: Option<target::SessionID>,
 }
 
 impl<T> MethodCall<T>
@@ -60,6 +69,19 @@ pub trait Method: Debug {
             id: call_id,
             params: self,
             method_name: Self::NAME,
+            session_id: None,
+        }
+    }
+
+    /// Like `to_method_call`, but addressed to a target attached with `flatten: true`: the
+    /// call carries the session ID itself instead of going through `Target.sendMessageToTarget`.
+    fn to_session_method_call(self, call_id: CallId, session_id: &str) -> MethodCall<Self>
+    where
+        Self: std::marker::Sized,
+    {
+        MethodCall {
+            session_id: Some(session_id.to_string()),
+            ..self.to_method_call(call_id)
         }
     }
 }
@@ -77,6 +99,9 @@ pub struct Response {
     pub call_id: CallId,
     pub result: Option<Value>,
     pub error: Option<RemoteError>,
+    /// Set when the call was made on a flattened session.
+    #[serde(rename(deserialize = "sessionId"), default)]
+    pub session_id: Option<target::SessionID>,
 }
 
 pub fn parse_response<T>(response: Response) -> Result<T, Error>
@@ -92,44 +117,206 @@ where
     Ok(result)
 }
 
+/// Declares `Event`, along with a strict decoder for the methods it has variants for: serde
+/// falls back to the untagged variants when one of those doesn't decode, without saying why.
+macro_rules! event_enum {
+    ($($method:literal => $variant:ident($event:ty),)*) => {
+        #[derive(Deserialize, Debug, Clone)]
+        #[serde(tag = "method")]
+        #[allow(clippy::large_enum_variant)]
+        pub enum Event {
+            $(
+                #[serde(rename = $method)]
+                $variant($event),
+            )*
+            /// Any other event, decoded with the generated bindings.
+            #[cfg(feature = "cdp")]
+            #[serde(untagged)]
+            Protocol(cdp::Event),
+            /// Any event not covered above, or that failed to decode as the variant for its
+            /// method.
+            #[serde(untagged)]
+            Raw(RawEvent),
+        }
+
+        $(
+            impl FromEvent for $event {
+                fn from_event(event: &Event) -> Option<Self> {
+                    match event {
+                        Event::$variant(event) => Some(event.clone()),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+
+        impl Event {
+            /// `None` if `Event` has no variant of its own for `method`.
+            fn decode_known(
+                method: &str,
+                params: &Value,
+            ) -> Option<Result<Self, serde_json::Error>> {
+                match method {
+                    $(
+                        $method => Some(
+                            serde_json::from_value::<$event>(json!({ "params": params }))
+                                .map(Event::$variant),
+                        ),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+event_enum! {
+    "Target.attachedToTarget" => AttachedToTarget(target::events::AttachedToTargetEvent),
+    "Target.receivedMessageFromTarget" =>
+        ReceivedMessageFromTarget(target::events::ReceivedMessageFromTargetEvent),
+    "Target.targetInfoChanged" => TargetInfoChanged(target::events::TargetInfoChangedEvent),
+    "Target.targetCreated" => TargetCreated(target::events::TargetCreatedEvent),
+    "Target.targetDestroyed" => TargetDestroyed(target::events::TargetDestroyedEvent),
+    "Target.detachedFromTarget" => DetachedFromTarget(target::events::DetachedFromTarget),
+    "Target.targetCrashed" => TargetCrashed(target::events::TargetCrashedEvent),
+    "Inspector.targetCrashed" => InspectorTargetCrashed(inspector::events::TargetCrashedEvent),
+    "Page.frameStartedLoading" => FrameStartedLoading(page::events::FrameStartedLoadingEvent),
+    "Page.frameNavigated" => FrameNavigated(page::events::FrameNavigatedEvent),
+    "Page.frameDetached" => FrameDetached(page::events::FrameDetachedEvent),
+    "Page.frameAttached" => FrameAttached(page::events::FrameAttachedEvent),
+    "Page.frameStoppedLoading" => FrameStoppedLoading(page::events::FrameStoppedLoadingEvent),
+    "Page.lifecycleEvent" => Lifecycle(page::events::LifecycleEvent),
+    "Network.requestIntercepted" => RequestIntercepted(network::events::RequestInterceptedEvent),
+    "Network.requestWillBeSent" => RequestWillBeSent(network::events::RequestWillBeSent),
+    "Network.responseReceived" => ResponseReceived(network::events::ResponseReceived),
+    "Network.dataReceived" => DataReceived(network::events::DataReceived),
+    "Network.loadingFinished" => LoadingFinished(network::events::LoadingFinished),
+    "Network.loadingFailed" => LoadingFailed(network::events::LoadingFailed),
+    "Fetch.requestPaused" => RequestPaused(fetch::events::RequestPausedEvent),
+    "Fetch.authRequired" => AuthRequired(fetch::events::AuthRequiredEvent),
+    "Security.certificateError" => SecurityCertificateError(security::events::CertificateError),
+    "Runtime.consoleAPICalled" => ConsoleAPICalled(runtime::events::ConsoleAPICalledEvent),
+    "Runtime.exceptionThrown" => ExceptionThrown(runtime::events::ExceptionThrownEvent),
+    "Runtime.bindingCalled" => BindingCalled(runtime::events::BindingCalledEvent),
+    "Page.javascriptDialogOpening" =>
+        JavascriptDialogOpening(page::events::JavascriptDialogOpeningEvent),
+}
+
+/// An event type that can be picked out of an `Event`, as `Tab::wait_for_event` does. The ones
+/// generated in `cdp` only come out of `Event::Protocol`, i.e. for methods `Event` doesn't have
+/// a variant of its own for.
+pub trait FromEvent: Sized {
+    fn from_event(event: &Event) -> Option<Self>;
+}
+
+impl FromEvent for Event {
+    fn from_event(event: &Event) -> Option<Self> {
+        Some(event.clone())
+    }
+}
+
+impl FromEvent for RawEvent {
+    fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::Raw(raw_event) => Some(raw_event.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// An event kept as JSON, because `Event` doesn't model it or because it couldn't be decoded
+/// as the variant that does.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RawEvent {
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    /// Set for events from a target attached with `flatten: true`.
+    #[serde(default)]
+    pub session_id: Option<target::SessionID>,
+    /// Why the event couldn't be decoded, if `Event` (or whichever enum it was last decoded
+    /// into) has a variant for its method.
+    #[serde(skip)]
+    pub decode_error: Option<String>,
+}
+
+impl RawEvent {
+    /// Decodes the event into `Event` or any other enum tagged by "method".
+    pub fn decode<T>(&self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let event = json!({"method": self.method, "params": self.params});
+        Ok(serde_json::from_value(event)?)
+    }
+
+    /// Decodes the event into the `Event` for its method. If there isn't one, it stays raw;
+    /// if it doesn't decode, it stays raw with a `decode_error`, and the JSON gets logged.
+    pub fn into_event(self) -> Event {
+        match Event::decode_known(&self.method, &self.params) {
+            Some(Ok(event)) => event,
+            Some(Err(err)) => Event::Raw(self.undecodable(&err)),
+            None => match self.decode::<Event>() {
+                Ok(Event::Raw(_)) | Err(_) => Event::Raw(self),
+                Ok(event) => event,
+            },
+        }
+    }
+
+    /// Records that the event couldn't be decoded even though its method is known.
+    pub fn undecodable(self, err: &dyn std::fmt::Display) -> Self {
+        warn!(
+            "Couldn't decode {} event: {}\nparams: {}",
+            self.method, err, self.params
+        );
+        Self {
+            decode_error: Some(err.to_string()),
+            ..self
+        }
+    }
+}
+
+/// An event from a target attached with `flatten: true`, which carries its session ID at the
+/// top level. It's left undecoded until it has been routed to whoever listens to that session,
+/// since there are more kinds of target events than `Event` covers.
 #[derive(Deserialize, Debug, Clone)]
-#[serde(tag = "method")]
-#[allow(clippy::large_enum_variant)]
-pub enum Event {
-    #[serde(rename = "Target.attachedToTarget")]
-    AttachedToTarget(target::events::AttachedToTargetEvent),
-    #[serde(rename = "Target.receivedMessageFromTarget")]
-    ReceivedMessageFromTarget(target::events::ReceivedMessageFromTargetEvent),
-    #[serde(rename = "Target.targetInfoChanged")]
-    TargetInfoChanged(target::events::TargetInfoChangedEvent),
-    #[serde(rename = "Target.targetCreated")]
-    TargetCreated(target::events::TargetCreatedEvent),
-    #[serde(rename = "Target.targetDestroyed")]
-    TargetDestroyed(target::events::TargetDestroyedEvent),
-    #[serde(rename = "Target.detachedFromTarget")]
-    DetachedFromTarget(target::events::DetachedFromTarget),
-    #[serde(rename = "Page.frameStartedLoading")]
-    FrameStartedLoading(page::events::FrameStartedLoadingEvent),
-    #[serde(rename = "Page.frameNavigated")]
-    FrameNavigated(page::events::FrameNavigatedEvent),
-    #[serde(rename = "Page.frameDetached")]
-    FrameDetached(page::events::FrameDetachedEvent),
-    #[serde(rename = "Page.frameAttached")]
-    FrameAttached(page::events::FrameAttachedEvent),
-    #[serde(rename = "Page.frameStoppedLoading")]
-    FrameStoppedLoading(page::events::FrameStoppedLoadingEvent),
-    #[serde(rename = "Page.lifecycleEvent")]
-    Lifecycle(page::events::LifecycleEvent),
-    #[serde(rename = "Network.requestIntercepted")]
-    RequestIntercepted(network::events::RequestInterceptedEvent),
-    #[serde(rename = "Security.certificateError")]
-    SecurityCertificateError(security::events::CertificateError),
+#[serde(rename_all = "camelCase")]
+pub struct SessionEvent {
+    pub session_id: target::SessionID,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl SessionEvent {
+    /// Decodes the event into `Event` or any other enum tagged by "method".
+    pub fn decode<T>(&self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let event = json!({"method": self.method, "params": self.params});
+        Ok(serde_json::from_value(event)?)
+    }
+
+    /// Decodes the event into the `Event` for its method, falling back to `Event::Raw` the way
+    /// `RawEvent::into_event` does.
+    pub fn into_event(self) -> Event {
+        RawEvent {
+            method: self.method,
+            params: self.params,
+            session_id: Some(self.session_id),
+            decode_error: None,
+        }
+        .into_event()
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
 #[allow(clippy::large_enum_variant)]
 pub enum Message {
+    SessionEvent(SessionEvent),
     Event(Event),
     Response(Response),
     ConnectionShutdown,
@@ -228,6 +415,131 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_flattened_session_messages() {
+        let event = parse_raw_message(
+            r#"{"method":"Page.lifecycleEvent","params":{"frameId":"F1","loaderId":"L1","name":"init","timestamp":1.0},"sessionId":"S1"}"#,
+        )
+        .unwrap();
+        match event {
+            Message::SessionEvent(session_event) => {
+                assert_eq!("S1", session_event.session_id);
+                match session_event.decode::<Event>().unwrap() {
+                    Event::Lifecycle(lifecycle) => assert_eq!("init", lifecycle.params.name),
+                    other => panic!("expected a lifecycle event, got {:?}", other),
+                }
+            }
+            other => panic!("expected a session event, got {:?}", other),
+        }
+
+        let response = parse_raw_message(r#"{"id":3,"result":{},"sessionId":"S1"}"#).unwrap();
+        match response {
+            Message::Response(response) => {
+                assert_eq!(3, response.call_id);
+                assert_eq!(Some("S1".to_string()), response.session_id);
+            }
+            other => panic!("expected a response, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "cdp")]
+    #[test]
+    fn generated_bindings() {
+        let call = cdp::page::methods::Navigate {
+            url: "https://example.com".to_string(),
+            referrer: None,
+            transition_type: Some(cdp::page::TransitionType::AutoToplevel),
+            frame_id: None,
+            referrer_policy: None,
+        }
+        .to_method_call(7);
+        assert_eq!(
+            json!({
+                "method": "Page.navigate",
+                "id": 7,
+                "params": {"url": "https://example.com", "transitionType": "auto_toplevel"}
+            }),
+            serde_json::to_value(&call).unwrap()
+        );
+
+        let returned: cdp::page::methods::NavigateReturnObject =
+            parse_response(serde_json::from_str(r#"{"id":7,"result":{"frameId":"F1"}}"#).unwrap())
+                .unwrap();
+        assert_eq!("F1", returned.frame_id);
+
+        // an event this crate doesn't model itself
+        let message = parse_raw_message(
+            r#"{"method":"Log.entryAdded","params":{"entry":{"source":"network","level":"error","text":"hi","timestamp":1.5}}}"#,
+        )
+        .unwrap();
+        match message {
+            Message::Event(Event::Protocol(cdp::Event::LogEntryAdded(event))) => {
+                assert_eq!(cdp::log::LogEntryLevel::Error, event.params.entry.level);
+                assert_eq!("hi", event.params.entry.text);
+            }
+            other => panic!("expected a log event, got {:?}", other),
+        }
+
+        // the hand-written events still take precedence
+        let message = parse_raw_message(
+            r#"{"method":"Page.frameStartedLoading","params":{"frameId":"F1"}}"#,
+        )
+        .unwrap();
+        match message {
+            Message::Event(Event::FrameStartedLoading(_)) => {}
+            other => panic!("expected a hand-written event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_and_undecodable_events_raw() {
+        let message =
+            parse_raw_message(r#"{"method":"Custom.somethingHappened","params":{"id":"A1"}}"#)
+                .unwrap();
+        match message {
+            Message::Event(Event::Raw(raw_event)) => {
+                assert_eq!("Custom.somethingHappened", raw_event.method);
+                assert_eq!(json!({"id": "A1"}), raw_event.params);
+                assert_eq!(None, raw_event.decode_error);
+            }
+            other => panic!("expected a raw event, got {:?}", other),
+        }
+
+        let message =
+            parse_raw_message(r#"{"method":"Page.frameStartedLoading","params":{"frame":1}}"#)
+                .unwrap();
+        match message {
+            Message::Event(Event::Raw(raw_event)) => {
+                assert_eq!(json!({"frame": 1}), raw_event.params);
+                assert!(raw_event.decode_error.unwrap().contains("frameId"));
+            }
+            other => panic!("expected a raw event, got {:?}", other),
+        }
+
+        // decodes with the generated bindings, but not as `Event::ExceptionThrown`
+        let message = parse_raw_message(
+            r#"{"method":"Runtime.exceptionThrown","params":{"timestamp":1.5,"exceptionDetails":{"exceptionId":1,"text":"Uncaught","lineNumber":-1,"columnNumber":0}}}"#,
+        )
+        .unwrap();
+        match message {
+            Message::Event(Event::Raw(raw_event)) => {
+                assert_eq!("Runtime.exceptionThrown", raw_event.method);
+                assert!(raw_event.decode_error.is_some());
+            }
+            other => panic!("expected a raw event, got {:?}", other),
+        }
+
+        let session_event = SessionEvent {
+            session_id: "S1".to_string(),
+            method: "Custom.somethingHappened".to_string(),
+            params: json!({"id": "A1"}),
+        };
+        match session_event.into_event() {
+            Event::Raw(raw_event) => assert_eq!(Some("S1".to_string()), raw_event.session_id),
+            other => panic!("expected a raw event, got {:?}", other),
+        }
+    }
+
     #[test]
     fn parse_security_certificate_error() {
         let message = "{\"method\":\"Security.certificateError\",\"params\":{\"eventId\":1,\"errorType\":\"ERR_CERT_AUTHORITY_INVALID\",\"requestURL\":\"https://59.202.58.131/\"}}";
@@ -243,6 +555,18 @@ mod tests {
     }
 }
 
+/// Parses a message from Chrome. An event that `Event` has a variant for but that doesn't
+/// decode still comes out, as an `Event::Raw` with a `decode_error`.
 pub fn parse_raw_message(raw_message: &str) -> Result<Message, Error> {
-    Ok(serde_json::from_str::<Message>(raw_message)?)
+    match serde_json::from_str::<Message>(raw_message)? {
+        Message::Event(Event::Raw(raw_event)) => Ok(Message::Event(raw_event.into_event())),
+        // serde also falls back to the generated bindings when a known event doesn't decode;
+        // that event is undecodable all the same, as it is without `cdp`.
+        #[cfg(feature = "cdp")]
+        Message::Event(Event::Protocol(_)) => {
+            let raw_event: RawEvent = serde_json::from_str(raw_message)?;
+            Ok(Message::Event(raw_event.into_event()))
+        }
+        message => Ok(message),
+    }
 }
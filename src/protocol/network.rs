@@ -4,9 +4,9 @@ use std::collections::HashMap;
 use crate::protocol::{runtime, security};
 
     pub type LoaderId = String;
-    pub type MonotonicTime = f32;
+    pub type MonotonicTime = f64;
     pub type RequestId = String;
-    pub type TimeSinceEpoch = f32;
+    pub type TimeSinceEpoch = f64;
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     pub enum InterceptionStage {
@@ -30,6 +30,25 @@ pub enum BlockedReason {
      ContentYype,
      CollapsedByClient,
 }
+/// Why a request failed, or should be failed, e.g. by `fetch::methods::FailRequest`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ErrorReason {
+    Failed,
+    Aborted,
+    TimedOut,
+    AccessDenied,
+    ConnectionClosed,
+    ConnectionReset,
+    ConnectionRefused,
+    ConnectionAborted,
+    ConnectionFailed,
+    NameNotResolved,
+    InternetDisconnected,
+    AddressUnreachable,
+    BlockedByClient,
+    BlockedByResponse,
+}
+
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     pub enum ResourceType {
         Document,
@@ -46,6 +65,7 @@ pub enum BlockedReason {
             SignedExchange, 
             Ping,
              CSPViolationReport,
+              #[serde(other)]
               Other
     }
 
@@ -77,7 +97,8 @@ pub enum BlockedReason {
     #[derive(Deserialize, Clone, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct ResourceTiming {
-        pub request_time: f32,
+        /// Seconds, the other fields being milliseconds relative to it.
+        pub request_time: MonotonicTime,
         pub proxy_start: f32,
         pub proxy_end: f32,
         pub dns_start: f32,
@@ -191,6 +212,84 @@ pub struct Request {
     pub is_link_preload: Option<bool>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// A cookie as Chrome reports it, e.g. from `methods::GetCookies`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    /// Seconds since the epoch; -1 for session cookies.
+    pub expires: TimeSinceEpoch,
+    pub size: u32,
+    pub http_only: bool,
+    pub secure: bool,
+    pub session: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<CookieSameSite>,
+}
+
+/// A cookie to set, e.g. with `methods::SetCookies`. Either `url` or `domain` is needed: with
+/// `url`, the cookie is only sent back to that host, with `domain` to its subdomains as well.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CookieParam {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<CookieSameSite>,
+    /// Seconds since the epoch; a session cookie if it's left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<TimeSinceEpoch>,
+}
+
+/// Host-only cookies (whose domain doesn't start with a dot) are set by `url`, so that they stay
+/// host-only.
+impl From<Cookie> for CookieParam {
+    fn from(cookie: Cookie) -> Self {
+        let (url, domain) = if cookie.domain.starts_with('.') {
+            (None, Some(cookie.domain))
+        } else {
+            let scheme = if cookie.secure { "https" } else { "http" };
+            let url = format!("{}://{}{}", scheme, cookie.domain, cookie.path);
+            (Some(url), None)
+        };
+        Self {
+            name: cookie.name,
+            value: cookie.value,
+            url,
+            domain,
+            path: Some(cookie.path),
+            secure: Some(cookie.secure),
+            http_only: Some(cookie.http_only),
+            same_site: cookie.same_site,
+            expires: if cookie.session {
+                None
+            } else {
+                Some(cookie.expires)
+            },
+        }
+    }
+}
+
 pub mod events {
     use serde::{Deserialize, Serialize};
 
@@ -231,6 +330,134 @@ pub mod events {
         pub params: RequestInterceptedEventParams,
     }
 
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestWillBeSent {
+        pub params: RequestWillBeSentParams,
+    }
+
+    // "{\"method\":\"Network.requestWillBeSent\",\"params\":{\"requestId\":\"1000022340.129\",
+    // \"loaderId\":\"B437570341CB0E65C27EB311E43BD1C4\",\"documentURL\":\"https://59.202.58.131/home\",
+    // \"request\":{\"url\":\"https://59.202.58.131/api/league/manager/list?_=1558774807951\",\"method\":\"GET\",\"headers\":{\"Accept\":\"*/*\",\"Referer\":\"https://59.202.58.131/home\",\"X-Requested-With\":\"XMLHttpRequest\",\"csrftoken\":\"d6422cb0-3f0f-4019-a73c-582a0b09ee15\",\"User-Agent\":\"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/74.0.3729.169 Safari/537.36\",\"Content-Type\":\"application/json;charset:utf-8\"},\"mixedContentType\":\"none\",\"initialPriority\":\"High\",\"referrerPolicy\":\"no-referrer-when-downgrade\"},
+    // \"timestamp\":602350.487155,\"wallTime\":1558774808.11041,
+    // \"initiator\":{\"type\":\"script\",\"stack\":{\"callFrames\":[{\"functionName\":\"send\",\"scriptId\":\"42\",\"url\":\"https://59.202.58.131/assets/scripts/vendor-daf58f8629.js\",\"lineNumber\":84,\"columnNumber\":3511}]}},
+    // \"type\":\"XHR\",\"frameId\":\"D8ACD4C37323FA44FAA676C48987E694\",\"hasUserGesture\":false}}"
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestWillBeSentParams {
+        pub request_id: super::RequestId,
+        pub loader_id: super::LoaderId,
+        #[serde(rename = "documentURL")]
+        pub document_url: String,
+        pub request: super::Request,
+        pub timestamp: super::MonotonicTime,
+        pub wall_time: super::TimeSinceEpoch,
+        pub initiator: super::Initiator,
+        pub redirect_response: Option<super::Response>,
+        #[serde(rename = "type")]
+        pub resource_type: Option<super::ResourceType>,
+        pub frame_id: Option<crate::protocol::page::FrameId>,
+        pub has_user_gesture: Option<bool>,
+    }
+
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ResponseReceived {
+        pub params: ResponseReceivedParams,
+    }
+    // "{\"method\":\"Network.responseReceived\",\"params\":{\"requestId\":\"1000009324.131\",\"loaderId\":\"C22D55F00A3F0778809CE5336D1DC450\",
+    // \"timestamp\":602980.547859,\"type\":\"XHR\",
+    // \"response\":{\"url\":\"https://59.202.58.131/api/assess/getAssessDataOfFirstIndex?curDate=2019%2F05%2F24&orgId=1&_=1558775437908\",\"status\":200,\"statusText\":\"OK\",\"headers\":{\"Date\":\"Sat, 25 May 2019 09:18:22 GMT\",\"Server\":\"nginx/1.6.3\",\"Connection\":\"keep-alive\",\"Content-Length\":\"1325\",\"X-Application-Context\":\"dingplus-user-web-dubbo:localtest,menu,privilege\",\"Content-Type\":\"application/json;charset=utf-8\"},\"mimeType\":\"application/json\",\"connectionReused\":true,\"connectionId\":395,\"remoteIPAddress\":\"59.202.58.131\",\"remotePort\":443,\"fromDiskCache\":false,\"fromServiceWorker\":false,\"encodedDataLength\":242,\"timing\":{\"requestTime\":602980.457097,\"proxyStart\":-1,\"proxyEnd\":-1,\"dnsStart\":-1,\"dnsEnd\":-1,\"connectStart\":-1,\"connectEnd\":-1,\"sslStart\":-1,\"sslEnd\":-1,\"workerStart\":-1,\"workerReady\":-1,\"sendStart\":0.436,\"sendEnd\":0.494,\"pushStart\":0,\"pushEnd\":0,\"receiveHeadersEnd\":89.751},\"protocol\":\"http/1.1\",\"securityState\":\"insecure\",\"securityDetails\":{\"protocol\":\"TLS 1.2\",\"keyExchange\":\"ECDHE_RSA\",\"keyExchangeGroup\":\"P-256\",\"cipher\":\"AES_128_GCM\",\"certificateId\":0,\"subjectName\":\"\",\"sanList\":[],\"issuer\":\"\",\"validFrom\":1481188811,\"validTo\":1796548811,\"signedCertificateTimestampList\":[],\"certificateTransparencyCompliance\":\"unknown\"}},
+    // \"frameId\":\"BAE1B46F570F91C8B3858CCB2F8BB9A4\"}}"
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ResponseReceivedParams {
+        pub request_id: super::RequestId,
+        pub loader_id: super::LoaderId,
+        pub timestamp: super::MonotonicTime,
+        #[serde(rename = "type")]
+        pub resource_type: super::ResourceType,
+        pub response: super::Response,
+        pub frame_id: Option<crate::protocol::page::FrameId>,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct LoadingFailed {
+        pub params: LoadingFailedParams,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct LoadingFailedParams {
+        pub request_id: super::RequestId,
+        pub timestamp: super::MonotonicTime,
+        #[serde(rename = "type")]
+        pub resource_type: super::ResourceType,
+        pub error_text: String,
+        pub canceled: Option<bool>,
+        pub blocked_reason: Option<super::BlockedReason>,
+    }
+
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DataReceived {
+        pub params: DataReceivedParams,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DataReceivedParams {
+        pub request_id: super::RequestId,
+        pub timestamp: super::MonotonicTime,
+        pub data_length: u32,
+        pub encoded_data_length: u32,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct LoadingFinished {
+        pub params: LoadingFinishedParams,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct LoadingFinishedParams {
+        pub request_id: super::RequestId,
+        pub timestamp: super::MonotonicTime,
+        pub encoded_data_length: u32,
+        pub should_report_corb_blocking: Option<bool>,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestServedFromCache {
+        pub params: RequestServedFromCacheParams,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestServedFromCacheParams {
+        pub request_id: super::RequestId,
+    }
+
+    #[derive(Deserialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ResourceChangedPriorityParams {
+        pub request_id: super::RequestId,
+        pub new_priority: super::ResourcePriority,
+        pub timestamp: super::MonotonicTime,
+    }
+
+    #[derive(Deserialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ResourceChangedPriority {
+        pub params: ResourceChangedPriorityParams,
+    }
+
     #[test]
     fn can_parse_request_intercepted_event() {
         use crate::protocol;
@@ -267,7 +494,7 @@ pub mod events {
 pub mod methods {
     use serde::{Deserialize, Serialize};
 
-    use crate::protocol::Method;
+    use crate::protocol::{EmptyReturnObject, Method};
     use std::collections::HashMap;
     use super::*;
 
@@ -289,6 +516,17 @@ pub mod methods {
         type ReturnObject = EnableReturnObject;
     }
 
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Disable {}
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DisableReturnObject {}
+    impl Method for Disable {
+        const NAME: &'static str = "Network.disable";
+        type ReturnObject = DisableReturnObject;
+    }
+
     #[derive(Serialize, Debug, Clone)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestPattern {
@@ -328,6 +566,7 @@ pub mod methods {
     #[derive(Serialize, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct AuthChallengeResponse<'a> {
+        /// Allowed values: Default, CancelAuth, ProvideCredentials
         pub response: &'a str,
         #[serde(skip_serializing_if = "Option::is_none")]
         pub username: Option<&'a str>,
@@ -362,6 +601,23 @@ pub mod methods {
         type ReturnObject = ContinueInterceptedRequestReturnObject;
     }
 
+    /// The body of a request that finished loading, for as long as Chrome keeps it around.
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetResponseBody<'a> {
+        pub request_id: &'a str,
+    }
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetResponseBodyReturnObject {
+        pub body: String,
+        pub base64_encoded: bool,
+    }
+    impl Method for GetResponseBody<'_> {
+        const NAME: &'static str = "Network.getResponseBody";
+        type ReturnObject = GetResponseBodyReturnObject;
+    }
+
     #[derive(Serialize, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct GetResponseBodyForInterception<'a> {
@@ -378,4 +634,64 @@ pub mod methods {
         type ReturnObject = GetResponseBodyForInterceptionReturnObject;
     }
 
+    /// The cookies sent to `urls`, or to the page's URL if it's `None`.
+    #[derive(Serialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetCookies<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub urls: Option<&'a [String]>,
+    }
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetCookiesReturnObject {
+        pub cookies: Vec<Cookie>,
+    }
+    impl Method for GetCookies<'_> {
+        const NAME: &'static str = "Network.getCookies";
+        type ReturnObject = GetCookiesReturnObject;
+    }
+
+    /// Every cookie in the browser context, whatever its URL.
+    #[derive(Serialize, Debug)]
+    pub struct GetAllCookies {}
+    impl Method for GetAllCookies {
+        const NAME: &'static str = "Network.getAllCookies";
+        type ReturnObject = GetCookiesReturnObject;
+    }
+
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SetCookies<'a> {
+        pub cookies: &'a [CookieParam],
+    }
+    impl Method for SetCookies<'_> {
+        const NAME: &'static str = "Network.setCookies";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    /// Deletes the cookies called `name` that match whichever of `url`, `domain` and `path` are
+    /// given; one of `url` or `domain` is needed.
+    #[derive(Serialize, Debug, Clone, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DeleteCookies<'a> {
+        pub name: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub url: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub domain: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub path: Option<&'a str>,
+    }
+    impl Method for DeleteCookies<'_> {
+        const NAME: &'static str = "Network.deleteCookies";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    #[derive(Serialize, Debug)]
+    pub struct ClearBrowserCookies {}
+    impl Method for ClearBrowserCookies {
+        const NAME: &'static str = "Network.clearBrowserCookies";
+        type ReturnObject = EmptyReturnObject;
+    }
+
 }
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use rand::{thread_rng, Rng};
 
 pub type FrameId = String;
+/// Identifies a script added with `AddScriptToEvaluateOnNewDocument`.
+pub type ScriptIdentifier = String;
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -16,6 +18,15 @@ pub enum ClientNavigationReason {
     Reload,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DialogType {
+    Alert,
+    Confirm,
+    Prompt,
+    Beforeunload,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum TransitionType {
@@ -247,12 +258,31 @@ pub mod events {
     pub struct FrameResized {
         pub params: EmptyReturnObject,
     }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct JavascriptDialogOpeningEvent {
+        pub params: JavascriptDialogOpeningParams,
+    }
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct JavascriptDialogOpeningParams {
+        /// URL of the frame that opened the dialog.
+        pub url: String,
+        pub message: String,
+        #[serde(rename = "type")]
+        pub dialog_type: DialogType,
+        /// True if the browser would show a dialog itself, were it not for the handler. Such
+        /// dialogs still block the page until answered with `HandleJavaScriptDialog`.
+        pub has_browser_handler: bool,
+        /// What a prompt dialog's input is filled in with.
+        pub default_prompt: Option<String>,
+    }
 }
 
 pub mod methods {
     use super::PrintToPdfOptions;
     use super::*;
-    use crate::protocol::{Method, dom};
+    use crate::protocol::{EmptyReturnObject, Method, dom};
     use serde::{Deserialize, Serialize};
 
     #[derive(Serialize, Debug)]
@@ -403,6 +433,50 @@ pub mod methods {
         type ReturnObject = CloseReturnObject;
     }
 
+    /// Evaluates `source` in every frame as soon as it has a new document, before any of the
+    /// page's own scripts run.
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AddScriptToEvaluateOnNewDocument<'a> {
+        pub source: &'a str,
+        /// Runs the script in an isolated world of this name instead of the page's own.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub world_name: Option<&'a str>,
+    }
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AddScriptToEvaluateOnNewDocumentReturnObject {
+        pub identifier: ScriptIdentifier,
+    }
+    impl Method for AddScriptToEvaluateOnNewDocument<'_> {
+        const NAME: &'static str = "Page.addScriptToEvaluateOnNewDocument";
+        type ReturnObject = AddScriptToEvaluateOnNewDocumentReturnObject;
+    }
+
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RemoveScriptToEvaluateOnNewDocument<'a> {
+        pub identifier: &'a str,
+    }
+    impl Method for RemoveScriptToEvaluateOnNewDocument<'_> {
+        const NAME: &'static str = "Page.removeScriptToEvaluateOnNewDocument";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    /// Accepts or dismisses the dialog `Page.javascriptDialogOpening` announced.
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct HandleJavaScriptDialog<'a> {
+        pub accept: bool,
+        /// The text to enter into a prompt before accepting it.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub prompt_text: Option<&'a str>,
+    }
+    impl Method for HandleJavaScriptDialog<'_> {
+        const NAME: &'static str = "Page.handleJavaScriptDialog";
+        type ReturnObject = EmptyReturnObject;
+    }
+
     #[derive(Serialize, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct BringToFront {}
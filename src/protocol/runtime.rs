@@ -31,8 +31,8 @@
     pub struct ExceptionDetails {
         pub exception_id: u16,
         pub text: String,
-        pub line_number: u16,
-        pub column_number: u16,
+        pub line_number: u32,
+        pub column_number: u32,
         pub script_id: Option<ScriptId>,
         pub url: Option<String>,
         pub stack_trace: Option<StackTrace>,
@@ -112,7 +112,7 @@
     }
 
 pub mod methods {
-    use crate::protocol::Method;
+    use crate::protocol::{EmptyReturnObject, Method};
     use serde::{Deserialize, Serialize};
     use super::*;
 
@@ -223,4 +223,110 @@ pub mod methods {
         const NAME: &'static str = "Runtime.enable";
         type ReturnObject = EnableReturnObject;
     }
-}
\ No newline at end of file
+
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Disable {}
+    impl Method for Disable {
+        const NAME: &'static str = "Runtime.disable";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    /// Adds a function `name` to the global object of every execution context, which takes a
+    /// string and, when called, sends `Runtime.bindingCalled` with it.
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AddBinding<'a> {
+        pub name: &'a str,
+        /// Only adds the binding to execution contexts of this name.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub execution_context_name: Option<&'a str>,
+    }
+    impl Method for AddBinding<'_> {
+        const NAME: &'static str = "Runtime.addBinding";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    /// Stops adding the binding to new execution contexts; existing ones keep it.
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RemoveBinding<'a> {
+        pub name: &'a str,
+    }
+    impl Method for RemoveBinding<'_> {
+        const NAME: &'static str = "Runtime.removeBinding";
+        type ReturnObject = EmptyReturnObject;
+    }
+}
+pub mod events {
+    use serde::Deserialize;
+    use super::*;
+
+    /// Which console method was called.
+    #[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    pub enum ConsoleAPIType {
+        Log,
+        Debug,
+        Info,
+        Error,
+        Warning,
+        Dir,
+        Dirxml,
+        Table,
+        Trace,
+        Clear,
+        StartGroup,
+        StartGroupCollapsed,
+        EndGroup,
+        Assert,
+        Profile,
+        ProfileEnd,
+        Count,
+        TimeEnd,
+        /// A type added to the protocol after this crate was written.
+        #[serde(other)]
+        Other,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct ConsoleAPICalledEvent {
+        pub params: ConsoleAPICalledParams,
+    }
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ConsoleAPICalledParams {
+        #[serde(rename = "type")]
+        pub call_type: ConsoleAPIType,
+        pub args: Vec<RemoteObject>,
+        pub execution_context_id: ExecutionContextId,
+        pub timestamp: Timestamp,
+        pub stack_trace: Option<StackTrace>,
+        pub context: Option<String>,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct BindingCalledEvent {
+        pub params: BindingCalledParams,
+    }
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct BindingCalledParams {
+        /// The binding's name, as given to `AddBinding`.
+        pub name: String,
+        /// What the page passed to the binding.
+        pub payload: String,
+        pub execution_context_id: ExecutionContextId,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct ExceptionThrownEvent {
+        pub params: ExceptionThrownParams,
+    }
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ExceptionThrownParams {
+        pub timestamp: Timestamp,
+        pub exception_details: ExceptionDetails,
+    }
+}
@@ -0,0 +1,42 @@
+pub mod methods {
+    use crate::protocol::network::methods::GetCookiesReturnObject;
+    use crate::protocol::network::CookieParam;
+    use crate::protocol::{EmptyReturnObject, Method};
+    use serde::Serialize;
+
+    /// Every cookie in a browser context, or the default one if `browser_context_id` is `None`.
+    /// Called on the browser rather than a tab.
+    #[derive(Serialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetCookies<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub browser_context_id: Option<&'a str>,
+    }
+    impl Method for GetCookies<'_> {
+        const NAME: &'static str = "Storage.getCookies";
+        type ReturnObject = GetCookiesReturnObject;
+    }
+
+    #[derive(Serialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SetCookies<'a> {
+        pub cookies: &'a [CookieParam],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub browser_context_id: Option<&'a str>,
+    }
+    impl Method for SetCookies<'_> {
+        const NAME: &'static str = "Storage.setCookies";
+        type ReturnObject = EmptyReturnObject;
+    }
+
+    #[derive(Serialize, Debug, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ClearCookies<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub browser_context_id: Option<&'a str>,
+    }
+    impl Method for ClearCookies<'_> {
+        const NAME: &'static str = "Storage.clearCookies";
+        type ReturnObject = EmptyReturnObject;
+    }
+}
@@ -11,6 +11,7 @@ pub enum TargetType {
     BackgroundPage,
     ServiceWorker,
     Browser,
+    #[serde(other)]
     Other,
 }
 
@@ -101,6 +102,20 @@ pub mod events {
         pub target_id: super::TargetId,
     }
 
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct TargetCrashedEvent {
+        pub params: TargetCrashedParams,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct TargetCrashedParams {
+        pub target_id: super::TargetId,
+        /// Termination status, e.g. "crashed" or "killed".
+        pub status: String,
+        pub error_code: i64,
+    }
+
     #[derive(Deserialize, Debug, Clone)]
     pub struct DetachedFromTarget {
         pub params: DetachedFromTargetParams,
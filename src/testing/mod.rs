@@ -0,0 +1,832 @@
+//! An in-process stand-in for Chrome's DevTools WebSocket endpoint, for testing code that drives
+//! this crate without launching a browser.
+//!
+//! A `MockServer` speaks just enough of the protocol for `Browser::connect` and the async
+//! `ChromeDebugSession` (via `ChromeBrowser::connect`) to work against it: the `Target` domain,
+//! `Page.navigate`, `Runtime.evaluate`, `DOM.getDocument` and `DOM.querySelector` (plus the DOM
+//! calls `Tab::find_element` makes on the node it finds). It starts out with one blank page.
+//!
+//! Any method can be scripted with `respond_to` or `respond_with`, which take precedence over
+//! the built-in behaviour; methods the server knows nothing about get an empty result. Events
+//! can be pushed to clients at any point with `emit_event` and `emit_target_event`.
+//!
+//! ```rust,no_run
+//! # use failure::Error;
+//! # fn main() -> Result<(), Error> {
+//! use serde_json::json;
+//! use headless_chrome::{testing::MockServer, Browser};
+//!
+//! let server = MockServer::start()?;
+//! server.respond_to("Runtime.evaluate", json!({"result": {"type": "number", "value": 42}}));
+//! server.add_element("button#submit");
+//!
+//! let browser = Browser::connect(server.ws_url())?;
+//! let tab = browser.wait_for_initial_tab()?;
+//! tab.navigate_to("https://example.com")?.wait_until_navigated()?;
+//! tab.find_element("button#submit")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use failure::Error;
+use log::*;
+use serde_json::{json, Value};
+use websocket::sender::Writer;
+use websocket::server::NoTlsAcceptor;
+use websocket::sync::Server;
+use websocket::OwnedMessage;
+
+use crate::protocol::dom::NodeId;
+use crate::protocol::target::{SessionID, TargetId};
+use crate::protocol::RemoteError;
+
+type Handler = Arc<dyn Fn(&Value) -> Result<Value, RemoteError> + Send + Sync>;
+
+type Client = Arc<Mutex<Writer<TcpStream>>>;
+
+#[derive(Debug, Clone)]
+struct MockTarget {
+    target_id: TargetId,
+    url: String,
+    title: String,
+    browser_context_id: Option<String>,
+    attached: bool,
+}
+
+impl MockTarget {
+    fn to_json(&self) -> Value {
+        json!({
+            "targetId": self.target_id,
+            "type": "page",
+            "title": self.title,
+            "url": self.url,
+            "attached": self.attached,
+            "browserContextId": self.browser_context_id,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MockSession {
+    target_id: TargetId,
+    /// Whether the client attached with `flatten: true`, in which case messages carry the
+    /// session ID rather than being wrapped in `Target.sendMessageToTarget` and
+    /// `Target.receivedMessageFromTarget`.
+    flatten: bool,
+}
+
+impl MockSession {
+    fn message_from_target(&self, session_id: &str, message: &Value) -> Value {
+        if self.flatten {
+            let mut message = message.clone();
+            message["sessionId"] = json!(session_id);
+            message
+        } else {
+            json!({
+                "method": "Target.receivedMessageFromTarget",
+                "params": {
+                    "sessionId": session_id,
+                    "targetId": self.target_id,
+                    "message": message.to_string(),
+                }
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MockNode {
+    node_id: NodeId,
+    parent_id: Option<NodeId>,
+    node_type: u8,
+    node_name: String,
+    selector: Option<String>,
+}
+
+impl MockNode {
+    fn local_name(&self) -> String {
+        if self.node_type == 1 {
+            self.node_name.to_lowercase()
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// What a method call did: the result (or error) to answer it with, and the events that follow.
+/// For calls on a target, `events` come from the target's session, while `browser_events` are
+/// sent at the browser level. `delayed_events` are sent like `events`, but only after `delay`.
+struct Reply {
+    result: Result<Value, RemoteError>,
+    events: Vec<Value>,
+    browser_events: Vec<Value>,
+    delay: Duration,
+    delayed_events: Vec<Value>,
+}
+
+impl Reply {
+    fn ok(result: Value) -> Self {
+        Self {
+            result: Ok(result),
+            events: vec![],
+            browser_events: vec![],
+            delay: Duration::from_millis(0),
+            delayed_events: vec![],
+        }
+    }
+
+    fn error(message: &str) -> Self {
+        Self {
+            result: Err(RemoteError {
+                code: -32000,
+                message: message.to_string(),
+            }),
+            events: vec![],
+            browser_events: vec![],
+            delay: Duration::from_millis(0),
+            delayed_events: vec![],
+        }
+    }
+
+    fn followed_by(mut self, method: &str, params: Value) -> Self {
+        let event = json!({"method": method, "params": params});
+        if self.delay > Duration::from_millis(0) {
+            self.delayed_events.push(event);
+        } else {
+            self.events.push(event);
+        }
+        self
+    }
+
+    /// Any events added after this are sent `delay` after the ones before it.
+    fn then_after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+struct State {
+    targets: Vec<MockTarget>,
+    sessions: HashMap<SessionID, MockSession>,
+    nodes: Vec<MockNode>,
+    handlers: HashMap<String, Handler>,
+    calls: Vec<Value>,
+    load_time: Duration,
+    next_id: usize,
+}
+
+impl State {
+    fn new() -> Self {
+        let mut state = Self {
+            targets: vec![],
+            sessions: HashMap::new(),
+            nodes: vec![],
+            handlers: HashMap::new(),
+            calls: vec![],
+            load_time: Duration::from_millis(500),
+            next_id: 1,
+        };
+        state.create_target("about:blank", None);
+
+        state.add_node(None, 9, "#document", None);
+        state.add_node(Some(1), 1, "HTML", Some("html"));
+        state.add_node(Some(2), 1, "HEAD", Some("head"));
+        state.add_node(Some(2), 1, "BODY", Some("body"));
+        state
+    }
+
+    fn unique_id(&mut self, prefix: &str) -> String {
+        let id = format!("{}-{}", prefix, self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    fn create_target(&mut self, url: &str, browser_context_id: Option<String>) -> MockTarget {
+        let target = MockTarget {
+            target_id: self.unique_id("TARGET"),
+            url: url.to_string(),
+            title: String::new(),
+            browser_context_id,
+            attached: false,
+        };
+        self.targets.push(target.clone());
+        target
+    }
+
+    fn target(&self, target_id: &str) -> Option<&MockTarget> {
+        self.targets
+            .iter()
+            .find(|target| target.target_id == target_id)
+    }
+
+    fn add_node(
+        &mut self,
+        parent_id: Option<NodeId>,
+        node_type: u8,
+        node_name: &str,
+        selector: Option<&str>,
+    ) -> NodeId {
+        let node_id = self.nodes.last().map_or(1, |last| last.node_id + 1);
+        self.nodes.push(MockNode {
+            node_id,
+            parent_id,
+            node_type,
+            node_name: node_name.to_string(),
+            selector: selector.map(ToString::to_string),
+        });
+        node_id
+    }
+
+    fn node(&self, params: &Value) -> Option<&MockNode> {
+        let node_id = params
+            .get("nodeId")
+            .or_else(|| params.get("backendNodeId"))
+            .and_then(Value::as_u64)?;
+        self.nodes
+            .iter()
+            .find(|node| u64::from(node.node_id) == node_id)
+    }
+
+    fn matching_nodes(&self, params: &Value) -> Vec<NodeId> {
+        let selector = params.get("selector").and_then(Value::as_str);
+        self.nodes
+            .iter()
+            .filter(|node| node.selector.is_some() && node.selector.as_deref() == selector)
+            .map(|node| node.node_id)
+            .collect()
+    }
+
+    fn node_to_json(&self, node: &MockNode, depth: i64) -> Value {
+        let children: Vec<&MockNode> = self
+            .nodes
+            .iter()
+            .filter(|child| child.parent_id == Some(node.node_id))
+            .collect();
+        let mut json = json!({
+            "nodeId": node.node_id,
+            "backendNodeId": node.node_id,
+            "parentId": node.parent_id,
+            "nodeType": node.node_type,
+            "nodeName": node.node_name,
+            "localName": node.local_name(),
+            "nodeValue": "",
+            "childNodeCount": children.len(),
+        });
+        if depth != 0 {
+            json["children"] = children
+                .into_iter()
+                .map(|child| self.node_to_json(child, depth - 1))
+                .collect();
+        }
+        json
+    }
+}
+
+/// A WebSocket server on a random local port that pretends to be Chrome's browser endpoint.
+/// It stops, disconnecting any clients, when dropped.
+pub struct MockServer {
+    port: u16,
+    state: Arc<Mutex<State>>,
+    clients: Arc<Mutex<Vec<Client>>>,
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "MockServer {{ port: {} }}", self.port)
+    }
+}
+
+impl MockServer {
+    pub fn start() -> Result<Self, Error> {
+        let server = Server::bind("127.0.0.1:0")?;
+        let port = server.local_addr()?.port();
+
+        let mock_server = Self {
+            port,
+            state: Arc::new(Mutex::new(State::new())),
+            clients: Arc::new(Mutex::new(vec![])),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+        };
+        mock_server.accept_connections(server);
+
+        debug!("Mock DevTools server listening on port {}", port);
+        Ok(mock_server)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The browser-level WebSocket URL, i.e. what `Browser::connect` expects.
+    pub fn ws_url(&self) -> String {
+        format!("ws://127.0.0.1:{}/devtools/browser/mock", self.port)
+    }
+
+    /// Answers every call to `method` with `result`.
+    pub fn respond_to(&self, method: &str, result: Value) {
+        self.respond_with(method, move |_params| Ok(result.clone()));
+    }
+
+    /// Answers every call to `method` with whatever `handler` returns for the call's params.
+    pub fn respond_with<F>(&self, method: &str, handler: F)
+    where
+        F: Fn(&Value) -> Result<Value, RemoteError> + Send + Sync + 'static,
+    {
+        self.state
+            .lock()
+            .unwrap()
+            .handlers
+            .insert(method.to_string(), Arc::new(handler));
+    }
+
+    /// How long pages take to load after `Page.navigate`, i.e. the time between the `init`
+    /// and `networkAlmostIdle` lifecycle events. Defaults to half a second, which is enough
+    /// for `Tab::wait_until_navigated` to notice the navigation starting.
+    pub fn set_load_time(&self, load_time: Duration) {
+        self.state.lock().unwrap().load_time = load_time;
+    }
+
+    /// Adds an element to the page's body, which `DOM.querySelector` and
+    /// `DOM.querySelectorAll` return for exactly `selector`.
+    pub fn add_element(&self, selector: &str) -> NodeId {
+        let tag_name: String = selector
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect();
+        let tag_name = if tag_name.is_empty() {
+            "DIV".to_string()
+        } else {
+            tag_name.to_uppercase()
+        };
+        let mut state = self.state.lock().unwrap();
+        let body_id = state.matching_nodes(&json!({"selector": "body"}))[0];
+        state.add_node(Some(body_id), 1, &tag_name, Some(selector))
+    }
+
+    /// The IDs of the targets the server knows about, in the order they were created.
+    pub fn target_ids(&self) -> Vec<TargetId> {
+        self.state
+            .lock()
+            .unwrap()
+            .targets
+            .iter()
+            .map(|target| target.target_id.clone())
+            .collect()
+    }
+
+    /// Every method call received so far, browser- and target-level alike. Calls sent via
+    /// `Target.sendMessageToTarget` show up twice: once wrapped, then unwrapped.
+    pub fn received_calls(&self) -> Vec<Value> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// The params of every call to `method` received so far.
+    pub fn received_params(&self, method: &str) -> Vec<Value> {
+        self.received_calls()
+            .into_iter()
+            .filter(|call| call["method"] == method)
+            .map(|call| call["params"].clone())
+            .collect()
+    }
+
+    /// Sends a browser-level event to every connected client.
+    pub fn emit_event(&self, method: &str, params: Value) {
+        broadcast(&self.clients, &json!({"method": method, "params": params}));
+    }
+
+    /// Sends an event from a target to every session attached to it.
+    pub fn emit_target_event(&self, target_id: &str, method: &str, params: Value) {
+        let event = json!({"method": method, "params": params});
+        let messages: Vec<Value> = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.target_id == target_id)
+            .map(|(session_id, session)| session.message_from_target(session_id, &event))
+            .collect();
+        for message in messages {
+            broadcast(&self.clients, &message);
+        }
+    }
+
+    /// Closes every client connection, as if Chrome had gone away. The server keeps accepting
+    /// new ones.
+    pub fn disconnect_clients(&self) {
+        for client in self.clients.lock().unwrap().drain(..) {
+            if client.lock().unwrap().shutdown_all().is_err() {
+                debug!("Couldn't shut down mock server connection");
+            }
+        }
+    }
+
+    fn accept_connections(&self, mut server: Server<NoTlsAcceptor>) {
+        let state = Arc::clone(&self.state);
+        let clients = Arc::clone(&self.clients);
+        let shutting_down = Arc::clone(&self.shutting_down);
+
+        thread::spawn(move || loop {
+            let upgrade = match server.accept() {
+                Ok(upgrade) => upgrade,
+                Err(_) if shutting_down.load(Ordering::SeqCst) => break,
+                Err(_) => continue,
+            };
+            if shutting_down.load(Ordering::SeqCst) {
+                break;
+            }
+            let client = match upgrade.accept() {
+                Ok(client) => client,
+                Err((_, err)) => {
+                    warn!("Mock server couldn't accept connection: {}", err);
+                    continue;
+                }
+            };
+            let (mut reader, writer) = match client.split() {
+                Ok(halves) => halves,
+                Err(err) => {
+                    warn!("Mock server couldn't split connection: {}", err);
+                    continue;
+                }
+            };
+            let writer = Arc::new(Mutex::new(writer));
+            clients.lock().unwrap().push(Arc::clone(&writer));
+
+            let state = Arc::clone(&state);
+            let clients = Arc::clone(&clients);
+            thread::spawn(move || {
+                for message in reader.incoming_messages() {
+                    match message {
+                        Ok(OwnedMessage::Text(raw_call)) => {
+                            handle_call(&state, &clients, &writer, &raw_call);
+                        }
+                        Ok(OwnedMessage::Close(_)) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+                }
+                trace!("Mock server connection closed");
+            });
+        });
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        self.disconnect_clients();
+        // wake the accepting thread up so that it notices
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+    }
+}
+
+fn send(client: &Client, message: &Value) {
+    let message = websocket::Message::text(message.to_string());
+    if let Err(err) = client.lock().unwrap().send_message(&message) {
+        debug!("Mock server couldn't send message: {}", err);
+    }
+}
+
+fn broadcast(clients: &Mutex<Vec<Client>>, message: &Value) {
+    let clients: Vec<Client> = clients.lock().unwrap().clone();
+    for client in &clients {
+        send(client, message);
+    }
+}
+
+fn response(call_id: &Value, result: Result<Value, RemoteError>) -> Value {
+    match result {
+        Ok(result) => json!({"id": call_id, "result": result}),
+        Err(err) => json!({
+            "id": call_id,
+            "error": {"code": err.code, "message": err.message}
+        }),
+    }
+}
+
+fn handle_call(
+    state: &Mutex<State>,
+    clients: &Arc<Mutex<Vec<Client>>>,
+    client: &Client,
+    raw_call: &str,
+) {
+    let call: Value = match serde_json::from_str(raw_call) {
+        Ok(call) => call,
+        Err(err) => {
+            warn!(
+                "Mock server got a call that isn't JSON ({}): {}",
+                err, raw_call
+            );
+            return;
+        }
+    };
+    let method = call["method"].as_str().unwrap_or("").to_string();
+    let params = call.get("params").cloned().unwrap_or_else(|| json!({}));
+    trace!("Mock server got call: {}", raw_call);
+
+    if let Some(session_id) = call["sessionId"].as_str() {
+        handle_target_call(state, clients, client, session_id, &call);
+    } else if method == "Target.sendMessageToTarget" {
+        state.lock().unwrap().calls.push(call.clone());
+        send(client, &response(&call["id"], Ok(json!({}))));
+
+        let inner_call: Value = params["message"]
+            .as_str()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or(Value::Null);
+        let session_id = params["sessionId"].as_str().unwrap_or("");
+        handle_target_call(state, clients, client, session_id, &inner_call);
+    } else {
+        let reply = answer(state, "", &call, &method, &params, false);
+        send(client, &response(&call["id"], reply.result));
+        for event in reply.events.into_iter().chain(reply.browser_events) {
+            broadcast(clients, &event);
+        }
+    }
+}
+
+fn handle_target_call(
+    state: &Mutex<State>,
+    clients: &Arc<Mutex<Vec<Client>>>,
+    client: &Client,
+    session_id: &str,
+    call: &Value,
+) {
+    let session = match state.lock().unwrap().sessions.get(session_id) {
+        Some(session) => session.clone(),
+        None => {
+            warn!(
+                "Mock server got a message for unknown session {}",
+                session_id
+            );
+            return;
+        }
+    };
+    let method = call["method"].as_str().unwrap_or("").to_string();
+    let params = call.get("params").cloned().unwrap_or_else(|| json!({}));
+    let reply = answer(state, &session.target_id, call, &method, &params, true);
+
+    let call_response = response(&call["id"], reply.result);
+    send(
+        client,
+        &session.message_from_target(session_id, &call_response),
+    );
+    for event in reply.events {
+        broadcast(clients, &session.message_from_target(session_id, &event));
+    }
+    for event in reply.browser_events {
+        broadcast(clients, &event);
+    }
+    if !reply.delayed_events.is_empty() {
+        let clients = Arc::clone(clients);
+        let session_id = session_id.to_string();
+        let (delay, delayed_events) = (reply.delay, reply.delayed_events);
+        thread::spawn(move || {
+            thread::sleep(delay);
+            for event in delayed_events {
+                broadcast(&clients, &session.message_from_target(&session_id, &event));
+            }
+        });
+    }
+}
+
+fn answer(
+    state: &Mutex<State>,
+    target_id: &str,
+    call: &Value,
+    method: &str,
+    params: &Value,
+    on_target: bool,
+) -> Reply {
+    let handler = {
+        let mut state = state.lock().unwrap();
+        state.calls.push(call.clone());
+        state.handlers.get(method).cloned()
+    };
+    // called without holding the lock, so that handlers can use the server
+    if let Some(handler) = handler {
+        return Reply {
+            result: handler(params),
+            events: vec![],
+            browser_events: vec![],
+            delay: Duration::from_millis(0),
+            delayed_events: vec![],
+        };
+    }
+
+    let mut state = state.lock().unwrap();
+    if on_target {
+        answer_target_call(&mut state, target_id, method, params)
+    } else {
+        answer_browser_call(&mut state, method, params)
+    }
+}
+
+fn answer_browser_call(state: &mut State, method: &str, params: &Value) -> Reply {
+    match method {
+        "Browser.getVersion" => Reply::ok(json!({
+            "protocolVersion": "1.3",
+            "product": "HeadlessChrome/75.0.3770.142",
+            "revision": "@mock",
+            "userAgent": "Mozilla/5.0 HeadlessChrome/75.0.3770.142 (headless_chrome::testing::MockServer)",
+            "jsVersion": "7.5.288.30",
+        })),
+        "Target.setDiscoverTargets" => {
+            let discover = params["discover"].as_bool().unwrap_or(false);
+            let mut reply = Reply::ok(json!({}));
+            if discover {
+                for target in &state.targets {
+                    reply = reply.followed_by(
+                        "Target.targetCreated",
+                        json!({"targetInfo": target.to_json()}),
+                    );
+                }
+            }
+            reply
+        }
+        "Target.getTargets" => {
+            let target_infos: Vec<Value> = state.targets.iter().map(MockTarget::to_json).collect();
+            Reply::ok(json!({ "targetInfos": target_infos }))
+        }
+        "Target.getTargetInfo" => match state.target(params["targetId"].as_str().unwrap_or("")) {
+            Some(target) => Reply::ok(json!({"targetInfo": target.to_json()})),
+            None => Reply::error("No target with given id found"),
+        },
+        "Target.createBrowserContext" => {
+            Reply::ok(json!({"browserContextId": state.unique_id("CONTEXT")}))
+        }
+        "Target.createTarget" => {
+            let url = params["url"].as_str().unwrap_or("about:blank");
+            let browser_context_id = params["browserContextId"].as_str().map(ToString::to_string);
+            let target = state.create_target(url, browser_context_id);
+            Reply::ok(json!({"targetId": target.target_id})).followed_by(
+                "Target.targetCreated",
+                json!({"targetInfo": target.to_json()}),
+            )
+        }
+        "Target.attachToTarget" => {
+            let target_id = params["targetId"].as_str().unwrap_or("");
+            if state.target(target_id).is_none() {
+                return Reply::error("No target with given id found");
+            }
+            let session_id = state.unique_id("SESSION");
+            let session = MockSession {
+                target_id: target_id.to_string(),
+                flatten: params["flatten"].as_bool().unwrap_or(false),
+            };
+            state.sessions.insert(session_id.clone(), session);
+            let target = state
+                .targets
+                .iter_mut()
+                .find(|target| target.target_id == target_id)
+                .expect("target was just found");
+            target.attached = true;
+            let target_info = target.to_json();
+            Reply::ok(json!({ "sessionId": session_id })).followed_by(
+                "Target.attachedToTarget",
+                json!({
+                    "sessionId": session_id,
+                    "targetInfo": target_info,
+                    "waitingForDebugger": false,
+                }),
+            )
+        }
+        "Target.closeTarget" => {
+            let target_id = params["targetId"].as_str().unwrap_or("").to_string();
+            if state.target(&target_id).is_none() {
+                return Reply::error("No target with given id found");
+            }
+            state.targets.retain(|target| target.target_id != target_id);
+            let session_ids: Vec<SessionID> = state
+                .sessions
+                .iter()
+                .filter(|(_, session)| session.target_id == target_id)
+                .map(|(session_id, _)| session_id.clone())
+                .collect();
+            let mut reply = Reply::ok(json!({"success": true}));
+            for session_id in session_ids {
+                state.sessions.remove(&session_id);
+                reply = reply.followed_by(
+                    "Target.detachedFromTarget",
+                    json!({"sessionId": session_id, "targetId": target_id}),
+                );
+            }
+            reply.followed_by("Target.targetDestroyed", json!({ "targetId": target_id }))
+        }
+        _ => Reply::ok(json!({})),
+    }
+}
+
+fn answer_target_call(state: &mut State, target_id: &str, method: &str, params: &Value) -> Reply {
+    match method {
+        "Page.navigate" => navigate(state, target_id, params),
+        "Runtime.evaluate" => {
+            let expression = params["expression"].as_str().unwrap_or("");
+            Reply::ok(json!({ "result": remote_object(expression) }))
+        }
+        "DOM.getDocument" => {
+            let depth = params["depth"].as_i64().unwrap_or(1);
+            let root = state.node_to_json(&state.nodes[0], depth);
+            Reply::ok(json!({ "root": root }))
+        }
+        "DOM.querySelector" => {
+            let node_id = state.matching_nodes(params).first().copied().unwrap_or(0);
+            Reply::ok(json!({ "nodeId": node_id }))
+        }
+        "DOM.querySelectorAll" => Reply::ok(json!({"nodeIds": state.matching_nodes(params)})),
+        "DOM.describeNode" => match state.node(params) {
+            Some(node) => {
+                let depth = params["depth"].as_i64().unwrap_or(1);
+                Reply::ok(json!({"node": state.node_to_json(node, depth)}))
+            }
+            None => Reply::error("Could not find node with given id"),
+        },
+        "DOM.resolveNode" => match state.node(params) {
+            Some(node) => Reply::ok(json!({
+                "object": {
+                    "type": "object",
+                    "subtype": "node",
+                    "className": format!("HTML{}Element", node.local_name()),
+                    "description": node.local_name(),
+                    "objectId": format!("mock-node-{}", node.node_id),
+                }
+            })),
+            None => Reply::error("No node with given id found"),
+        },
+        _ => Reply::ok(json!({})),
+    }
+}
+
+/// Answers `Page.navigate` the way Chrome does for a page that loads straight away, including
+/// the lifecycle events `Tab::wait_until_navigated` waits for.
+fn navigate(state: &mut State, target_id: &str, params: &Value) -> Reply {
+    let url = params["url"].as_str().unwrap_or("").to_string();
+    let loader_id = state.unique_id("LOADER");
+    let target_info = match state
+        .targets
+        .iter_mut()
+        .find(|target| target.target_id == target_id)
+    {
+        Some(target) => {
+            target.url.clone_from(&url);
+            target.to_json()
+        }
+        None => return Reply::error("No target with given id found"),
+    };
+
+    let mut reply = Reply::ok(json!({"frameId": target_id, "loaderId": loader_id}))
+        .followed_by("Page.frameStartedLoading", json!({ "frameId": target_id }));
+    for name in &[
+        "init",
+        "DOMContentLoaded",
+        "load",
+        "networkAlmostIdle",
+        "networkIdle",
+    ] {
+        reply = reply.followed_by(
+            "Page.lifecycleEvent",
+            json!({"frameId": target_id, "loaderId": loader_id, "name": name, "timestamp": 0.0}),
+        );
+        if *name == "init" {
+            reply = reply.then_after(state.load_time);
+        }
+    }
+    reply = reply
+        .followed_by(
+            "Page.frameNavigated",
+            json!({"frame": {
+                "id": target_id,
+                "loaderId": loader_id,
+                "url": url,
+                "securityOrigin": url,
+                "mimeType": "text/html",
+            }}),
+        )
+        .followed_by("Page.frameStoppedLoading", json!({ "frameId": target_id }));
+
+    // Chrome reports this at the browser level, not on the page's session
+    reply.browser_events.push(json!({
+        "method": "Target.targetInfoChanged",
+        "params": {"targetInfo": target_info},
+    }));
+    reply
+}
+
+/// Evaluates `expression` only as far as JSON literals go; anything else is `undefined`.
+fn remote_object(expression: &str) -> Value {
+    match serde_json::from_str(expression.trim()) {
+        Ok(Value::Null) => json!({"type": "object", "subtype": "null", "value": null}),
+        Ok(value @ Value::Bool(_)) => json!({"type": "boolean", "value": value}),
+        Ok(value @ Value::Number(_)) => json!({"type": "number", "value": value}),
+        Ok(value @ Value::String(_)) => json!({"type": "string", "value": value}),
+        Ok(value) => json!({"type": "object", "value": value}),
+        Err(_) => json!({"type": "undefined"}),
+    }
+}
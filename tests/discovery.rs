@@ -0,0 +1,94 @@
+use std::io;
+
+use headless_chrome::browser::discovery;
+use headless_chrome::protocol::target::TargetType;
+
+mod server;
+
+const VERSION_JSON: &str = r#"{
+   "Browser": "HeadlessChrome/74.0.3729.157",
+   "Protocol-Version": "1.3",
+   "User-Agent": "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/74.0.3729.157 Safari/537.36",
+   "V8-Version": "7.4.288.28",
+   "WebKit-Version": "537.36 (@9ab0cfab84ded083718d3a4ff830726efd38869f)",
+   "webSocketDebuggerUrl": "ws://127.0.0.1:9222/devtools/browser/b0b8a4fb-bb17-4359-9533-a8d9f3908bd8"
+}"#;
+
+const LIST_JSON: &str = r#"[ {
+   "description": "",
+   "devtoolsFrontendUrl": "/devtools/inspector.html?ws=127.0.0.1:9222/devtools/page/DAB7FB6187B554E10B0BD18821265734",
+   "id": "DAB7FB6187B554E10B0BD18821265734",
+   "title": "Example Domain",
+   "type": "page",
+   "url": "https://example.com/",
+   "webSocketDebuggerUrl": "ws://127.0.0.1:9222/devtools/page/DAB7FB6187B554E10B0BD18821265734"
+}, {
+   "description": "",
+   "id": "E1D4F4A2C06C1FDA6CB8C89AFBE4C1A2",
+   "parentId": "DAB7FB6187B554E10B0BD18821265734",
+   "title": "https://example.com/frame.html",
+   "type": "iframe",
+   "url": "https://example.com/frame.html"
+} ]"#;
+
+fn json_endpoints() -> server::Server {
+    server::Server::new(|request: tiny_http::Request| {
+        let body = match request.url() {
+            "/json/version" => VERSION_JSON,
+            "/json/list" => LIST_JSON,
+            _ => return request.respond(tiny_http::Response::new_empty(404.into())),
+        };
+        let response = tiny_http::Response::new(
+            200.into(),
+            vec![
+                tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                    .unwrap(),
+            ],
+            io::Cursor::new(body),
+            Some(body.len()),
+            None,
+        );
+        request.respond(response)
+    })
+}
+
+#[test]
+fn gets_version_from_json_endpoint() {
+    let server = json_endpoints();
+    let version = discovery::get_version(&format!("127.0.0.1:{}", server.port())).unwrap();
+    assert_eq!("HeadlessChrome/74.0.3729.157", version.browser);
+    assert_eq!(
+        "ws://127.0.0.1:9222/devtools/browser/b0b8a4fb-bb17-4359-9533-a8d9f3908bd8",
+        version.web_socket_debugger_url
+    );
+}
+
+#[test]
+fn lists_targets_from_json_endpoint() {
+    let server = json_endpoints();
+    let host_port = format!("127.0.0.1:{}", server.port());
+
+    let targets = discovery::list_targets(&host_port).unwrap();
+    assert_eq!(2, targets.len());
+    assert_eq!("DAB7FB6187B554E10B0BD18821265734", targets[0].target_id);
+    assert_eq!("https://example.com/", targets[0].url);
+    assert!(!targets[0].attached);
+    match targets[1].target_type {
+        TargetType::Other => {}
+        ref other => panic!("expected an iframe to be Other, got {:?}", other),
+    }
+    assert!(targets[1].attached);
+
+    let pages = discovery::list_pages(&host_port).unwrap();
+    assert_eq!(1, pages.len());
+    assert_eq!("Example Domain", pages[0].title);
+}
+
+#[test]
+fn reports_missing_endpoints() {
+    let server = server::Server::new(|request: tiny_http::Request| {
+        request.respond(tiny_http::Response::new_empty(404.into()))
+    });
+    let result = discovery::get_version(&format!("127.0.0.1:{}", server.port()));
+    assert!(result.is_err());
+}
@@ -0,0 +1,1752 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use futures::future::{self, Future};
+use futures::{Async, Stream};
+use serde_json::json;
+
+use headless_chrome::browser::cookies::CookieFormat;
+use headless_chrome::browser::process::ChromeExited;
+use headless_chrome::browser::tab::auth::{Credentials, CredentialsProvider};
+use headless_chrome::browser::tab::dialog::{DialogPolicy, DialogResponse};
+use headless_chrome::browser::tab::har::{Har, Header, QueryParam};
+use headless_chrome::browser::tab::interception::{
+    self, FetchDecision, FulfilledResponse, RequestOverrides,
+};
+use headless_chrome::browser::version::{
+    ChromeVersion, Incompatibility, IncompatibleChrome, VersionCheck,
+};
+use headless_chrome::browser_async::chrome_browser::ChromeBrowser;
+use headless_chrome::browser_async::page_message::{
+    MethodCallDone, PageResponse, PageResponseWrapper, ReceivedEvent,
+};
+use headless_chrome::browser_async::task_describe::target_tasks::TargetEvent;
+use headless_chrome::browser_async::task_describe::TaskDescribe;
+use headless_chrome::browser_async::{ChromeDebugSession, DebugSession, TargetLostReason};
+use headless_chrome::protocol::fetch::{HeaderEntry, RequestPattern, RequestStage};
+use headless_chrome::protocol::network::methods::DeleteCookies;
+use headless_chrome::protocol::network::{CookieParam, CookieSameSite, ErrorReason, ResourceType};
+use headless_chrome::protocol::page::events::FrameStartedLoadingEvent;
+use headless_chrome::protocol::page::DialogType;
+use headless_chrome::protocol::runtime::methods::Evaluate;
+use headless_chrome::protocol::{Event, RawEvent, RemoteError};
+use headless_chrome::testing::MockServer;
+use headless_chrome::util::{Timeout, Wait};
+use headless_chrome::{Browser, LaunchOptionsBuilder};
+
+/// A shell script standing in for Chrome, which points whoever launches it at `server`, then
+/// "crashes" once `exit_file` shows up.
+#[cfg(unix)]
+fn fake_chrome(dir: &Path, server: &MockServer, exit_file: &Path) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("chrome");
+    let script = format!(
+        "#!/bin/sh\n\
+         echo 'DevTools listening on {}' >&2\n\
+         while [ ! -e '{}' ]; do sleep 0.05; done\n\
+         echo 'Received signal 11 SEGV_MAPERR' >&2\n\
+         exit 139\n",
+        server.ws_url(),
+        exit_file.display()
+    );
+    std::fs::write(&path, script).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn sync_browser_drives_mock_server() {
+    let server = MockServer::start().unwrap();
+    let button_id = server.add_element("button#submit");
+    server.respond_with("Runtime.evaluate", |params| {
+        if params["expression"] == "document.title" {
+            Ok(json!({"result": {"type": "string", "value": "Mock page"}}))
+        } else {
+            Err(RemoteError {
+                code: -32000,
+                message: "unexpected expression".to_string(),
+            })
+        }
+    });
+
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    assert_eq!(75, browser.version().unwrap().major);
+    let tab = browser.wait_for_initial_tab().unwrap();
+
+    tab.navigate_to("https://example.com/")
+        .unwrap()
+        .wait_until_navigated()
+        .unwrap();
+    assert_eq!(
+        vec![json!({"url": "https://example.com/"})],
+        server.received_params("Page.navigate")
+    );
+
+    // calls to the tab go straight to its flattened session
+    assert_eq!(
+        json!(true),
+        server.received_params("Target.attachToTarget")[0]["flatten"]
+    );
+    assert!(server
+        .received_params("Target.sendMessageToTarget")
+        .is_empty());
+
+    let element = tab.find_element("button#submit").unwrap();
+    assert_eq!(button_id, element.backend_node_id);
+    assert!(tab.find_element("#missing").is_err());
+
+    let title = tab
+        .call_method(Evaluate {
+            expression: "document.title",
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(Some(json!("Mock page")), title.result.value);
+
+    let new_tab = browser.new_tab().unwrap();
+    assert_eq!(2, server.target_ids().len());
+    assert_eq!(&server.target_ids()[1], new_tab.get_target_id());
+}
+
+#[test]
+fn sync_browser_receives_injected_events() {
+    let server = MockServer::start().unwrap();
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    browser.wait_for_initial_tab().unwrap();
+
+    server.emit_event(
+        "Target.targetCreated",
+        json!({"targetInfo": {
+            "targetId": "INJECTED", "type": "page", "title": "", "url": "about:blank", "attached": false
+        }}),
+    );
+    Wait::with_timeout(Duration::from_secs(5))
+        .until(|| {
+            server
+                .received_params("Target.attachToTarget")
+                .into_iter()
+                .find(|params| params["targetId"] == "INJECTED")
+        })
+        .unwrap();
+
+    let tab = browser.wait_for_initial_tab().unwrap();
+    let target_id = tab.get_target_id().clone();
+    let navigated = thread::spawn(move || tab.wait_until_navigated().map(|_| ()));
+    for name in &["init", "networkAlmostIdle"] {
+        server.emit_target_event(
+            &target_id,
+            "Page.lifecycleEvent",
+            json!({"frameId": target_id, "loaderId": "L1", "name": name, "timestamp": 0.0}),
+        );
+        thread::sleep(Duration::from_millis(500));
+    }
+    navigated.join().unwrap().unwrap();
+}
+
+#[test]
+fn sync_tab_event_listeners() {
+    let server = Arc::new(MockServer::start().unwrap());
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    let target_id = tab.get_target_id().clone();
+
+    let methods = Arc::new(Mutex::new(vec![]));
+    let listener = {
+        let methods = Arc::clone(&methods);
+        tab.add_event_listener(move |event| {
+            let method = match event {
+                Event::FrameStartedLoading(_) => "Page.frameStartedLoading".to_string(),
+                Event::Raw(raw_event) => raw_event.method.clone(),
+                _ => return,
+            };
+            methods.lock().unwrap().push(method);
+        })
+    };
+
+    let emitter = {
+        let server = Arc::clone(&server);
+        let target_id = target_id.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            server.emit_target_event(
+                &target_id,
+                "Page.frameStartedLoading",
+                json!({"frameId": "F1"}),
+            );
+            server.emit_target_event(&target_id, "Custom.first", json!({}));
+            server.emit_target_event(&target_id, "Custom.second", json!({"n": 2}));
+        })
+    };
+    let second: RawEvent = tab
+        .wait_for_event(
+            |raw_event: &RawEvent| raw_event.method == "Custom.second",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+    emitter.join().unwrap();
+    assert_eq!(json!({"n": 2}), second.params);
+    Wait::with_timeout(Duration::from_secs(5))
+        .until(|| Some(()).filter(|_| methods.lock().unwrap().len() == 3))
+        .unwrap();
+    assert_eq!(
+        vec!["Page.frameStartedLoading", "Custom.first", "Custom.second"],
+        *methods.lock().unwrap()
+    );
+
+    drop(listener);
+    server.emit_target_event(&target_id, "Custom.third", json!({}));
+    let err = tab
+        .wait_for_event(
+            |_: &FrameStartedLoadingEvent| true,
+            Duration::from_millis(300),
+        )
+        .unwrap_err();
+    assert!(err.downcast::<Timeout>().is_ok());
+    assert_eq!(3, methods.lock().unwrap().len());
+}
+
+#[test]
+fn sync_tab_captures_console_and_exceptions() {
+    let server = MockServer::start().unwrap();
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    let target_id = tab.get_target_id().clone();
+    tab.enable_runtime().unwrap();
+    assert!(!server.received_params("Runtime.enable").is_empty());
+
+    server.emit_target_event(
+        &target_id,
+        "Runtime.consoleAPICalled",
+        json!({
+            "type": "log",
+            "args": [{"type": "string", "value": "count:"}, {"type": "number", "value": 2}],
+            "executionContextId": 1,
+            "timestamp": 1.0
+        }),
+    );
+    assert!(!tab.console().has_errors());
+    server.emit_target_event(
+        &target_id,
+        "Runtime.exceptionThrown",
+        json!({
+            "timestamp": 2.0,
+            "exceptionDetails": {
+                "exceptionId": 1,
+                "text": "Uncaught",
+                "lineNumber": 3,
+                "columnNumber": 9,
+                "exception": {"type": "object", "subtype": "error", "description": "Error: boom"}
+            }
+        }),
+    );
+
+    let console = Wait::with_timeout(Duration::from_secs(5))
+        .until(|| Some(tab.console()).filter(|console| console.exceptions().count() == 1))
+        .unwrap();
+    assert!(console.has_errors());
+    let messages: Vec<_> = console.messages().map(|message| &message.text).collect();
+    assert_eq!(vec!["count: 2"], messages);
+    let exception = console.exceptions().next().unwrap();
+    assert_eq!("Error: boom", exception.text);
+    assert_eq!(3, exception.details.line_number);
+
+    tab.clear_console();
+    assert!(!tab.console().has_errors());
+}
+
+#[test]
+fn sync_tab_answers_dialogs() {
+    let server = MockServer::start().unwrap();
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    let target_id = tab.get_target_id().clone();
+    let open_dialog = |dialog_type: &str| {
+        server.emit_target_event(
+            &target_id,
+            "Page.javascriptDialogOpening",
+            json!({
+                "url": "http://example.com",
+                "message": "sure?",
+                "type": dialog_type,
+                "hasBrowserHandler": true,
+                "defaultPrompt": ""
+            }),
+        );
+    };
+    let answers = |count: usize| {
+        Wait::with_timeout(Duration::from_secs(5))
+            .until(|| {
+                Some(server.received_params("Page.handleJavaScriptDialog"))
+                    .filter(|answers| answers.len() == count)
+            })
+            .unwrap()
+    };
+
+    open_dialog("beforeunload");
+    assert_eq!(json!({"accept": true}), answers(1)[0]);
+
+    tab.set_dialog_policy(DialogPolicy::AcceptWithPromptText("Ferris".to_string()));
+    open_dialog("prompt");
+    assert_eq!(
+        json!({"accept": true, "promptText": "Ferris"}),
+        answers(2)[1]
+    );
+
+    tab.set_dialog_policy(DialogPolicy::Callback(Arc::new(|params| DialogResponse {
+        accept: params.dialog_type != DialogType::Confirm,
+        prompt_text: None,
+    })));
+    open_dialog("confirm");
+    assert_eq!(json!({"accept": false}), answers(3)[2]);
+
+    // a callback can change the policy without deadlocking the tab
+    let weak_tab = Arc::downgrade(&tab);
+    tab.set_dialog_policy(DialogPolicy::Callback(Arc::new(move |_| {
+        if let Some(tab) = weak_tab.upgrade() {
+            tab.set_dialog_policy(DialogPolicy::Dismiss);
+        }
+        DialogResponse {
+            accept: true,
+            prompt_text: None,
+        }
+    })));
+    open_dialog("alert");
+    assert_eq!(json!({"accept": true}), answers(4)[3]);
+    open_dialog("alert");
+    assert_eq!(json!({"accept": false}), answers(5)[4]);
+
+    tab.set_dialog_policy(DialogPolicy::Manual);
+    let (opened_tx, opened_rx) = mpsc::channel();
+    let _listener = tab.add_event_listener(move |event| {
+        if let Event::JavascriptDialogOpening(dialog_event) = event {
+            opened_tx.send(dialog_event.params.dialog_type).unwrap();
+        }
+    });
+    open_dialog("alert");
+    let dialog_type = opened_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(DialogType::Alert, dialog_type);
+    assert_eq!(
+        5,
+        server.received_params("Page.handleJavaScriptDialog").len()
+    );
+    tab.handle_java_script_dialog(false, None).unwrap();
+    assert_eq!(json!({"accept": false}), answers(6)[5]);
+}
+
+#[test]
+fn sync_tab_re_adds_scripts_on_new_document() {
+    let server = MockServer::start().unwrap();
+    let next_identifier = AtomicUsize::new(1);
+    server.respond_with("Page.addScriptToEvaluateOnNewDocument", move |_params| {
+        let identifier = next_identifier.fetch_add(1, Ordering::SeqCst);
+        Ok(json!({ "identifier": identifier.to_string() }))
+    });
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+
+    let hooks = tab
+        .add_script_on_new_document("window.hooked = true")
+        .unwrap();
+    let mock = tab
+        .add_script_on_new_document("window.fetch = mockFetch")
+        .unwrap();
+    assert_eq!(("1", "2"), (hooks.as_str(), mock.as_str()));
+    tab.remove_script_on_new_document(&mock).unwrap();
+
+    tab.reattach().unwrap();
+    assert_eq!(
+        vec![
+            json!({"source": "window.hooked = true"}),
+            json!({"source": "window.fetch = mockFetch"}),
+            json!({"source": "window.hooked = true"}),
+        ],
+        server.received_params("Page.addScriptToEvaluateOnNewDocument")
+    );
+    // the script re-added after re-attaching is removed under its new identifier
+    tab.remove_script_on_new_document(&hooks).unwrap();
+    assert_eq!(
+        vec![json!({"identifier": "2"}), json!({"identifier": "3"})],
+        server.received_params("Page.removeScriptToEvaluateOnNewDocument")
+    );
+}
+
+#[test]
+fn sync_tab_exposes_functions() {
+    let server = MockServer::start().unwrap();
+    server.respond_to(
+        "Page.addScriptToEvaluateOnNewDocument",
+        json!({"identifier": "1"}),
+    );
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    let target_id = tab.get_target_id().clone();
+
+    tab.expose_function("add", |args| {
+        let args: Vec<i64> = serde_json::from_value(args)?;
+        if args.is_empty() {
+            return Err(failure::err_msg("nothing to add"));
+        }
+        Ok(json!(args.iter().sum::<i64>()))
+    })
+    .unwrap();
+    assert_eq!(
+        vec![json!({"name": "__headless_chrome_add"})],
+        server.received_params("Runtime.addBinding")
+    );
+    let wrapper = &server.received_params("Page.addScriptToEvaluateOnNewDocument")[0]["source"];
+    assert!(wrapper
+        .as_str()
+        .unwrap()
+        .contains(r#"("add", "__headless_chrome_add")"#));
+
+    let call = |seq: u64, args: serde_json::Value| {
+        server.emit_target_event(
+            &target_id,
+            "Runtime.bindingCalled",
+            json!({
+                "name": "__headless_chrome_add",
+                "payload": json!({"seq": seq, "args": args}).to_string(),
+                "executionContextId": 7
+            }),
+        );
+        Wait::with_timeout(Duration::from_secs(5))
+            .until(|| {
+                server
+                    .received_params("Runtime.evaluate")
+                    .into_iter()
+                    .filter(|params| params["contextId"] == 7)
+                    .nth(seq as usize - 1)
+            })
+            .unwrap()["expression"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        r#"window.__headlessChromeCalls["add"](1, 5, null)"#,
+        call(1, json!([2, 3]))
+    );
+    assert_eq!(
+        r#"window.__headlessChromeCalls["add"](2, null, "nothing to add")"#,
+        call(2, json!([]))
+    );
+
+    tab.reattach().unwrap();
+    assert_eq!(2, server.received_params("Runtime.addBinding").len());
+}
+
+#[test]
+fn sync_tab_answers_paused_requests() {
+    let server = MockServer::start().unwrap();
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    let target_id = tab.get_target_id().clone();
+
+    let patterns = [RequestPattern {
+        url_pattern: Some("*/api/*".to_string()),
+        ..Default::default()
+    }];
+    tab.enable_fetch_interception(
+        &patterns,
+        Box::new(|_transport, _session_id, paused| {
+            let url = paused.request.url;
+            if url.ends_with("/fixture") {
+                FetchDecision::Fulfill(
+                    FulfilledResponse::new(201, "{}").header("Content-Type", "application/json"),
+                )
+            } else if url.ends_with("/tracking") {
+                FetchDecision::Fail(ErrorReason::BlockedByClient)
+            } else {
+                FetchDecision::Continue(RequestOverrides {
+                    url: Some(url.replace("/api/", "/api/v2/")),
+                    headers: Some(vec![HeaderEntry::new("X-Test", "1")]),
+                    post_data: Some(b"a=1".to_vec()),
+                    ..Default::default()
+                })
+            }
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        vec![json!({"patterns": [{"urlPattern": "*/api/*"}]})],
+        server.received_params("Fetch.enable")
+    );
+
+    let pause = |request_id: &str, path: &str| {
+        server.emit_target_event(
+            &target_id,
+            "Fetch.requestPaused",
+            json!({
+                "requestId": request_id,
+                "request": {
+                    "url": format!("http://example.com/api/{}", path),
+                    "method": "POST",
+                    "headers": {},
+                    "initialPriority": "High",
+                    "referrerPolicy": "no-referrer"
+                },
+                "frameId": "frame",
+                "resourceType": "Fetch"
+            }),
+        );
+    };
+    let answer = |method: &str| {
+        Wait::with_timeout(Duration::from_secs(5))
+            .until(|| server.received_params(method).pop())
+            .unwrap()
+    };
+
+    pause("interception-1", "users");
+    assert_eq!(
+        json!({
+            "requestId": "interception-1",
+            "url": "http://example.com/api/v2/users",
+            "postData": "YT0x",
+            "headers": [{"name": "X-Test", "value": "1"}]
+        }),
+        answer("Fetch.continueRequest")
+    );
+    pause("interception-2", "fixture");
+    assert_eq!(
+        json!({
+            "requestId": "interception-2",
+            "responseCode": 201,
+            "responseHeaders": [{"name": "Content-Type", "value": "application/json"}],
+            "body": "e30="
+        }),
+        answer("Fetch.fulfillRequest")
+    );
+    pause("interception-3", "tracking");
+    assert_eq!(
+        json!({"requestId": "interception-3", "errorReason": "BlockedByClient"}),
+        answer("Fetch.failRequest")
+    );
+
+    tab.reattach().unwrap();
+    assert_eq!(2, server.received_params("Fetch.enable").len());
+}
+
+#[test]
+fn sync_tab_rewrites_paused_responses() {
+    let server = MockServer::start().unwrap();
+    server.respond_with("Fetch.getResponseBody", |params| {
+        assert_eq!(json!({"requestId": "interception-1"}), *params);
+        Ok(json!({"body": base64::encode(r#"{"users":[]}"#), "base64Encoded": true}))
+    });
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+
+    let patterns = [RequestPattern {
+        request_stage: Some(RequestStage::Response),
+        ..Default::default()
+    }];
+    tab.enable_fetch_interception(
+        &patterns,
+        Box::new(|transport, session_id, paused| {
+            match interception::paused_response(&transport, session_id, &paused) {
+                Ok(Some(mut response)) => {
+                    let mut body: serde_json::Value =
+                        serde_json::from_slice(&response.body).unwrap();
+                    body["users"] = json!(["fixture"]);
+                    response.body = body.to_string().into_bytes();
+                    response.remove_header("content-security-policy");
+                    FetchDecision::Fulfill(response)
+                }
+                _ => FetchDecision::default(),
+            }
+        }),
+    )
+    .unwrap();
+
+    server.emit_target_event(
+        tab.get_target_id(),
+        "Fetch.requestPaused",
+        json!({
+            "requestId": "interception-1",
+            "request": {
+                "url": "http://example.com/api/users",
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "High",
+                "referrerPolicy": "no-referrer"
+            },
+            "frameId": "frame",
+            "resourceType": "XHR",
+            "responseStatusCode": 200,
+            "responseHeaders": [
+                {"name": "Content-Type", "value": "application/json"},
+                {"name": "Content-Security-Policy", "value": "default-src 'none'"},
+                {"name": "Content-Encoding", "value": "gzip"},
+                {"name": "Content-Length", "value": "40"}
+            ]
+        }),
+    );
+    let fulfilled = Wait::with_timeout(Duration::from_secs(5))
+        .until(|| server.received_params("Fetch.fulfillRequest").pop())
+        .unwrap();
+    assert_eq!(
+        json!({
+            "requestId": "interception-1",
+            "responseCode": 200,
+            "responseHeaders": [{"name": "Content-Type", "value": "application/json"}],
+            "body": base64::encode(r#"{"users":["fixture"]}"#)
+        }),
+        fulfilled
+    );
+}
+
+#[test]
+fn sync_tab_answers_auth_challenges() {
+    let server = MockServer::start().unwrap();
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    let target_id = tab.get_target_id().clone();
+
+    tab.set_credentials_provider(CredentialsProvider::Callback(Arc::new(|challenge| {
+        if challenge.realm == "admin" {
+            Some(Credentials::new("user", "secret"))
+        } else {
+            None
+        }
+    })))
+    .unwrap();
+    assert_eq!(
+        vec![json!({"patterns": [{}], "handleAuthRequests": true})],
+        server.received_params("Fetch.enable")
+    );
+
+    let challenge = |request_id: &str, realm: &str, answers: usize| {
+        server.emit_target_event(
+            &target_id,
+            "Fetch.authRequired",
+            json!({
+                "requestId": request_id,
+                "request": {
+                    "url": "http://example.com/private",
+                    "method": "GET",
+                    "headers": {},
+                    "initialPriority": "VeryHigh",
+                    "referrerPolicy": "no-referrer"
+                },
+                "frameId": "frame",
+                "resourceType": "Document",
+                "authChallenge": {
+                    "source": "Server",
+                    "origin": "http://example.com",
+                    "scheme": "basic",
+                    "realm": realm
+                }
+            }),
+        );
+        Wait::with_timeout(Duration::from_secs(5))
+            .until(|| {
+                server
+                    .received_params("Fetch.continueWithAuth")
+                    .get(answers - 1)
+                    .cloned()
+            })
+            .unwrap()
+    };
+
+    assert_eq!(
+        json!({
+            "requestId": "auth-1",
+            "authChallengeResponse": {
+                "response": "ProvideCredentials",
+                "username": "user",
+                "password": "secret"
+            }
+        }),
+        challenge("auth-1", "admin", 1)
+    );
+    assert_eq!(
+        json!({
+            "requestId": "auth-2",
+            "authChallengeResponse": {"response": "CancelAuth"}
+        }),
+        challenge("auth-2", "elsewhere", 2)
+    );
+
+    // a callback can change the provider without deadlocking the tab
+    let weak_tab = Arc::downgrade(&tab);
+    tab.set_credentials_provider(CredentialsProvider::Callback(Arc::new(move |_| {
+        if let Some(tab) = weak_tab.upgrade() {
+            tab.set_credentials_provider(CredentialsProvider::Static(Credentials::new(
+                "static", "secret",
+            )))
+            .unwrap();
+        }
+        None
+    })))
+    .unwrap();
+    assert_eq!(
+        "CancelAuth",
+        challenge("auth-3", "admin", 3)["authChallengeResponse"]["response"]
+    );
+    assert_eq!(
+        "static",
+        challenge("auth-4", "admin", 4)["authChallengeResponse"]["username"]
+    );
+    // the same request challenged again: the credentials were rejected
+    assert_eq!(
+        json!({
+            "requestId": "auth-4",
+            "authChallengeResponse": {"response": "CancelAuth"}
+        }),
+        challenge("auth-4", "admin", 5)
+    );
+
+    tab.set_credentials_provider(CredentialsProvider::BrowserDefault)
+        .unwrap();
+    assert_eq!(1, server.received_params("Fetch.disable").len());
+}
+
+/// A request that gets a response, one that gets redirected, and the one it's redirected to,
+/// which fails.
+fn emit_network_traffic(server: &MockServer, target_id: &str) {
+    let request = |url: &str| {
+        json!({
+            "url": url,
+            "method": "GET",
+            "headers": {"Cookie": "session=abc"},
+            "initialPriority": "High",
+            "referrerPolicy": "no-referrer"
+        })
+    };
+    server.emit_target_event(
+        target_id,
+        "Network.requestWillBeSent",
+        json!({
+            "requestId": "1.1",
+            "loaderId": "loader",
+            "documentURL": "http://example.com/",
+            "request": request("http://example.com/api?q=a%20b&x=1"),
+            "timestamp": 100.0,
+            "wallTime": 1_558_774_808.110_41,
+            "initiator": {"type": "other"},
+            "type": "XHR"
+        }),
+    );
+    server.emit_target_event(
+        target_id,
+        "Network.responseReceived",
+        json!({
+            "requestId": "1.1",
+            "loaderId": "loader",
+            "timestamp": 100.06,
+            "type": "XHR",
+            "response": {
+                "url": "http://example.com/api?q=a%20b&x=1",
+                "status": 200,
+                "statusText": "OK",
+                "headers": {"Content-Type": "text/plain", "Set-Cookie": "a=1; Path=/; HttpOnly"},
+                "mimeType": "text/plain",
+                "connectionReused": false,
+                "connectionId": 7,
+                "remoteIPAddress": "10.0.0.1",
+                "remotePort": 80,
+                "encodedDataLength": 100,
+                "protocol": "http/1.1",
+                "securityState": "insecure",
+                "timing": {
+                    "requestTime": 100.01,
+                    "proxyStart": -1, "proxyEnd": -1,
+                    "dnsStart": 1, "dnsEnd": 3,
+                    "connectStart": 3, "connectEnd": 8,
+                    "sslStart": -1, "sslEnd": -1,
+                    "workerStart": -1, "workerReady": -1,
+                    "sendStart": 9, "sendEnd": 10,
+                    "pushStart": 0, "pushEnd": 0,
+                    "receiveHeadersEnd": 50
+                }
+            }
+        }),
+    );
+    server.emit_target_event(
+        target_id,
+        "Network.dataReceived",
+        json!({"requestId": "1.1", "timestamp": 100.08, "dataLength": 5, "encodedDataLength": 5}),
+    );
+    server.emit_target_event(
+        target_id,
+        "Network.loadingFinished",
+        json!({"requestId": "1.1", "timestamp": 100.1, "encodedDataLength": 105}),
+    );
+
+    server.emit_target_event(
+        target_id,
+        "Network.requestWillBeSent",
+        json!({
+            "requestId": "2.1",
+            "loaderId": "loader",
+            "documentURL": "http://example.com/old",
+            "request": request("http://example.com/old"),
+            "timestamp": 101.0,
+            "wallTime": 1_558_774_809.0,
+            "initiator": {"type": "other"},
+            "type": "Document"
+        }),
+    );
+    server.emit_target_event(
+        target_id,
+        "Network.requestWillBeSent",
+        json!({
+            "requestId": "2.1",
+            "loaderId": "loader",
+            "documentURL": "http://example.com/new",
+            "request": request("http://example.com/new"),
+            "timestamp": 101.02,
+            "wallTime": 1_558_774_809.02,
+            "initiator": {"type": "other"},
+            "type": "Document",
+            "redirectResponse": {
+                "url": "http://example.com/old",
+                "status": 301,
+                "statusText": "Moved Permanently",
+                "headers": {"Location": "/new"},
+                "mimeType": "",
+                "connectionReused": true,
+                "connectionId": 7,
+                "encodedDataLength": 80,
+                "securityState": "insecure"
+            }
+        }),
+    );
+    server.emit_target_event(
+        target_id,
+        "Network.loadingFailed",
+        json!({
+            "requestId": "2.1",
+            "timestamp": 101.05,
+            "type": "Document",
+            "errorText": "net::ERR_CONNECTION_REFUSED"
+        }),
+    );
+}
+
+/// What `emit_network_traffic` should come out as, given that the bodies are recorded.
+fn assert_recorded_har(har: &Har) {
+    let approx = |expected: f64, actual: f64| {
+        assert!(
+            (expected - actual).abs() < 1e-6,
+            "{} != {}",
+            expected,
+            actual
+        );
+    };
+    assert_eq!("1.2", har.log.version);
+    let entries = &har.log.entries;
+    assert_eq!(3, entries.len());
+
+    let ok = &entries[0];
+    assert_eq!("2019-05-25T09:00:08.110Z", ok.started_date_time);
+    assert_eq!("http://example.com/api?q=a%20b&x=1", ok.request.url);
+    assert_eq!("HTTP/1.1", ok.request.http_version);
+    assert_eq!(
+        vec![
+            QueryParam {
+                name: "q".to_string(),
+                value: "a b".to_string()
+            },
+            QueryParam {
+                name: "x".to_string(),
+                value: "1".to_string()
+            },
+        ],
+        ok.request.query_string
+    );
+    assert_eq!("session", ok.request.cookies[0].name);
+    assert_eq!(200, ok.response.status);
+    assert_eq!(
+        Header {
+            name: "Content-Type".to_string(),
+            value: "text/plain".to_string()
+        },
+        ok.response.headers[0]
+    );
+    assert_eq!(Some(true), ok.response.cookies[0].http_only);
+    assert_eq!(Some("/".to_string()), ok.response.cookies[0].path);
+    assert_eq!(5, ok.response.content.size);
+    assert_eq!(Some("aGVsbG8=".to_string()), ok.response.content.text);
+    assert_eq!(Some("base64".to_string()), ok.response.content.encoding);
+    assert_eq!(5, ok.response.body_size);
+    assert_eq!(Some("10.0.0.1".to_string()), ok.server_ip_address);
+    assert_eq!(Some("7".to_string()), ok.connection);
+    approx(11.0, ok.timings.blocked);
+    approx(2.0, ok.timings.dns);
+    approx(5.0, ok.timings.connect);
+    approx(-1.0, ok.timings.ssl);
+    approx(1.0, ok.timings.send);
+    approx(40.0, ok.timings.wait);
+    approx(40.0, ok.timings.receive);
+    approx(99.0, ok.time);
+
+    let redirect = &entries[1];
+    assert_eq!(301, redirect.response.status);
+    assert_eq!("http://example.com/new", redirect.response.redirect_url);
+    assert_eq!(None, redirect.response.content.text);
+    approx(20.0, redirect.time);
+
+    let failed = &entries[2];
+    assert_eq!("http://example.com/new", failed.request.url);
+    assert_eq!(0, failed.response.status);
+    assert_eq!(
+        Some("net::ERR_CONNECTION_REFUSED".to_string()),
+        failed.response.error
+    );
+    approx(30.0, failed.time);
+
+    let json = serde_json::to_value(har).unwrap();
+    assert_eq!("10.0.0.1", json["log"]["entries"][0]["serverIPAddress"]);
+    assert_eq!(
+        "http://example.com/new",
+        json["log"]["entries"][1]["response"]["redirectURL"]
+    );
+    assert_eq!(
+        "net::ERR_CONNECTION_REFUSED",
+        json["log"]["entries"][2]["response"]["_error"]
+    );
+}
+
+#[test]
+fn sync_tab_records_har() {
+    let server = MockServer::start().unwrap();
+    server.respond_to(
+        "Network.getResponseBody",
+        json!({"body": "aGVsbG8=", "base64Encoded": true}),
+    );
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+
+    tab.start_har_recording(true).unwrap();
+    assert_eq!(1, server.received_params("Network.enable").len());
+    emit_network_traffic(&server, tab.get_target_id());
+
+    let har = Wait::with_timeout(Duration::from_secs(5))
+        .until(|| {
+            tab.har()
+                .filter(|har| har.log.entries.len() == 3)
+                .filter(|har| har.log.entries[2].response.error.is_some())
+        })
+        .unwrap();
+    assert_recorded_har(&har);
+    assert_eq!(
+        vec![json!({"requestId": "1.1"})],
+        server.received_params("Network.getResponseBody")
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("traffic.har");
+    tab.stop_har_recording().unwrap().save(&path).unwrap();
+    let saved: Har = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_recorded_har(&saved);
+    assert!(tab.har().is_none());
+}
+
+fn mock_cookies() -> serde_json::Value {
+    json!({"cookies": [
+        {"name": "sid", "value": "abc", "domain": ".example.com", "path": "/", "expires": 1893456000.0,
+         "size": 6, "httpOnly": true, "secure": true, "session": false, "sameSite": "Lax"},
+        {"name": "theme", "value": "dark", "domain": "app.example.com", "path": "/", "expires": -1.0,
+         "size": 9, "httpOnly": false, "secure": false, "session": true}
+    ]})
+}
+
+#[test]
+fn sync_tab_manages_cookies() {
+    let server = MockServer::start().unwrap();
+    server.respond_to("Network.getCookies", mock_cookies());
+    server.respond_to("Network.getAllCookies", mock_cookies());
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+
+    let cookies = tab.get_cookies().unwrap();
+    assert_eq!(2, cookies.len());
+    assert_eq!(Some(CookieSameSite::Lax), cookies[0].same_site);
+    assert!(cookies[1].session);
+    assert_eq!(cookies, tab.get_all_cookies().unwrap());
+    assert_eq!(
+        vec![json!({})],
+        server.received_params("Network.getCookies")
+    );
+
+    let params: Vec<CookieParam> = cookies.into_iter().map(CookieParam::from).collect();
+    tab.set_cookies(&params).unwrap();
+    assert_eq!(
+        vec![json!({"cookies": [
+            {"name": "sid", "value": "abc", "domain": ".example.com", "path": "/", "secure": true,
+             "httpOnly": true, "sameSite": "Lax", "expires": 1893456000.0},
+            {"name": "theme", "value": "dark", "url": "http://app.example.com/", "path": "/",
+             "secure": false, "httpOnly": false}
+        ]})],
+        server.received_params("Network.setCookies")
+    );
+
+    tab.delete_cookies(&[
+        DeleteCookies {
+            name: "sid",
+            url: Some("https://example.com/"),
+            ..Default::default()
+        },
+        DeleteCookies {
+            name: "theme",
+            domain: Some("app.example.com"),
+            ..Default::default()
+        },
+        DeleteCookies {
+            name: "theme",
+            ..Default::default()
+        },
+    ])
+    .unwrap()
+    .clear_browser_cookies()
+    .unwrap();
+    // only the cookie with neither a URL nor a domain gets the page's
+    assert_eq!(
+        vec![
+            json!({"name": "sid", "url": "https://example.com/"}),
+            json!({"name": "theme", "domain": "app.example.com"}),
+            json!({"name": "theme", "url": tab.get_url()}),
+        ],
+        server.received_params("Network.deleteCookies")
+    );
+    assert_eq!(
+        1,
+        server.received_params("Network.clearBrowserCookies").len()
+    );
+}
+
+#[test]
+fn sync_context_imports_and_exports_cookies() {
+    let server = MockServer::start().unwrap();
+    server.respond_to(
+        "Target.createBrowserContext",
+        json!({"browserContextId": "CONTEXT"}),
+    );
+    server.respond_to("Storage.getCookies", mock_cookies());
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    let context = browser.new_context().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    for file in &["cookies.json", "cookies.txt"] {
+        let path = dir.path().join(file);
+        context
+            .export_cookies(&path, CookieFormat::from_path(&path))
+            .unwrap();
+        assert_eq!(
+            2,
+            context
+                .import_cookies(&path, CookieFormat::from_path(&path))
+                .unwrap()
+        );
+    }
+    assert_eq!(
+        vec![json!({"browserContextId": "CONTEXT"}); 2],
+        server.received_params("Storage.getCookies")
+    );
+
+    // both formats bring back the same cookies (bar `sameSite`, which cookies.txt doesn't
+    // have), host-only ones being set by URL
+    let set = server.received_params("Storage.setCookies");
+    assert_eq!(2, set.len());
+    for params in &set {
+        assert_eq!("CONTEXT", params["browserContextId"]);
+        let cookies = &params["cookies"];
+        assert_eq!(".example.com", cookies[0]["domain"]);
+        assert_eq!(json!(true), cookies[0]["httpOnly"]);
+        assert_eq!(1_893_456_000.0, cookies[0]["expires"]);
+        assert_eq!("http://app.example.com/", cookies[1]["url"]);
+        assert!(cookies[1].get("domain").is_none());
+        assert!(cookies[1].get("expires").is_none());
+    }
+    assert_eq!("Lax", set[0]["cookies"][0]["sameSite"]);
+
+    context.clear_cookies().unwrap();
+    assert_eq!(
+        vec![json!({"browserContextId": "CONTEXT"})],
+        server.received_params("Storage.clearCookies")
+    );
+}
+
+#[test]
+fn refuses_unsupported_versions_when_required() {
+    let server = MockServer::start().unwrap();
+    server.respond_to(
+        "Browser.getVersion",
+        json!({
+            "protocolVersion": "1.3",
+            "product": "HeadlessChrome/65.0.3325.181",
+            "revision": "@mock",
+            "userAgent": "Mozilla/5.0",
+            "jsVersion": "6.5.254.41",
+        }),
+    );
+
+    let browser = Browser::connect(server.ws_url()).unwrap();
+    assert!(!browser.version().unwrap().at_least(70));
+
+    let err = Browser::connect_with_version_check(server.ws_url(), VersionCheck::Require)
+        .err()
+        .unwrap();
+    let incompatible = err.downcast::<IncompatibleChrome>().unwrap();
+    assert_eq!(Incompatibility::TooOld, incompatible.incompatibility);
+
+    let mut session = ChromeDebugSession::new(
+        ChromeBrowser::connect(&server.ws_url()).with_version_check(VersionCheck::Require),
+    );
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let err = runtime
+        .block_on(future::poll_fn(move || session.poll()))
+        .unwrap_err();
+    assert!(err.downcast::<IncompatibleChrome>().is_ok());
+}
+
+#[test]
+fn async_session_drives_mock_server() {
+    let server = MockServer::start().unwrap();
+    let mut session = ChromeDebugSession::new(ChromeBrowser::connect(&server.ws_url()));
+    let mut discover_sent = false;
+
+    let created_target_id = future::poll_fn(move || loop {
+        let (_, _, task) = match session.poll()? {
+            Async::Ready(Some(item)) => item,
+            Async::Ready(None) => return Err(failure::err_msg("session ended")),
+            Async::NotReady => return Ok(Async::NotReady),
+        };
+        match task {
+            TaskDescribe::ChromeConnected if !discover_sent => {
+                discover_sent = true;
+                assert_eq!(
+                    Some((75, 0, 3770, 142)),
+                    session.chrome_version().map(ChromeVersion::number)
+                );
+                session.send_message_direct(
+                    json!({"id": 1, "method": "Target.setDiscoverTargets", "params": {"discover": true}})
+                        .to_string(),
+                );
+            }
+            TaskDescribe::TargetEvent(TargetEvent::TargetCreated(event)) => {
+                return Ok(Async::Ready(event.into_target_info().target_id));
+            }
+            _ => {}
+        }
+    });
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let created_target_id: String = runtime
+        .block_on(created_target_id.map_err(|err: failure::Error| err))
+        .unwrap();
+    assert_eq!(server.target_ids()[0], created_target_id);
+}
+
+/// Connects a `DebugSession` to `server`, turns on target discovery and attaches to the page
+/// the server creates, then hands every response after that (starting with the page's
+/// `PageAttached`) to `step`, until it returns something.
+fn drive_attached_session<T, F>(server: &Arc<MockServer>, mut step: F) -> T
+where
+    T: Send + 'static,
+    F: FnMut(
+            &mut DebugSession,
+            &MockServer,
+            PageResponseWrapper,
+        ) -> Result<Option<T>, failure::Error>
+        + Send
+        + 'static,
+{
+    let mut session = DebugSession::new_default(ChromeBrowser::connect(&server.ws_url()));
+    let server = Arc::clone(server);
+
+    let done = future::poll_fn(move || loop {
+        let response = match session.poll()? {
+            Async::Ready(Some(response)) => response,
+            Async::Ready(None) => return Err(failure::err_msg("session ended")),
+            Async::NotReady => return Ok(Async::NotReady),
+        };
+        match response.page_response {
+            PageResponse::ChromeConnected => session.set_discover_targets(true),
+            PageResponse::ReceivedEvent(ReceivedEvent::PageCreated) => session
+                .find_tab_by_id_mut(response.target_id.as_ref())?
+                .attach_to_page(),
+            _ => {
+                if let Some(done) = step(&mut session, &server, response)? {
+                    return Ok(Async::Ready(done));
+                }
+            }
+        }
+    });
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime
+        .block_on(done.map_err(|err: failure::Error| err))
+        .unwrap()
+}
+
+#[test]
+fn async_session_passes_on_raw_events() {
+    let server = Arc::new(MockServer::start().unwrap());
+    let mut raw_events = vec![];
+
+    let raw_events = drive_attached_session(&server, move |_, server, response| {
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                let target_id = &target_info.target_id;
+                server.emit_target_event(
+                    target_id,
+                    "Custom.somethingHappened",
+                    json!({"id": "A1"}),
+                );
+                // a known event, but missing its frame
+                server.emit_target_event(target_id, "Page.frameNavigated", json!({}));
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::RawEvent(raw_event)) => {
+                assert!(response.target_id.is_some());
+                raw_events.push(raw_event);
+                if raw_events.len() == 2 {
+                    return Ok(Some(std::mem::take(&mut raw_events)));
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    });
+
+    assert_eq!("Custom.somethingHappened", raw_events[0].method);
+    assert_eq!(json!({"id": "A1"}), raw_events[0].params);
+    assert!(raw_events[0].session_id.is_some());
+    assert!(raw_events[0].decode_error.is_none());
+    assert_eq!("Page.frameNavigated", raw_events[1].method);
+    assert!(raw_events[1].decode_error.is_some());
+}
+
+#[test]
+fn async_tab_captures_console_and_exceptions() {
+    let server = Arc::new(MockServer::start().unwrap());
+    let mut received = vec![];
+
+    let received = drive_attached_session(&server, move |session, server, response| {
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                let target_id = &target_info.target_id;
+                server.emit_target_event(
+                    target_id,
+                    "Runtime.consoleAPICalled",
+                    json!({
+                        "type": "error",
+                        "args": [{"type": "string", "value": "failed"}],
+                        "executionContextId": 1,
+                        "timestamp": 1.0
+                    }),
+                );
+                server.emit_target_event(
+                    target_id,
+                    "Runtime.exceptionThrown",
+                    json!({
+                        "timestamp": 2.0,
+                        "exceptionDetails": {
+                            "exceptionId": 1,
+                            "text": "Uncaught",
+                            "lineNumber": 0,
+                            "columnNumber": 0
+                        }
+                    }),
+                );
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::ConsoleMessage(message)) => {
+                received.push(message.text);
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::UncaughtException(exception)) => {
+                received.push(exception.text);
+                let tab = session.find_tab_by_id_mut(response.target_id.as_ref())?;
+                assert!(tab.console.has_errors());
+                assert_eq!(1, tab.console.messages().count());
+                return Ok(Some(std::mem::take(&mut received)));
+            }
+            _ => {}
+        }
+        Ok(None)
+    });
+
+    assert_eq!(vec!["failed", "Uncaught"], received);
+}
+
+#[test]
+fn async_tab_answers_dialogs() {
+    let server = Arc::new(MockServer::start().unwrap());
+    let mut opened = None;
+
+    let (opened, answers) = drive_attached_session(&server, move |session, server, response| {
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                let tab = session.find_tab_by_id_mut(Some(&target_info.target_id))?;
+                tab.dialog_policy = DialogPolicy::Dismiss;
+                server.emit_target_event(
+                    &target_info.target_id,
+                    "Page.javascriptDialogOpening",
+                    json!({
+                        "url": "http://example.com",
+                        "message": "leave?",
+                        "type": "beforeunload",
+                        "hasBrowserHandler": false
+                    }),
+                );
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::JavascriptDialogOpening(params)) => {
+                opened = Some(params);
+            }
+            _ => {}
+        }
+        let answers = server.received_params("Page.handleJavaScriptDialog");
+        Ok(if answers.is_empty() {
+            None
+        } else {
+            Some((opened.take(), answers))
+        })
+    });
+
+    let opened = opened.expect("the dialog is reported before it's answered");
+    assert_eq!(DialogType::Beforeunload, opened.dialog_type);
+    assert_eq!("leave?", opened.message);
+    assert_eq!(vec![json!({"accept": false})], answers);
+}
+
+#[test]
+fn async_tab_re_adds_scripts_on_new_document() {
+    let server = Arc::new(MockServer::start().unwrap());
+    let next_identifier = AtomicUsize::new(1);
+    server.respond_with("Page.addScriptToEvaluateOnNewDocument", move |_params| {
+        let identifier = next_identifier.fetch_add(1, Ordering::SeqCst);
+        Ok(json!({ "identifier": identifier.to_string() }))
+    });
+    let mut attached = 0;
+    let mut identifier = None;
+
+    let removed = drive_attached_session(&server, move |session, server, response| {
+        let target_id = response.target_id.clone();
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(..)) => {
+                attached += 1;
+                if attached == 1 {
+                    session
+                        .find_tab_by_id_mut(target_id.as_ref())?
+                        .add_script_on_new_document("window.hooked = true", None);
+                }
+            }
+            PageResponse::MethodCallDone(MethodCallDone::AddScriptToEvaluateOnNewDocument(
+                task,
+            )) => {
+                identifier = task.task_result;
+                // attaching again gives the page a new session
+                session
+                    .find_tab_by_id_mut(target_id.as_ref())?
+                    .attach_to_page();
+            }
+            _ => {}
+        }
+        if let Some(first_identifier) = &identifier {
+            let added = server.received_params("Page.addScriptToEvaluateOnNewDocument");
+            if added.len() == 2 {
+                session
+                    .find_tab_by_id_mut(server.target_ids().first())?
+                    .remove_script_on_new_document(first_identifier);
+                identifier = None;
+            }
+        }
+        let removed = server.received_params("Page.removeScriptToEvaluateOnNewDocument");
+        Ok(if removed.is_empty() {
+            None
+        } else {
+            Some(removed)
+        })
+    });
+
+    assert_eq!(vec![json!({"identifier": "2"})], removed);
+    assert_eq!(
+        vec![json!({"source": "window.hooked = true"}); 2],
+        server.received_params("Page.addScriptToEvaluateOnNewDocument")
+    );
+}
+
+#[test]
+fn async_tab_exposes_functions() {
+    let server = Arc::new(MockServer::start().unwrap());
+    server.respond_to(
+        "Page.addScriptToEvaluateOnNewDocument",
+        json!({"identifier": "1"}),
+    );
+
+    let returned = drive_attached_session(&server, |session, server, response| {
+        if let PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) =
+            response.page_response
+        {
+            let tab = session.find_tab_by_id_mut(Some(&target_info.target_id))?;
+            tab.expose_function("greet", |args| Ok(json!(format!("hi {}", args[0]))));
+            server.emit_target_event(
+                &target_info.target_id,
+                "Runtime.bindingCalled",
+                json!({
+                    "name": "__headless_chrome_greet",
+                    "payload": r#"{"seq":1,"args":["Ferris"]}"#,
+                    "executionContextId": 3
+                }),
+            );
+        }
+        let returned: Vec<_> = server
+            .received_params("Runtime.evaluate")
+            .into_iter()
+            .filter(|params| params["contextId"] == 3)
+            .collect();
+        Ok(if returned.is_empty() {
+            None
+        } else {
+            Some(returned)
+        })
+    });
+
+    assert_eq!(
+        json!(r#"window.__headlessChromeCalls["greet"](1, "hi \"Ferris\"", null)"#),
+        returned[0]["expression"]
+    );
+    assert_eq!(
+        vec![json!({"name": "__headless_chrome_greet"})],
+        server.received_params("Runtime.addBinding")
+    );
+}
+
+#[test]
+fn async_tab_answers_paused_requests() {
+    let server = Arc::new(MockServer::start().unwrap());
+
+    let fulfilled = drive_attached_session(&server, |session, server, response| {
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                let tab = session.find_tab_by_id_mut(Some(&target_info.target_id))?;
+                tab.fetch_enable(vec![RequestPattern::default()]);
+                server.emit_target_event(
+                    &target_info.target_id,
+                    "Fetch.requestPaused",
+                    json!({
+                        "requestId": "interception-1",
+                        "request": {
+                            "url": "http://example.com/data.json",
+                            "method": "GET",
+                            "headers": {},
+                            "initialPriority": "High",
+                            "referrerPolicy": "no-referrer"
+                        },
+                        "frameId": "frame",
+                        "resourceType": "Prefetch"
+                    }),
+                );
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::RequestPaused(request_id)) => {
+                let tab = session.find_tab_by_id_mut(response.target_id.as_ref())?;
+                let paused = tab.request_paused[&request_id].get_raw_parameters();
+                assert_eq!(ResourceType::Other, paused.resource_type);
+                let body = format!("{{\"url\": {:?}}}", paused.request.url);
+                tab.answer_paused_request(
+                    &request_id,
+                    FetchDecision::Fulfill(FulfilledResponse::new(200, body)),
+                );
+                assert!(tab.request_paused.is_empty());
+            }
+            _ => {}
+        }
+        let fulfilled = server.received_params("Fetch.fulfillRequest");
+        Ok(if fulfilled.is_empty() {
+            None
+        } else {
+            Some(fulfilled)
+        })
+    });
+
+    assert_eq!(
+        vec![json!({
+            "requestId": "interception-1",
+            "responseCode": 200,
+            "responseHeaders": [],
+            "body": base64::encode(r#"{"url": "http://example.com/data.json"}"#)
+        })],
+        fulfilled
+    );
+    assert_eq!(
+        vec![json!({"patterns": [{}]})],
+        server.received_params("Fetch.enable")
+    );
+}
+
+#[test]
+fn async_tab_rewrites_paused_responses() {
+    let server = Arc::new(MockServer::start().unwrap());
+    server.respond_to(
+        "Fetch.getResponseBody",
+        json!({"body": "<p>staging</p>", "base64Encoded": false}),
+    );
+
+    let fulfilled = drive_attached_session(&server, |session, server, response| {
+        let target_id = response.target_id.clone();
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                let tab = session.find_tab_by_id_mut(Some(&target_info.target_id))?;
+                tab.fetch_enable(vec![RequestPattern {
+                    request_stage: Some(RequestStage::Response),
+                    ..Default::default()
+                }]);
+                server.emit_target_event(
+                    &target_info.target_id,
+                    "Fetch.requestPaused",
+                    json!({
+                        "requestId": "interception-1",
+                        "request": {
+                            "url": "http://example.com/",
+                            "method": "GET",
+                            "headers": {},
+                            "initialPriority": "VeryHigh",
+                            "referrerPolicy": "no-referrer"
+                        },
+                        "frameId": "frame",
+                        "resourceType": "Document",
+                        "responseStatusCode": 404,
+                        "responseHeaders": [{"name": "Content-Type", "value": "text/html"}]
+                    }),
+                );
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::RequestPaused(request_id)) => session
+                .find_tab_by_id_mut(target_id.as_ref())?
+                .get_paused_response_body(&request_id, None),
+            PageResponse::MethodCallDone(MethodCallDone::GetResponseBody(task)) => {
+                let tab = session.find_tab_by_id_mut(target_id.as_ref())?;
+                let paused = tab.request_paused[&task.request_id].get_raw_parameters();
+                let mut response = task.get_response(paused)?.unwrap();
+                response.status = 200;
+                response.set_header("content-type", "text/plain");
+                tab.answer_paused_request(&task.request_id, FetchDecision::Fulfill(response));
+            }
+            _ => {}
+        }
+        let fulfilled = server.received_params("Fetch.fulfillRequest");
+        Ok(if fulfilled.is_empty() {
+            None
+        } else {
+            Some(fulfilled)
+        })
+    });
+
+    assert_eq!(
+        vec![json!({
+            "requestId": "interception-1",
+            "responseCode": 200,
+            "responseHeaders": [{"name": "content-type", "value": "text/plain"}],
+            "body": base64::encode("<p>staging</p>")
+        })],
+        fulfilled
+    );
+}
+
+#[test]
+fn async_tab_answers_auth_challenges() {
+    let server = Arc::new(MockServer::start().unwrap());
+
+    let answered = drive_attached_session(&server, |session, server, response| {
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                let tab = session.find_tab_by_id_mut(Some(&target_info.target_id))?;
+                tab.credentials_provider =
+                    CredentialsProvider::Static(Credentials::new("user", "secret"));
+                tab.fetch_enable(vec![RequestPattern::default()]);
+                // the second time round, the credentials were rejected
+                for _ in 0..2 {
+                    server.emit_target_event(
+                        &target_info.target_id,
+                        "Fetch.authRequired",
+                        json!({
+                            "requestId": "auth-1",
+                            "request": {
+                                "url": "http://example.com/private",
+                                "method": "GET",
+                                "headers": {},
+                                "initialPriority": "VeryHigh",
+                                "referrerPolicy": "no-referrer"
+                            },
+                            "frameId": "frame",
+                            "resourceType": "Document",
+                            "authChallenge": {
+                                "origin": "http://example.com",
+                                "scheme": "basic",
+                                "realm": "admin"
+                            }
+                        }),
+                    );
+                }
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::AuthRequired(params)) => {
+                assert_eq!("admin", params.auth_challenge.realm);
+            }
+            _ => {}
+        }
+        let answered = server.received_params("Fetch.continueWithAuth");
+        Ok(if answered.len() < 2 {
+            None
+        } else {
+            Some(answered)
+        })
+    });
+
+    assert_eq!(
+        vec![
+            json!({
+                "requestId": "auth-1",
+                "authChallengeResponse": {
+                    "response": "ProvideCredentials",
+                    "username": "user",
+                    "password": "secret"
+                }
+            }),
+            json!({
+                "requestId": "auth-1",
+                "authChallengeResponse": {"response": "CancelAuth"}
+            })
+        ],
+        answered
+    );
+    assert_eq!(
+        vec![json!({"patterns": [{}], "handleAuthRequests": true})],
+        server.received_params("Fetch.enable")
+    );
+}
+
+#[test]
+fn async_tab_records_har() {
+    let server = Arc::new(MockServer::start().unwrap());
+    server.respond_to(
+        "Network.getResponseBody",
+        json!({"body": "aGVsbG8=", "base64Encoded": true}),
+    );
+    let mut got_body = false;
+    let mut failed = false;
+
+    let har = drive_attached_session(&server, move |session, server, response| {
+        match response.page_response {
+            PageResponse::ReceivedEvent(ReceivedEvent::PageAttached(target_info, _)) => {
+                let tab = session.find_tab_by_id_mut(Some(&target_info.target_id))?;
+                tab.start_har_recording(true);
+                emit_network_traffic(server, &target_info.target_id);
+            }
+            PageResponse::MethodCallDone(MethodCallDone::NetworkGetResponseBody(task)) => {
+                assert_eq!(b"hello".to_vec(), task.get_body()?);
+                got_body = true;
+            }
+            PageResponse::ReceivedEvent(ReceivedEvent::LoadingFailed(_)) => failed = true,
+            _ => {}
+        }
+        if got_body && failed {
+            let tab = session.find_tab_by_id_mut(response.target_id.as_ref())?;
+            return Ok(Some(tab.har_recorder.as_ref().unwrap().har()));
+        }
+        Ok(None)
+    });
+
+    assert_recorded_har(&har);
+    assert_eq!(1, server.received_params("Network.enable").len());
+}
+
+#[test]
+#[cfg(unix)]
+fn sync_calls_fail_with_chromes_exit() {
+    let server = MockServer::start().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let exit_file = dir.path().join("exit");
+    let chrome = fake_chrome(dir.path(), &server, &exit_file);
+
+    let browser = Browser::new(
+        LaunchOptionsBuilder::default()
+            .path(Some(chrome))
+            .build()
+            .unwrap(),
+    )
+    .unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    assert!(browser.exited().is_none());
+
+    std::fs::write(&exit_file, "").unwrap();
+    server.disconnect_clients();
+
+    let err = tab
+        .call_method(Evaluate {
+            expression: "1",
+            ..Default::default()
+        })
+        .unwrap_err();
+    let exited = err.downcast::<ChromeExited>().unwrap();
+    assert_eq!(Some(139), exited.status.code());
+    assert_eq!(
+        Some(&"Received signal 11 SEGV_MAPERR".to_string()),
+        exited.last_output.last()
+    );
+    assert!(browser.exited().is_some());
+}
+
+#[test]
+#[cfg(unix)]
+fn async_session_reports_lost_targets_and_chromes_exit() {
+    let server = Arc::new(MockServer::start().unwrap());
+    let dir = tempfile::tempdir().unwrap();
+    let exit_file = dir.path().join("exit");
+    let chrome = fake_chrome(dir.path(), &server, &exit_file);
+
+    let mut session = DebugSession::new(
+        LaunchOptionsBuilder::default()
+            .path(Some(chrome))
+            .build()
+            .unwrap(),
+    );
+    let mut lost = None;
+
+    let lost_and_exited = {
+        let server = Arc::clone(&server);
+        future::poll_fn(move || loop {
+            let response = match session.poll() {
+                Ok(Async::Ready(Some(response))) => response,
+                Ok(Async::Ready(None)) => return Err(failure::err_msg("session ended quietly")),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(err) => return Ok(Async::Ready((lost.take(), err))),
+            };
+            match response.page_response {
+                PageResponse::ChromeConnected => session.set_discover_targets(true),
+                PageResponse::ReceivedEvent(ReceivedEvent::PageCreated) => server.emit_event(
+                    "Target.targetCrashed",
+                    json!({"targetId": response.target_id, "status": "crashed", "errorCode": 139}),
+                ),
+                PageResponse::TargetLost(reason) => {
+                    let tab = session
+                        .find_tab_by_id_mut(response.target_id.as_ref())
+                        .unwrap();
+                    assert!(tab.ensure_not_lost().is_err());
+                    lost = Some(reason);
+                    std::fs::write(&exit_file, "").unwrap();
+                    server.disconnect_clients();
+                }
+                _ => {}
+            }
+        })
+    };
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let (lost, err) = runtime
+        .block_on(lost_and_exited.map_err(|err: failure::Error| err))
+        .unwrap();
+    assert_eq!(
+        Some(TargetLostReason::Crashed {
+            status: Some("crashed".to_string()),
+            error_code: Some(139),
+        }),
+        lost
+    );
+    assert_eq!(
+        Some(139),
+        err.downcast::<ChromeExited>().unwrap().status.code()
+    );
+}
@@ -0,0 +1,318 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use futures::future::{self, Future};
+use futures::{Async, Stream};
+use serde_json::{json, Value};
+
+use headless_chrome::browser::pool::{
+    BrowserPool, OnRelease, PoolExhausted, PoolOptionsBuilder, PoolStats,
+};
+use headless_chrome::browser_async::chrome_browser::ChromeBrowser;
+use headless_chrome::browser_async::page_message::{MethodCallDone, PageResponse};
+use headless_chrome::browser_async::{DebugSession, DebugSessionPool, PoolResponse};
+use headless_chrome::testing::MockServer;
+use headless_chrome::Browser;
+
+/// A pool whose browsers each connect to a mock server of their own, which are kept in
+/// `servers` in the order the browsers were launched.
+fn mock_pool(options: PoolOptionsBuilder) -> (BrowserPool, Arc<Mutex<Vec<MockServer>>>) {
+    slow_mock_pool(options, Duration::from_millis(0))
+}
+
+/// Like `mock_pool`, but launching a browser takes `launch_time`.
+fn slow_mock_pool(
+    options: PoolOptionsBuilder,
+    launch_time: Duration,
+) -> (BrowserPool, Arc<Mutex<Vec<MockServer>>>) {
+    let servers = Arc::new(Mutex::new(vec![]));
+    let pool = {
+        let servers = Arc::clone(&servers);
+        BrowserPool::new(options.build().unwrap(), move || {
+            thread::sleep(launch_time);
+            let server = MockServer::start()?;
+            let browser = Browser::connect(server.ws_url())?;
+            servers.lock().unwrap().push(server);
+            Ok(browser)
+        })
+    };
+    (pool, servers)
+}
+
+fn closed_targets(server: &MockServer) -> Vec<Value> {
+    server
+        .received_params("Target.closeTarget")
+        .into_iter()
+        .map(|params| params["targetId"].clone())
+        .collect()
+}
+
+#[test]
+fn leases_tabs_across_browsers_and_contexts() {
+    let (pool, servers) = mock_pool(
+        PoolOptionsBuilder::default()
+            .browsers(2)
+            .contexts_per_browser(2)
+            .max_leases(3)
+            .max_leases_per_browser(Some(3))
+            .lease_timeout(Duration::from_millis(200))
+            .clone(),
+    );
+
+    let first = pool.lease().unwrap();
+    let second = pool.lease().unwrap();
+    let third = pool.lease().unwrap();
+    assert_eq!(2, servers.lock().unwrap().len());
+    assert_eq!(
+        2,
+        servers.lock().unwrap()[0]
+            .received_params("Target.createBrowserContext")
+            .len()
+    );
+    assert_ne!(first.context_id(), third.context_id());
+    assert_eq!(
+        PoolStats {
+            browsers: 2,
+            retired_browsers: 0,
+            leased_tabs: 3,
+            idle_tabs: 0,
+        },
+        pool.stats()
+    );
+
+    let exhausted = pool.lease().err().unwrap();
+    assert!(exhausted.downcast::<PoolExhausted>().is_ok());
+
+    let first_id = first.get_target_id().clone();
+    drop(first);
+    assert_eq!(
+        vec![Value::String(first_id)],
+        closed_targets(&servers.lock().unwrap()[0])
+    );
+
+    // the first browser hands out its third tab, and gets retired for it
+    let fourth = pool.lease().unwrap();
+    assert_eq!(1, pool.stats().retired_browsers);
+    drop(third);
+    drop(fourth);
+    assert_eq!(
+        PoolStats {
+            browsers: 1,
+            retired_browsers: 0,
+            leased_tabs: 1,
+            idle_tabs: 0,
+        },
+        pool.stats()
+    );
+
+    let _fifth = pool.lease().unwrap();
+    assert_eq!(3, servers.lock().unwrap().len());
+    drop(second);
+}
+
+#[test]
+fn concurrent_leases_wait_for_the_browser_being_launched() {
+    let (pool, servers) = slow_mock_pool(
+        PoolOptionsBuilder::default()
+            .browsers(1)
+            .lease_timeout(Duration::from_secs(5))
+            .clone(),
+        Duration::from_millis(300),
+    );
+
+    let leasing: Vec<_> = (0..2)
+        .map(|_| {
+            let pool = pool.clone();
+            thread::spawn(move || pool.lease())
+        })
+        .collect();
+    let leases: Vec<_> = leasing
+        .into_iter()
+        .map(|leasing| leasing.join().unwrap().unwrap())
+        .collect();
+
+    assert_eq!(1, servers.lock().unwrap().len());
+    assert_eq!(
+        PoolStats {
+            browsers: 1,
+            retired_browsers: 0,
+            leased_tabs: 2,
+            idle_tabs: 0,
+        },
+        pool.stats()
+    );
+    assert_ne!(leases[0].get_target_id(), leases[1].get_target_id());
+}
+
+#[test]
+fn resets_released_tabs_and_retires_unhealthy_browsers() {
+    let (pool, servers) = mock_pool(
+        PoolOptionsBuilder::default()
+            .browsers(1)
+            .contexts_per_browser(0)
+            .on_release(OnRelease::ResetToBlank)
+            .clone(),
+    );
+
+    let lease = pool.lease().unwrap();
+    assert_eq!(None, lease.context_id());
+    let target_id = lease.get_target_id().clone();
+    drop(lease);
+    assert_eq!(1, pool.stats().idle_tabs);
+    assert!(closed_targets(&servers.lock().unwrap()[0]).is_empty());
+    // the tab is only reset once it's leased again
+    assert!(servers.lock().unwrap()[0]
+        .received_params("Page.navigate")
+        .is_empty());
+
+    let lease = pool.lease().unwrap();
+    assert_eq!(&target_id, lease.get_target_id());
+    assert_eq!(
+        vec![Value::String("about:blank".to_string())],
+        servers.lock().unwrap()[0]
+            .received_params("Page.navigate")
+            .into_iter()
+            .map(|params| params["url"].clone())
+            .collect::<Vec<_>>()
+    );
+    drop(lease);
+
+    assert_eq!(0, pool.health_check());
+    servers.lock().unwrap()[0].disconnect_clients();
+    assert_eq!(1, pool.health_check());
+    assert_eq!(PoolStats::default(), pool.stats());
+
+    let _lease = pool.lease().unwrap();
+    let servers = servers.lock().unwrap();
+    assert_eq!(2, servers.len());
+    assert_eq!(1, servers[1].received_params("Target.createTarget").len());
+}
+
+#[test]
+fn session_pool_leases_tabs_in_contexts_and_closes_them() {
+    let server = MockServer::start().unwrap();
+    let ws_url = server.ws_url();
+    let mut pool = DebugSessionPool::new(
+        PoolOptionsBuilder::default()
+            .browsers(1)
+            .contexts_per_browser(2)
+            .max_leases(2)
+            .build()
+            .unwrap(),
+        move || DebugSession::new_default(ChromeBrowser::connect(&ws_url)),
+    );
+
+    let mut first = pool.lease();
+    let second = pool.lease().unwrap();
+    assert!(pool.lease().is_none());
+    let mut contexts = vec![];
+    let mut closing = None;
+
+    let closed = future::poll_fn(move || loop {
+        let response = match pool.poll()? {
+            Async::Ready(Some(response)) => response,
+            Async::Ready(None) => return Err(failure::err_msg("pool ended")),
+            Async::NotReady => return Ok(Async::NotReady),
+        };
+        match response {
+            PoolResponse::TabReady(id) => {
+                let lease = match &first {
+                    Some(lease) if lease.id() == id => lease,
+                    _ => &second,
+                };
+                let tab = pool.tab_mut(lease).unwrap();
+                contexts.push(tab.target_info.browser_context_id.clone());
+                if contexts.len() == 2 {
+                    let tab = pool.tab_mut(first.as_ref().unwrap()).unwrap();
+                    closing = Some(tab.target_info.target_id.clone());
+                    first = None;
+                }
+            }
+            PoolResponse::Session(_, response) => {
+                if let PageResponse::MethodCallDone(MethodCallDone::PageClosed(_)) =
+                    response.page_response
+                {
+                    assert!(pool.tab_mut(&second).is_some());
+                    return Ok(Async::Ready((
+                        std::mem::take(&mut contexts),
+                        closing.take(),
+                        pool.leased(),
+                    )));
+                }
+            }
+        }
+    });
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let (contexts, closed, leased) = runtime
+        .block_on(closed.map_err(|err: failure::Error| err))
+        .unwrap();
+    assert_eq!(
+        2,
+        server.received_params("Target.createBrowserContext").len()
+    );
+    assert!(contexts.iter().all(Option::is_some));
+    assert_ne!(contexts[0], contexts[1]);
+    assert_eq!(
+        vec![Value::String(closed.unwrap())],
+        closed_targets(&server)
+    );
+    // the second lease is still out
+    assert_eq!(1, leased);
+}
+
+#[test]
+fn session_pool_resets_kept_tabs_for_the_next_lease() {
+    let server = Arc::new(MockServer::start().unwrap());
+    let ws_url = server.ws_url();
+    let mut pool = DebugSessionPool::new(
+        PoolOptionsBuilder::default()
+            .browsers(1)
+            .contexts_per_browser(0)
+            .max_leases(1)
+            .on_release(OnRelease::ResetToBlank)
+            .build()
+            .unwrap(),
+        move || DebugSession::new_default(ChromeBrowser::connect(&ws_url)),
+    );
+
+    let mut lease = pool.lease();
+    let mut target_ids = vec![];
+
+    let reset = {
+        let server = Arc::clone(&server);
+        future::poll_fn(move || loop {
+            let response = match pool.poll()? {
+                Async::Ready(Some(response)) => response,
+                Async::Ready(None) => return Err(failure::err_msg("pool ended")),
+                Async::NotReady => return Ok(Async::NotReady),
+            };
+            if let PoolResponse::TabReady(_) = response {
+                let tab = pool.tab_mut(lease.as_ref().unwrap()).unwrap();
+                target_ids.push(tab.target_info.target_id.clone());
+                if target_ids.len() == 1 {
+                    assert!(server.received_params("Page.navigate").is_empty());
+                    drop(lease.take());
+                    lease = pool.lease();
+                    assert!(lease.is_some());
+                }
+            }
+            if !server.received_params("Page.navigate").is_empty() {
+                return Ok(Async::Ready(std::mem::take(&mut target_ids)));
+            }
+        })
+    };
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let target_ids = runtime
+        .block_on(reset.map_err(|err: failure::Error| err))
+        .unwrap();
+    assert_eq!(2, target_ids.len());
+    assert_eq!(target_ids[0], target_ids[1]);
+    assert!(closed_targets(&server).is_empty());
+    assert_eq!(
+        json!({"url": "about:blank"}),
+        server.received_params("Page.navigate")[0]
+    );
+}
@@ -0,0 +1,86 @@
+use serde_json::{json, Value};
+
+use headless_chrome::browser::transport::recording::{Direction, Recorder};
+use headless_chrome::Browser;
+
+/// Records the exchange for a call made on a tab's (flattened) session.
+fn record_target_call(
+    recorder: &Recorder,
+    call_id: u64,
+    method: &str,
+    params: Value,
+    result: Value,
+) {
+    recorder.record(
+        Direction::Sent,
+        &json!({"id": call_id, "method": method, "params": params, "sessionId": "S1"}).to_string(),
+    );
+    recorder.record(
+        Direction::Received,
+        &json!({"id": call_id, "result": result, "sessionId": "S1"}).to_string(),
+    );
+}
+
+fn record_session(recorder: &Recorder) {
+    recorder.record(
+        Direction::Sent,
+        &json!({"id": 1, "method": "Target.setDiscoverTargets", "params": {"discover": true}})
+            .to_string(),
+    );
+    recorder.record(
+        Direction::Received,
+        &json!({
+            "method": "Target.targetCreated",
+            "params": {"targetInfo": {
+                "targetId": "T1", "type": "page", "title": "", "url": "about:blank", "attached": false
+            }}
+        })
+        .to_string(),
+    );
+    recorder.record(
+        Direction::Received,
+        &json!({"id": 1, "result": {}}).to_string(),
+    );
+
+    recorder.record(
+        Direction::Sent,
+        &json!({"id": 2, "method": "Target.attachToTarget", "params": {"targetId": "T1", "flatten": true}})
+            .to_string(),
+    );
+    recorder.record(
+        Direction::Received,
+        &json!({"id": 2, "result": {"sessionId": "S1"}}).to_string(),
+    );
+
+    record_target_call(recorder, 3, "Page.enable", json!({}), json!({}));
+    record_target_call(
+        recorder,
+        4,
+        "Page.setLifecycleEventsEnabled",
+        json!({"enabled": true}),
+        json!({}),
+    );
+    record_target_call(
+        recorder,
+        5,
+        "Page.navigate",
+        json!({"url": "https://example.com/"}),
+        json!({"frameId": "F1", "loaderId": "L1"}),
+    );
+}
+
+#[test]
+fn replays_a_recorded_session() {
+    let dir = tempfile::tempdir().unwrap();
+    let recording_path = dir.path().join("session.jsonl");
+    record_session(&Recorder::create(&recording_path).unwrap());
+
+    let browser = Browser::replay(&recording_path).unwrap();
+    let tab = browser.wait_for_initial_tab().unwrap();
+    assert_eq!("about:blank", tab.get_url());
+
+    tab.navigate_to("https://example.com/").unwrap();
+
+    // nothing in the recording for this one, so it fails right away
+    assert!(tab.reload(false, None).is_err());
+}